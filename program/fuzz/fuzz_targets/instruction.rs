@@ -0,0 +1,30 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use main::instruction::AppInstruction;
+use main::processor::Processor;
+use main::test_utils::{run_handler, take_logs, FakeAccount};
+use solana_program::pubkey::Pubkey;
+
+//
+// Any instruction data over any number of accounts, batch counts included,
+// fails with an error rather than a panic
+//
+fuzz_target!(|input: (Vec<u8>, u8)| {
+  let (data, tail) = input;
+  if let Ok((payload, _)) = AppInstruction::split_client_version(&data) {
+    if let Ok(ix) = AppInstruction::unpack(payload) {
+      assert_eq!(ix.pack().len(), ix.packed_len());
+      assert_eq!(AppInstruction::unpack(&ix.pack()).unwrap(), ix);
+    }
+  }
+  let program_id = Pubkey::new_from_array([1; 32]);
+  let mut accounts: Vec<FakeAccount> = (0..tail)
+    .map(|index| FakeAccount::new(Pubkey::new_from_array([index; 32]), program_id, vec![]))
+    .collect();
+  let _ = run_handler(
+    |program_id, accounts| Processor::process(program_id, accounts, &data),
+    &program_id,
+    &mut accounts,
+  );
+  let _ = take_logs();
+});
@@ -0,0 +1,101 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use main::helper::{
+  pattern::Pattern,
+  units::{ScaledAmount, ScaledComp, ScaledDebt, Shares, TokenAmount},
+};
+use main::MAX_PRECISION_EXP;
+
+//
+// The patterns never panic and keep their bounds at any precision
+//
+fuzz_target!(
+  |input: (u64, u128, u64, u64, i128, u64, u8, u64, i64, i64)| {
+    let (reward, floor, total_shares, shares, compensation, delay, exp, amount, start, now) = input;
+    let precision = 10u128.pow((exp % (MAX_PRECISION_EXP + 1)) as u32);
+    let floor = ScaledAmount(floor);
+    let total_shares = Shares(total_shares);
+    let compensation = ScaledComp(compensation);
+    let added = Shares(shares.min(total_shares.0));
+
+    // Newcomers never forfeit more than a whole period
+    if let (Some(forfeit), Some(emission)) = (
+      Pattern::estimate_forfeit(reward, floor, total_shares, added, precision),
+      Pattern::estimate_emission(reward, floor, total_shares, 1, precision),
+    ) {
+      assert!(forfeit <= emission);
+    }
+
+    // More shares accrue at least as much
+    let acc = ScaledAmount(floor.0);
+    if let (Some(less), Some(more)) = (
+      Pattern::accrue(added, acc, precision),
+      Pattern::accrue(total_shares, acc, precision),
+    ) {
+      assert!(less <= more);
+      if let Some((yeild, debt)) =
+        Pattern::settle_accumulated(total_shares, added, less, acc, precision)
+      {
+        assert_eq!(debt, less);
+        assert_eq!(yeild.0 as u128, more.0 - less.0);
+      }
+    }
+
+    // Staking then unstaking the same shares at the same delay restores the compensation
+    let next_total_shares = Shares(total_shares.0.saturating_add(shares));
+    if let Some((_, debt, staked)) = Pattern::fully_stake(
+      Shares(shares),
+      ScaledDebt(0),
+      compensation,
+      delay,
+      reward,
+      floor,
+      total_shares,
+      next_total_shares,
+      precision,
+    ) {
+      if let Some((_, _, unstaked)) = Pattern::fully_unstake(
+        Shares(shares),
+        debt,
+        staked,
+        delay,
+        reward,
+        floor,
+        next_total_shares,
+        total_shares,
+        precision,
+      ) {
+        let expected = if total_shares.0 == 0 {
+          ScaledComp(0)
+        } else {
+          compensation
+        };
+        assert_eq!(unstaked, expected);
+      }
+    }
+
+    // Vesting unlocks at most the amount, merged schedules end by the later end
+    let duration = delay;
+    if let Some(unlocked) = Pattern::unlock_vesting(amount, start, duration, now) {
+      assert!(unlocked <= amount);
+    }
+    let remaining = (now as u64) % (duration.saturating_add(1));
+    if let Some(merged) = Pattern::merge_vesting(amount, remaining, shares, duration) {
+      assert!(merged <= remaining.max(duration));
+    }
+    if let Some(clamped) = Pattern::clamp_yield(TokenAmount(amount), delay, reward) {
+      assert!(clamped.0 <= amount);
+    }
+
+    // Rescaling up then back down is exact
+    if precision <= u64::MAX as u128 {
+      let precision = precision as u64;
+      if let Some(up) = Pattern::rescale_compensation(compensation, 1, precision) {
+        assert_eq!(
+          Pattern::rescale_compensation(up, precision, 1),
+          Some(compensation)
+        );
+      }
+    }
+  }
+);
@@ -0,0 +1,228 @@
+use crate::context::account_specs;
+use crate::error::AppError;
+use crate::helper::params::PoolParams;
+use crate::instruction::AppInstruction;
+use crate::schema::stake_pool::StakePool;
+use solana_program::{instruction::Instruction, pubkey::Pubkey, system_program, sysvar};
+use std::collections::BTreeSet;
+
+///
+/// Largest serialized legacy transaction (the packet data size)
+///
+pub const MAX_LEGACY_TRANSACTION_SIZE: usize = 1232;
+
+///
+/// Length of a compact-u16 as serialized in transactions
+///
+pub fn compact_len(value: usize) -> usize {
+  match value {
+    0..=0x7f => 1,
+    0x80..=0x3fff => 2,
+    _ => 3,
+  }
+}
+
+///
+/// Bytes an instruction adds to a legacy transaction sharing none of its keys
+/// Optional trailing accounts are not counted
+///
+pub fn estimated_instruction_size(ix: &AppInstruction) -> usize {
+  let specs = account_specs(ix);
+  let data_len = ix.packed_len();
+  // Program id index, account indices and data
+  let compiled = 1 + compact_len(specs.len()) + specs.len() + compact_len(data_len) + data_len;
+  // Account keys, the program id included, and signatures
+  let keys = 32 * (specs.len() + 1);
+  let signatures = 64 * specs.iter().filter(|spec| spec.is_signer()).count();
+  compiled + keys + signatures
+}
+
+///
+/// Serialized size of a legacy transaction made of the instructions
+/// A fee payer is counted when no account signs
+///
+pub fn legacy_transaction_size(ixs: &[Instruction]) -> usize {
+  let mut keys = BTreeSet::new();
+  let mut signers = BTreeSet::new();
+  for ix in ixs {
+    keys.insert(ix.program_id);
+    for meta in &ix.accounts {
+      keys.insert(meta.pubkey);
+      if meta.is_signer {
+        signers.insert(meta.pubkey);
+      }
+    }
+  }
+  let num_signatures = signers.len().max(1);
+  let num_keys = keys.len() + num_signatures - signers.len();
+  let compiled: usize = ixs
+    .iter()
+    .map(|ix| {
+      1 + compact_len(ix.accounts.len())
+        + ix.accounts.len()
+        + compact_len(ix.data.len())
+        + ix.data.len()
+    })
+    .sum();
+  // Signatures, header, keys, recent blockhash and instructions
+  compact_len(num_signatures)
+    + 64 * num_signatures
+    + 3
+    + compact_len(num_keys)
+    + 32 * num_keys
+    + 32
+    + compact_len(ixs.len())
+    + compiled
+}
+
+pub fn fits_in_legacy_transaction(ixs: &[Instruction]) -> bool {
+  legacy_transaction_size(ixs) <= MAX_LEGACY_TRANSACTION_SIZE
+}
+
+///
+/// Accounts every instruction of a pool shares, for an address lookup table
+/// Signers and per-user accounts cannot be looked up and are left out
+///
+pub fn lookup_table_accounts(
+  program_id: &Pubkey,
+  stake_pool_acc: &Pubkey,
+  stake_pool_data: &StakePool,
+  splt_program: &Pubkey,
+  splata_program: &Pubkey,
+) -> Vec<Pubkey> {
+  vec![
+    *program_id,
+    *stake_pool_acc,
+    stake_pool_data.mint_share,
+    stake_pool_data.mint_token,
+    stake_pool_data.treasury_token,
+    stake_pool_data.mint_sen,
+    stake_pool_data.treasury_sen,
+    stake_pool_data.treasurer,
+    *splt_program,
+    *splata_program,
+    system_program::id(),
+    sysvar::rent::id(),
+  ]
+}
+
+///
+/// InitializeStakePool of valid parameters only, as the program would accept them
+///
+pub fn initialize_stake_pool(
+  params: &PoolParams,
+  genesis_timestamp: i64,
+  allow_pre_stake: bool,
+  pool_seed: Option<[u8; 32]>,
+) -> Result<AppInstruction, AppError> {
+  params.validate()?;
+  Ok(AppInstruction::InitializeStakePool {
+    reward: params.reward,
+    period: params.period,
+    genesis_timestamp,
+    allow_pre_stake,
+    pool_seed,
+    precision_exp: params.precision_exp,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::context::StakeContext;
+  use crate::interfaces::isplata::ISPLATA;
+  use crate::test_utils::{metas, splata_program_id, splt_program_id};
+  use solana_program::message::Message;
+  use std::str::FromStr;
+
+  fn stake_instruction(owner: Pubkey) -> (AppInstruction, Instruction) {
+    let ix = AppInstruction::Stake {
+      amount: 1000,
+      reference: Some(7),
+      idempotency_key: None,
+    };
+    let mut keys: Vec<Pubkey> = StakeContext::SPECS
+      .iter()
+      .map(|_| Pubkey::new_unique())
+      .collect();
+    keys[0] = owner;
+    let instruction = Instruction {
+      program_id: Pubkey::new_unique(),
+      accounts: metas(StakeContext::SPECS, &keys),
+      data: ix.pack(),
+    };
+    (ix, instruction)
+  }
+
+  // SetComputeUnitLimit of the compute budget program
+  fn compute_budget_instruction() -> Instruction {
+    let mut data = vec![2];
+    data.extend_from_slice(&200_000u32.to_le_bytes());
+    Instruction {
+      program_id: Pubkey::from_str("ComputeBudget111111111111111111111111111111").unwrap(),
+      accounts: vec![],
+      data,
+    }
+  }
+
+  // Signatures then the message, as a legacy transaction is serialized
+  fn serialized_size(ixs: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(ixs, Some(payer));
+    let signatures = message.header.num_required_signatures as usize;
+    compact_len(signatures) + 64 * signatures + message.serialize().len()
+  }
+
+  #[test]
+  fn test_instruction_estimate_is_its_share_of_a_transaction() {
+    let owner = Pubkey::new_unique();
+    let (ix, instruction) = stake_instruction(owner);
+    // Signature count, header, key count, blockhash and instruction count
+    let envelope = 1 + 3 + 1 + 32 + 1;
+    assert_eq!(
+      estimated_instruction_size(&ix) + envelope,
+      serialized_size(&[instruction], &owner)
+    );
+  }
+
+  #[test]
+  fn test_transaction_size_matches_the_serialized_message() {
+    let owner = Pubkey::new_unique();
+    let (_, stake) = stake_instruction(owner);
+    let create = ISPLATA::initialize_account(
+      owner,
+      stake.accounts[5].pubkey,
+      owner,
+      Pubkey::new_unique(),
+      system_program::id(),
+      splt_program_id(),
+      sysvar::rent::id(),
+      splata_program_id(),
+    )
+    .unwrap();
+    let ixs = [compute_budget_instruction(), create, stake];
+    let size = legacy_transaction_size(&ixs);
+    assert_eq!(size, serialized_size(&ixs, &owner));
+    // One signature and 19 distinct keys, the compute budget takes no account
+    // and 5 bytes of data, the creation 7 accounts and the stake 12
+    let stake_data = AppInstruction::Stake {
+      amount: 1000,
+      reference: Some(7),
+      idempotency_key: None,
+    }
+    .packed_len();
+    let compiled = (1 + 1 + 1 + 5) + (1 + 1 + 7 + 1) + (1 + 1 + 12 + 1 + stake_data);
+    assert_eq!(size, 1 + 64 + 3 + 1 + 32 * 19 + 32 + 1 + compiled);
+    assert!(fits_in_legacy_transaction(&ixs));
+  }
+
+  #[test]
+  fn test_oversized_transactions_do_not_fit() {
+    let owner = Pubkey::new_unique();
+    let ixs: Vec<Instruction> = (0..4).map(|_| stake_instruction(owner).1).collect();
+    let size = legacy_transaction_size(&ixs);
+    assert_eq!(size, serialized_size(&ixs, &owner));
+    assert!(size > MAX_LEGACY_TRANSACTION_SIZE);
+    assert!(!fits_in_legacy_transaction(&ixs));
+    assert!(fits_in_legacy_transaction(&ixs[..2]));
+  }
+}
@@ -0,0 +1,582 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::{
+  account::Account,
+  contribution::Contribution,
+  council::SecurityCouncil,
+  debt::Debt,
+  distribution::DistributionConfig,
+  mint::Mint,
+  registry::{MintWhitelist, Registry, RegistryPage},
+  stake_pool::StakePool,
+};
+use solana_program::{
+  account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+  program_pack::Pack, pubkey::Pubkey,
+};
+
+/// The account must sign the transaction
+pub const SIGNER: u8 = 1;
+/// The account is written by the instruction
+pub const WRITABLE: u8 = 2;
+/// The account must be owned by this program
+pub const PROGRAM: u8 = 4;
+
+///
+/// Account spec
+/// Name and role of an account at a given position of an instruction
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AccountSpec {
+  pub name: &'static str,
+  pub flags: u8,
+  pub len: usize, // Expected data length, zero means unchecked
+}
+
+impl AccountSpec {
+  pub fn is_signer(&self) -> bool {
+    self.flags & SIGNER != 0
+  }
+  pub fn is_writable(&self) -> bool {
+    self.flags & WRITABLE != 0
+  }
+  pub fn is_program(&self) -> bool {
+    self.flags & PROGRAM != 0
+  }
+  // Name, signer and writable flags as clients build the account metas
+  pub fn meta(&self) -> (&'static str, bool, bool) {
+    (self.name, self.is_signer(), self.is_writable())
+  }
+  // Owner, data length and writability expected from the role
+  pub fn check(&self, program_id: &Pubkey, acc: &AccountInfo) -> ProgramResult {
+    let result = if self.is_program() && acc.owner != program_id {
+      Err(AppError::IncorrectProgramId.into())
+    } else if self.is_program() && self.len != 0 && acc.data_len() < self.len {
+      // The runtime cannot grow an account created by a former layout
+      Err(AppError::LegacyLayout.into())
+    } else if self.len != 0 && acc.data_len() != self.len {
+      Err(ProgramError::InvalidAccountData)
+    } else if self.is_writable() && !acc.is_writable {
+      Err(AppError::ReadonlyAccount.into())
+    } else {
+      Ok(())
+    };
+    result.map_err(|e| self.reject(e))
+  }
+  // Name the role that failed
+  pub fn reject(&self, e: ProgramError) -> ProgramError {
+    msg!(
+      "Expected role={} flags={} len={}",
+      self.name,
+      self.flags,
+      self.len
+    );
+    e
+  }
+}
+
+///
+/// Declare the accounts of an instruction in order with their roles,
+/// optionally followed by `=> LEN` for the expected data length.
+/// The generated `load` checks the account count, then the ownership,
+/// data length and writability of every role, then the signers.
+///
+#[macro_export]
+macro_rules! context {
+  (@len) => { 0 };
+  (@len $len:expr) => { $len };
+  ($name:ident { $($field:ident: $flags:expr $(=> $len:expr)?),* $(,)? }) => {
+    pub struct $name<'a, 'b> {
+      $(pub $field: &'a $crate::context::__private::AccountInfo<'b>,)*
+    }
+
+    impl<'a, 'b> $name<'a, 'b> {
+      pub const SPECS: &'static [$crate::context::AccountSpec] = &[
+        $($crate::context::AccountSpec {
+          name: stringify!($field),
+          flags: $flags,
+          len: $crate::context!(@len $($len)?),
+        },)*
+      ];
+
+      pub fn load(
+        program_id: &$crate::context::__private::Pubkey,
+        accounts: &'a [$crate::context::__private::AccountInfo<'b>],
+      ) -> Result<Self, $crate::context::__private::ProgramError> {
+        if accounts.len() < Self::SPECS.len() {
+          return Err($crate::context::__private::ProgramError::NotEnoughAccountKeys);
+        }
+        let accounts_iter = &mut accounts.iter();
+        let context = Self {
+          $($field: $crate::context::__private::next_account_info(accounts_iter)?,)*
+        };
+        let loaded = [$(context.$field,)*];
+        for (acc, spec) in loaded.iter().zip(Self::SPECS) {
+          spec.check(program_id, acc)?;
+        }
+        for (acc, spec) in loaded.iter().zip(Self::SPECS) {
+          if spec.is_signer() {
+            $crate::processor::Processor::is_signer(&[acc]).map_err(|e| spec.reject(e))?;
+          }
+        }
+        Ok(context)
+      }
+    }
+  };
+}
+
+// Paths the context macro expands to, so that it also works outside the crate
+#[doc(hidden)]
+pub mod __private {
+  pub use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+  };
+}
+
+// The stake pool account is allocated by the instruction itself,
+// so its ownership is checked in the processor.
+// Optionally followed by the registry, its current page and its mint whitelist,
+// a registry always comes with the other two
+context!(InitializeStakePoolContext {
+  payer: SIGNER | WRITABLE,
+  owner: 0,
+  stake_pool_acc: WRITABLE, // Signs unless derived from a pool seed
+  mint_share_acc: SIGNER | WRITABLE,
+  proof_acc: 0, // program_id xor treasurer xor stake_pool_id
+  mint_token_acc: 0 => Mint::LEN,
+  treasury_token_acc: WRITABLE,
+  mint_sen_acc: 0 => Mint::LEN,
+  treasury_sen_acc: WRITABLE,
+  treasurer: 0,
+  system_program: 0,
+  splt_program: 0,
+  sysvar_rent_acc: 0,
+  splata_program: 0,
+});
+
+context!(InitializeAccountsContext {
+  payer: SIGNER | WRITABLE,
+  owner: 0,
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  mint_share_acc: 0 => Mint::LEN,
+  mint_sen_acc: 0 => Mint::LEN,
+  reward_acc: WRITABLE,
+  share_acc: WRITABLE,
+  debt_acc: WRITABLE,
+  system_program: 0,
+  splt_program: 0,
+  sysvar_rent_acc: 0,
+  splata_program: 0,
+});
+
+// Optionally followed by the payer, system program, rent sysvar and
+// associated token program to re-create a closed share account
+context!(StakeContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  mint_share_acc: WRITABLE => Mint::LEN,
+  src_acc: WRITABLE => Account::LEN,
+  treasury_token_acc: WRITABLE => Account::LEN,
+  share_acc: WRITABLE,
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  dst_sen_acc: WRITABLE => Account::LEN,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+  mint_token_acc: 0 => Mint::LEN,
+});
+
+context!(UnstakeContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  mint_share_acc: WRITABLE => Mint::LEN,
+  dst_acc: WRITABLE => Account::LEN,
+  treasury_token_acc: WRITABLE => Account::LEN,
+  share_acc: WRITABLE => Account::LEN,
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  dst_sen_acc: WRITABLE => Account::LEN,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+// A keeper may harvest without the owner to the owner's default SEN account.
+// Followed by the memo program when the memo flag is set
+context!(HarvestContext {
+  owner: 0, // Signs unless harvesting to the default SEN account
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  mint_share_acc: 0 => Mint::LEN,
+  share_acc: 0 => Account::LEN,
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  dst_sen_acc: WRITABLE => Account::LEN, // Any account of the reward mint
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+// Shared by the owner-only instructions that only touch the stake pool
+context!(StakePoolOwnerContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+});
+
+context!(SeedContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  src_sen_acc: WRITABLE => Account::LEN,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  splt_program: 0,
+});
+
+context!(UnseedContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  dst_sen_acc: WRITABLE => Account::LEN,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+context!(TransferStakePoolOwnershipContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  new_owner: 0,
+});
+
+context!(CloseDebtContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  share_acc: 0, // Must be closed already
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  dst_acc: WRITABLE,
+});
+
+context!(CloseStakePoolContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  dst_acc: WRITABLE,
+});
+
+context!(GetStoredDebtContext { debt_acc: PROGRAM => Debt::LEN });
+
+// Optionally followed by the cranker and its SEN account to receive the crank tip
+context!(CrankSeedContext {
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  src_sen_acc: WRITABLE => Account::LEN, // Delegated to the treasurer
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+context!(InitializeContributionContext {
+  payer: SIGNER | WRITABLE,
+  owner: 0,
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  contribution_acc: WRITABLE,
+  system_program: 0,
+  sysvar_rent_acc: 0,
+});
+
+context!(WithdrawSeedContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  contribution_acc: PROGRAM | WRITABLE => Contribution::LEN,
+  dst_sen_acc: WRITABLE => Account::LEN,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+context!(RepairShareMintContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  mint_share_acc: WRITABLE => Mint::LEN,
+  authority: SIGNER, // Current mint authority of the share mint
+  council: SIGNER,
+  council_acc: PROGRAM => SecurityCouncil::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+context!(InitializeSecurityCouncilContext {
+  payer: SIGNER | WRITABLE,
+  council: SIGNER,
+  council_acc: WRITABLE,
+  system_program: 0,
+  sysvar_rent_acc: 0,
+});
+
+context!(InitializeRegistryContext {
+  payer: SIGNER | WRITABLE,
+  authority: 0,
+  registry_acc: WRITABLE,
+  system_program: 0,
+  sysvar_rent_acc: 0,
+});
+
+context!(RegisterExistingPoolContext {
+  authority: SIGNER, // Registry authority
+  payer: SIGNER | WRITABLE,
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  registry_acc: PROGRAM | WRITABLE => Registry::LEN,
+  registry_page_acc: WRITABLE,
+  system_program: 0,
+  sysvar_rent_acc: 0,
+});
+
+context!(DelistPoolContext {
+  authority: SIGNER, // Pool owner or registry authority
+  stake_pool_acc: 0, // May have been closed already
+  registry_acc: PROGRAM => Registry::LEN,
+  registry_page_acc: PROGRAM | WRITABLE => RegistryPage::LEN,
+});
+
+context!(GetRewardMintInfoContext {
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  treasury_sen_acc: 0 => Account::LEN,
+  mint_sen_acc: 0 => Mint::LEN,
+});
+
+context!(SetUnseedDestinationContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  unseed_destination: 0, // The system program id lifts the restriction
+});
+
+context!(GetEffectiveRewardContext {
+  stake_pool_acc: PROGRAM => StakePool::LEN
+});
+
+// The owner is both the pool owner and the staker whose yield is reseeded
+context!(HarvestAndSeedContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  mint_share_acc: 0 => Mint::LEN,
+  share_acc: 0 => Account::LEN,
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  treasury_sen_acc: 0 => Account::LEN,
+});
+
+// Optionally followed by the cranker, its SEN account, the SEN treasury,
+// the treasurer and the token program to receive the crank tip
+context!(UpdatePoolContext {
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+});
+
+// Followed by the accounts of InitializeStakePool
+context!(CloneStakePoolContext {
+  template_acc: PROGRAM => StakePool::LEN,
+});
+
+// Followed by exactly count pairs of stake pool and new owner
+context!(TransferOwnershipBatchContext { owner: SIGNER });
+
+context!(GetClaimableContext {
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  share_acc: 0 => Account::LEN,
+  debt_acc: PROGRAM => Debt::LEN,
+});
+
+context!(AllowMintContext {
+  authority: SIGNER, // Registry authority
+  payer: SIGNER | WRITABLE,
+  registry_acc: PROGRAM => Registry::LEN,
+  mint_whitelist_acc: WRITABLE,
+  mint_token_acc: 0 => Mint::LEN,
+  system_program: 0,
+  sysvar_rent_acc: 0,
+});
+
+context!(DisallowMintContext {
+  authority: SIGNER, // Registry authority
+  registry_acc: PROGRAM => Registry::LEN,
+  mint_whitelist_acc: PROGRAM | WRITABLE => MintWhitelist::LEN,
+  mint_token_acc: 0 => Mint::LEN,
+});
+
+context!(GetPoolTimingContext {
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+});
+
+// Same accounts as Unstake, the owner also receives the rents
+context!(ExitPoolContext {
+  owner: SIGNER | WRITABLE,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  mint_share_acc: WRITABLE => Mint::LEN,
+  dst_acc: WRITABLE => Account::LEN,
+  treasury_token_acc: WRITABLE => Account::LEN,
+  share_acc: WRITABLE, // May have been closed already
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  dst_sen_acc: WRITABLE => Account::LEN,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+context!(GetRunwaySeedContext {
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  treasury_sen_acc: 0 => Account::LEN,
+});
+
+// The default key as the SEN account clears the default
+context!(SetDefaultSenAccountContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  sen_acc: 0,
+});
+
+context!(ClaimVestedContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  dst_sen_acc: WRITABLE => Account::LEN,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+// The debt account may not exist yet, so it is checked in the processor
+context!(IsAccountInitializedContext {
+  owner: 0,
+  stake_pool_acc: PROGRAM => StakePool::LEN,
+  debt_acc: 0,
+});
+
+context!(ClearWithdrawOnlyContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  treasury_sen_acc: 0 => Account::LEN,
+});
+
+// Permissionless, the caller is paid a share of the reclaimed rent
+context!(GarbageCollectPoolContext {
+  caller: SIGNER | WRITABLE,
+  owner: WRITABLE, // Recorded pool owner, receives the rest of the rent
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  mint_share_acc: WRITABLE => Mint::LEN,
+  treasury_token_acc: WRITABLE => Account::LEN,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+// The accounts of Harvest to a destination that may not exist yet,
+// then what creates it as the owner's associated SEN account
+context!(HarvestWithCreateContext {
+  owner: 0, // Signs unless harvesting to the default SEN account
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  mint_share_acc: 0 => Mint::LEN,
+  share_acc: 0 => Account::LEN,
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  dst_sen_acc: WRITABLE,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+  payer: SIGNER | WRITABLE,
+  mint_sen_acc: 0 => Mint::LEN,
+  system_program: 0,
+  sysvar_rent_acc: 0,
+  splata_program: 0,
+});
+
+// Followed by exactly count child stake pools
+context!(SetDistributionContext {
+  authority: SIGNER,
+  payer: SIGNER | WRITABLE,
+  config_acc: WRITABLE,
+  system_program: 0,
+  sysvar_rent_acc: 0,
+});
+
+// Permissionless, followed by exactly count pairs of child stake pool and its treasury SEN
+context!(DistributeContext {
+  config_acc: PROGRAM => DistributionConfig::LEN,
+  funding_acc: WRITABLE => Account::LEN,
+  distributor: 0, // Delegate of the funding account
+  splt_program: 0,
+});
+
+// The accounts of Harvest to an empty, temporary wrapped SOL account of the owner,
+// closed to the owner once paid
+context!(HarvestSolContext {
+  owner: SIGNER | WRITABLE,
+  stake_pool_acc: PROGRAM | WRITABLE => StakePool::LEN,
+  mint_share_acc: 0 => Mint::LEN,
+  share_acc: 0 => Account::LEN,
+  debt_acc: PROGRAM | WRITABLE => Debt::LEN,
+  dst_sen_acc: WRITABLE => Account::LEN,
+  treasury_sen_acc: WRITABLE => Account::LEN,
+  treasurer: 0,
+  splt_program: 0,
+});
+
+///
+/// Fixed accounts of every instruction in order, the source of truth for client metas.
+/// Optional trailing accounts are documented at their context.
+///
+pub fn account_specs(instruction: &AppInstruction) -> &'static [AccountSpec] {
+  match instruction {
+    AppInstruction::InitializeStakePool { .. } => InitializeStakePoolContext::SPECS,
+    AppInstruction::InitializeAccounts { .. } => InitializeAccountsContext::SPECS,
+    AppInstruction::Stake { .. } => StakeContext::SPECS,
+    AppInstruction::Unstake { .. } => UnstakeContext::SPECS,
+    AppInstruction::Harvest { .. } => HarvestContext::SPECS,
+    AppInstruction::FreezeStakePool { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::ThawStakePool { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::Seed { .. } => SeedContext::SPECS,
+    AppInstruction::Unseed { .. } => UnseedContext::SPECS,
+    AppInstruction::TransferStakePoolOwnership { .. } => TransferStakePoolOwnershipContext::SPECS,
+    AppInstruction::CloseDebt { .. } => CloseDebtContext::SPECS,
+    AppInstruction::CloseStakePool { .. } => CloseStakePoolContext::SPECS,
+    AppInstruction::RescaleCompensation { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::GetStoredDebt { .. } => GetStoredDebtContext::SPECS,
+    AppInstruction::CrankSeed { .. } => CrankSeedContext::SPECS,
+    AppInstruction::UpdateCrankSeed { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::InitializeContribution { .. } => InitializeContributionContext::SPECS,
+    AppInstruction::WithdrawSeed { .. } => WithdrawSeedContext::SPECS,
+    AppInstruction::RepairShareMint { .. } => RepairShareMintContext::SPECS,
+    AppInstruction::InitializeRegistry { .. } => InitializeRegistryContext::SPECS,
+    AppInstruction::RegisterExistingPool { .. } => RegisterExistingPoolContext::SPECS,
+    AppInstruction::DelistPool { .. } => DelistPoolContext::SPECS,
+    AppInstruction::SetRewardFloor { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::SetUnseedLimit { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::GetRewardMintInfo { .. } => GetRewardMintInfoContext::SPECS,
+    AppInstruction::SetUnseedDestination { .. } => SetUnseedDestinationContext::SPECS,
+    AppInstruction::SetHarvestInterval { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::GetEffectiveReward { .. } => GetEffectiveRewardContext::SPECS,
+    AppInstruction::HarvestAndSeed { .. } => HarvestAndSeedContext::SPECS,
+    AppInstruction::UpdatePool { .. } => UpdatePoolContext::SPECS,
+    AppInstruction::MigrateStakePool { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::CloneStakePool { .. } => CloneStakePoolContext::SPECS,
+    AppInstruction::TransferOwnershipBatch { .. } => TransferOwnershipBatchContext::SPECS,
+    AppInstruction::GetClaimable { .. } => GetClaimableContext::SPECS,
+    AppInstruction::AllowMint { .. } => AllowMintContext::SPECS,
+    AppInstruction::DisallowMint { .. } => DisallowMintContext::SPECS,
+    AppInstruction::GetPoolTiming { .. } => GetPoolTimingContext::SPECS,
+    AppInstruction::ExitPool { .. } => ExitPoolContext::SPECS,
+    AppInstruction::SetActivation { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::SetMaxAccrualPeriods { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::GetRunwaySeed { .. } => GetRunwaySeedContext::SPECS,
+    AppInstruction::SetCrankTip { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::SetDefaultSenAccount { .. } => SetDefaultSenAccountContext::SPECS,
+    AppInstruction::SetMinClientVersion { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::SetVestingDuration { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::ClaimVested { .. } => ClaimVestedContext::SPECS,
+    // Only exactly count pairs of stake pool and treasury token
+    AppInstruction::GetTvlBatch { .. } => &[],
+    AppInstruction::IsAccountInitialized { .. } => IsAccountInitializedContext::SPECS,
+    AppInstruction::ClearWithdrawOnly { .. } => ClearWithdrawOnlyContext::SPECS,
+    AppInstruction::ShiftGenesis { .. } => StakePoolOwnerContext::SPECS,
+    AppInstruction::GarbageCollectPool { .. } => GarbageCollectPoolContext::SPECS,
+    AppInstruction::HarvestWithCreate { .. } => HarvestWithCreateContext::SPECS,
+    // Takes no account
+    AppInstruction::GetLayout { .. } => &[],
+    AppInstruction::SetDistribution { .. } => SetDistributionContext::SPECS,
+    AppInstruction::Distribute { .. } => DistributeContext::SPECS,
+    AppInstruction::HarvestSol { .. } => HarvestSolContext::SPECS,
+    AppInstruction::InitializeSecurityCouncil { .. } => InitializeSecurityCouncilContext::SPECS,
+  }
+}
@@ -11,6 +11,7 @@ use thiserror::Error;
 pub use solana_program::program_error::PrintProgramError as PrintAppError;
 
 /// Errors that may be returned by the app program.
+/// Codes are part of the interface, append new variants at the end.
 #[derive(Clone, Debug, Eq, Error, DeriveFromPrimitive, PartialEq)]
 pub enum AppError {
   #[error("Invalid instruction")]
@@ -35,6 +36,148 @@ pub enum AppError {
   InvalidMint,
   #[error("Exceed limit")]
   ExceedLimit,
+  #[error("Pool not frozen")]
+  UnfrozenPool,
+  #[error("Treasury SEN unmatched")]
+  UnmatchedTreasurySen,
+  #[error("Account too small")]
+  AccountTooSmall,
+  #[error("Not rent exempt")]
+  NotRentExempt,
+  #[error("Locked")]
+  Locked,
+  #[error("Decimals unmatched")]
+  DecimalMismatch,
+  #[error("Corrupted share mint")]
+  CorruptedShareMint,
+  #[error("Unseed limit exceeded")]
+  UnseedLimitExceeded,
+  #[error("Insufficient shares")]
+  InsufficientShares,
+  #[error("Unauthorized destination")]
+  UnauthorizedDestination,
+  #[error("Corrupted pool")]
+  CorruptedPool,
+  #[error("Invalid genesis timestamp")]
+  InvalidGenesis,
+  #[error("Pool not launched")]
+  NotLaunched,
+  #[error("Invalid pool version")]
+  InvalidVersion,
+  #[error("Share account missing, initialize the accounts again")]
+  ShareAccountMissing,
+  #[error("Lamports not conserved")]
+  UnbalancedLamports,
+  #[error("Mint not allowed")]
+  MintNotAllowed,
+  #[error("Account not writable")]
+  ReadonlyAccount,
+  #[error("Pool not active")]
+  PoolNotActive,
+  #[error("Stake pool key cannot derive a treasurer")]
+  InvalidPoolSeed,
+  #[error("Inconsistent state")]
+  InconsistentState,
+  #[error("Invariant violation")]
+  InvariantViolation,
+  #[error("Client too old")]
+  ClientTooOld,
+  #[error("Treasury is not the associated account of the treasurer")]
+  UnmatchedTreasury,
+  #[error("Vesting not fully claimed")]
+  VestingPending,
+  #[error("Invalid mint authority")]
+  InvalidMintAuthority,
+  #[error("Batch accounts unmatched")]
+  BatchAccountsMismatch,
+  #[error("Invalid precision exponent")]
+  InvalidPrecision,
+  #[error("The pool only allows withdrawals")]
+  WithdrawOnly,
+  #[error("Shares are not fully backed")]
+  BackingInvariantViolation,
+  #[error("The pool still holds shares or funds")]
+  PoolNotEmpty,
+  #[error("Destination is not the associated account of the owner")]
+  UnmatchedAssociatedAccount,
+  #[error("Idempotency key already used")]
+  DuplicateIdempotencyKey,
+  #[error("Invalid period")]
+  InvalidPeriod,
+  #[error("Weights must be nonzero and sum to 10000")]
+  InvalidWeights,
+  #[error("Account of a legacy layout, close it on the former program")]
+  LegacyLayout,
+  #[error("Harvested yield vests on this pool")]
+  VestingEnabled,
+  #[error("Temporary account must be empty")]
+  NonEmptyTemporaryAccount,
+  #[error("Transferred amount unmatched")]
+  TransferAmountMismatch,
+}
+
+impl AppError {
+  /// Map a custom program error code back to its variant
+  pub fn from_code(code: u32) -> Option<AppError> {
+    AppError::from_u32(code)
+  }
+  /// Human readable message of the variant
+  pub fn message(&self) -> &'static str {
+    match self {
+      AppError::InvalidInstruction => "Invalid instruction",
+      AppError::InvalidOwner => "Invalid owner",
+      AppError::IncorrectProgramId => "Incorrect program id",
+      AppError::ConstructorOnce => "Already constructed",
+      AppError::Overflow => "Operation overflowed",
+      AppError::UnmatchedPool => "Pool unmatched",
+      AppError::FrozenPool => "Pool frozen",
+      AppError::ZeroValue => "Zero value",
+      AppError::InsufficientFunds => "Insufficient funds",
+      AppError::InvalidMint => "Invalid mint",
+      AppError::ExceedLimit => "Exceed limit",
+      AppError::UnfrozenPool => "Pool not frozen",
+      AppError::UnmatchedTreasurySen => "Treasury SEN unmatched",
+      AppError::AccountTooSmall => "Account too small",
+      AppError::NotRentExempt => "Not rent exempt",
+      AppError::Locked => "Locked",
+      AppError::DecimalMismatch => "Decimals unmatched",
+      AppError::CorruptedShareMint => "Corrupted share mint",
+      AppError::UnseedLimitExceeded => "Unseed limit exceeded",
+      AppError::InsufficientShares => "Insufficient shares",
+      AppError::UnauthorizedDestination => "Unauthorized destination",
+      AppError::CorruptedPool => "Corrupted pool",
+      AppError::InvalidGenesis => "Invalid genesis timestamp",
+      AppError::NotLaunched => "Pool not launched",
+      AppError::InvalidVersion => "Invalid pool version",
+      AppError::ShareAccountMissing => "Share account missing, initialize the accounts again",
+      AppError::UnbalancedLamports => "Lamports not conserved",
+      AppError::MintNotAllowed => "Mint not allowed",
+      AppError::ReadonlyAccount => "Account not writable",
+      AppError::PoolNotActive => "Pool not active",
+      AppError::InvalidPoolSeed => "Stake pool key cannot derive a treasurer",
+      AppError::InconsistentState => "Inconsistent state",
+      AppError::InvariantViolation => "Invariant violation",
+      AppError::ClientTooOld => "Client too old",
+      AppError::UnmatchedTreasury => "Treasury is not the associated account of the treasurer",
+      AppError::VestingPending => "Vesting not fully claimed",
+      AppError::InvalidMintAuthority => "Invalid mint authority",
+      AppError::BatchAccountsMismatch => "Batch accounts unmatched",
+      AppError::InvalidPrecision => "Invalid precision exponent",
+      AppError::WithdrawOnly => "The pool only allows withdrawals",
+      AppError::BackingInvariantViolation => "Shares are not fully backed",
+      AppError::PoolNotEmpty => "The pool still holds shares or funds",
+      AppError::UnmatchedAssociatedAccount => {
+        "Destination is not the associated account of the owner"
+      }
+      AppError::DuplicateIdempotencyKey => "Idempotency key already used",
+      AppError::InvalidPeriod => "Invalid period",
+      AppError::InvalidWeights => "Weights must be nonzero and sum to 10000",
+      AppError::LegacyLayout => "Account of a legacy layout, close it on the former program",
+      AppError::VestingEnabled => "Harvested yield vests on this pool",
+      AppError::NonEmptyTemporaryAccount => "Temporary account must be empty",
+      AppError::TransferAmountMismatch => "Transferred amount unmatched",
+    }
+  }
 }
 
 impl From<AppError> for ProgramError {
@@ -54,18 +197,83 @@ impl PrintProgramError for AppError {
   where
     E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
   {
-    match self {
-      AppError::InvalidInstruction => msg!("Error: Invalid instruction"),
-      AppError::InvalidOwner => msg!("Error: Invalid owner"),
-      AppError::IncorrectProgramId => msg!("Error: Incorrect program id"),
-      AppError::ConstructorOnce => msg!("Error: Already constructed"),
-      AppError::Overflow => msg!("Error: Operation overflowed"),
-      AppError::UnmatchedPool => msg!("Error: Pool unmatched"),
-      AppError::FrozenPool => msg!("Error: Pool frozen"),
-      AppError::ZeroValue => msg!("Error: Zero value"),
-      AppError::InsufficientFunds => msg!("Error: Insufficient funds"),
-      AppError::InvalidMint => msg!("Error: Invalid mint"),
-      AppError::ExceedLimit => msg!("Error: Exceed limit"),
+    msg!("Error: {}", self.message());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Committed codes, a variant may only be appended
+  const CODES: &[(u32, &str)] = &[
+    (0, "InvalidInstruction"),
+    (1, "InvalidOwner"),
+    (2, "IncorrectProgramId"),
+    (3, "ConstructorOnce"),
+    (4, "Overflow"),
+    (5, "UnmatchedPool"),
+    (6, "FrozenPool"),
+    (7, "ZeroValue"),
+    (8, "InsufficientFunds"),
+    (9, "InvalidMint"),
+    (10, "ExceedLimit"),
+    (11, "UnfrozenPool"),
+    (12, "UnmatchedTreasurySen"),
+    (13, "AccountTooSmall"),
+    (14, "NotRentExempt"),
+    (15, "Locked"),
+    (16, "DecimalMismatch"),
+    (17, "CorruptedShareMint"),
+    (18, "UnseedLimitExceeded"),
+    (19, "InsufficientShares"),
+    (20, "UnauthorizedDestination"),
+    (21, "CorruptedPool"),
+    (22, "InvalidGenesis"),
+    (23, "NotLaunched"),
+    (24, "InvalidVersion"),
+    (25, "ShareAccountMissing"),
+    (26, "UnbalancedLamports"),
+    (27, "MintNotAllowed"),
+    (28, "ReadonlyAccount"),
+    (29, "PoolNotActive"),
+    (30, "InvalidPoolSeed"),
+    (31, "InconsistentState"),
+    (32, "InvariantViolation"),
+    (33, "ClientTooOld"),
+    (34, "UnmatchedTreasury"),
+    (35, "VestingPending"),
+    (36, "InvalidMintAuthority"),
+    (37, "BatchAccountsMismatch"),
+    (38, "InvalidPrecision"),
+    (39, "WithdrawOnly"),
+    (40, "BackingInvariantViolation"),
+    (41, "PoolNotEmpty"),
+    (42, "UnmatchedAssociatedAccount"),
+    (43, "DuplicateIdempotencyKey"),
+    (44, "InvalidPeriod"),
+    (45, "InvalidWeights"),
+    (46, "LegacyLayout"),
+    (47, "VestingEnabled"),
+    (48, "NonEmptyTemporaryAccount"),
+    (49, "TransferAmountMismatch"),
+  ];
+
+  #[test]
+  fn test_codes_match_the_snapshot() {
+    for (code, name) in CODES.iter() {
+      let error = AppError::from_code(*code).unwrap();
+      assert_eq!(format!("{:?}", error), *name);
+      assert_eq!(ProgramError::from(error), ProgramError::Custom(*code));
+    }
+    assert_eq!(AppError::from_code(CODES.len() as u32), None);
+  }
+
+  #[test]
+  fn test_messages_match_the_display() {
+    for (code, _) in CODES.iter() {
+      let error = AppError::from_code(*code).unwrap();
+      assert_eq!(error.message(), error.to_string());
     }
   }
 }
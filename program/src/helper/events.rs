@@ -0,0 +1,60 @@
+use solana_program::{msg, pubkey::Pubkey};
+use std::fmt::Display;
+
+///
+/// Events
+/// Single stable log lines for indexers, fields are never reordered
+///
+pub fn emit_pool_created(
+  stake_pool: &Pubkey,
+  owner: &Pubkey,
+  mint_token: &Pubkey,
+  mint_share: &Pubkey,
+  reward: u64,
+  period: u64,
+) {
+  msg!(
+    "POOL_CREATED:stake_pool={},owner={},mint_token={},mint_share={},reward={},period={}",
+    stake_pool,
+    owner,
+    mint_token,
+    mint_share,
+    reward,
+    period
+  );
+}
+
+///
+/// Owner action on a pool, count is the pool's admin_action_count after the action
+///
+pub fn emit_admin_action(
+  action: &str,
+  stake_pool: &Pubkey,
+  signer: &Pubkey,
+  old: impl Display,
+  new: impl Display,
+  count: u64,
+  timestamp: i64,
+) {
+  msg!(
+    "ADMIN_ACTION:action={},stake_pool={},signer={},old={},new={},count={},timestamp={}",
+    action,
+    stake_pool,
+    signer,
+    old,
+    new,
+    count,
+    timestamp
+  );
+}
+
+///
+/// Yield paid by a harvest, so one SEN account can tell its pools apart
+///
+pub fn emit_harvested(stake_pool: &Pubkey, debt: &Pubkey, dst_sen: &Pubkey, amount: u64) {
+  // A format string with four values would select the sol_log_64 form of msg!
+  msg!(&format!(
+    "HARVESTED:stake_pool={},debt={},dst_sen={},amount={}",
+    stake_pool, debt, dst_sen, amount
+  ));
+}
@@ -1,2 +1,5 @@
+pub mod events;
+pub mod params;
 pub mod pattern;
 pub mod pubutil;
+pub mod units;
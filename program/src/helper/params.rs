@@ -0,0 +1,51 @@
+use crate::error::AppError;
+use crate::{MAX_PRECISION_EXP, PRECISION_EXP};
+
+///
+/// Longest period, a year of seconds
+///
+pub const MAX_PERIOD: u64 = 31536000;
+
+///
+/// Parameters of a stake pool
+/// Clients and the program validate them with the same function
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoolParams {
+  pub reward: u64,
+  pub period: u64,
+  pub precision_exp: u8,
+}
+
+impl PoolParams {
+  pub fn new(reward: u64, period: u64) -> Self {
+    PoolParams {
+      reward,
+      period,
+      precision_exp: PRECISION_EXP,
+    }
+  }
+
+  pub fn precision_exp(mut self, precision_exp: u8) -> Self {
+    self.precision_exp = precision_exp;
+    self
+  }
+
+  pub fn validate(&self) -> Result<(), AppError> {
+    if self.reward == 0 {
+      return Err(AppError::ZeroValue);
+    }
+    if self.period == 0 || self.period > MAX_PERIOD {
+      return Err(AppError::InvalidPeriod);
+    }
+    // A year of emission must fit the treasury amount
+    if self.reward.checked_mul(MAX_PERIOD / self.period).is_none() {
+      return Err(AppError::ExceedLimit);
+    }
+    // Wider precisions keep rewards over huge total shares from flooring to zero
+    if self.precision_exp < PRECISION_EXP || self.precision_exp > MAX_PRECISION_EXP {
+      return Err(AppError::InvalidPrecision);
+    }
+    Ok(())
+  }
+}
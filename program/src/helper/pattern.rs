@@ -1,8 +1,7 @@
+use crate::helper::units::{ScaledAmount, ScaledComp, ScaledDebt, Shares, TokenAmount};
 use num_bigint::BigInt;
 use num_traits::ToPrimitive;
 
-const PRECISION: u64 = 1000000000000000000; // 10^18
-
 ///
 /// Farming Patterns
 /// Every actions can be generalized by the following pattern of flow
@@ -11,118 +10,745 @@ const PRECISION: u64 = 1000000000000000000; // 10^18
 pub struct Pattern {}
 
 impl Pattern {
-  pub fn fractionalize_reward(reward: u64, total_shares: u64) -> Option<(BigInt, BigInt)> {
-    let precision = BigInt::from(PRECISION);
-    if total_shares == 0 {
+  pub fn fractionalize_reward(
+    reward: u64,
+    floor: ScaledAmount,
+    total_shares: Shares,
+    precision: u128,
+  ) -> Option<(BigInt, BigInt)> {
+    let precision = BigInt::from(precision);
+    if total_shares.0 == 0 {
       return Some((BigInt::from(0u64), precision));
     }
     let reward = BigInt::from(reward);
-    let total_shares = BigInt::from(total_shares);
+    let total_shares = BigInt::from(total_shares.0);
     let fractional_reward = precision.clone() * reward.clone() / total_shares.clone();
+    // High TVL cannot dilute the reward below the floor
+    let fractional_reward = fractional_reward.max(BigInt::from(floor.0));
     Some((fractional_reward, precision))
   }
 
+  ///
+  /// Whether a nonzero reward floors to zero per share at the total shares
+  ///
+  pub fn is_truncated(
+    reward: u64,
+    floor: ScaledAmount,
+    total_shares: Shares,
+    precision: u128,
+  ) -> Option<bool> {
+    let (fraction, _) = Self::fractionalize_reward(reward, floor, total_shares, precision)?;
+    Some(reward != 0 && total_shares.0 != 0 && fraction == BigInt::from(0u64))
+  }
+
+  ///
+  /// Convert compensation from one precision to another
+  ///
+  pub fn rescale_compensation(
+    compensation: ScaledComp,
+    old_precision: u64,
+    new_precision: u64,
+  ) -> Option<ScaledComp> {
+    if old_precision == 0 {
+      return None;
+    }
+    let compensation = BigInt::from(compensation.0);
+    let old_precision = BigInt::from(old_precision);
+    let new_precision = BigInt::from(new_precision);
+    (compensation * new_precision / old_precision)
+      .to_i128()
+      .map(ScaledComp)
+  }
+
+  ///
+  /// Keep the accrued debt of all shares unchanged when the reward or the floor changes
+  /// Both old and new are given as (reward, floor)
+  ///
+  pub fn rerate_compensation(
+    old: (u64, ScaledAmount),
+    new: (u64, ScaledAmount),
+    total_shares: Shares,
+    compensation: ScaledComp,
+    delay: u64,
+    precision: u128,
+  ) -> Option<ScaledComp> {
+    let compensation = BigInt::from(compensation.0);
+    let delay = BigInt::from(delay);
+    let (current_fraction, _) = Self::fractionalize_reward(old.0, old.1, total_shares, precision)?;
+    let (next_fraction, _) = Self::fractionalize_reward(new.0, new.1, total_shares, precision)?;
+    (compensation + (current_fraction - next_fraction) * delay)
+      .to_i128()
+      .map(ScaledComp)
+  }
+
+  ///
+  /// Reward of the in-progress period for newly added shares
+  /// They only accrue from the next period boundary
+  ///
+  pub fn estimate_forfeit(
+    reward: u64,
+    floor: ScaledAmount,
+    total_shares: Shares,
+    added_shares: Shares,
+    precision: u128,
+  ) -> Option<TokenAmount> {
+    let (fraction, precision) = Self::fractionalize_reward(reward, floor, total_shares, precision)?;
+    (fraction * BigInt::from(added_shares.0) / precision)
+      .to_u64()
+      .map(TokenAmount)
+  }
+
+  ///
+  /// Deduct the forfeit once the boundary period has been credited
+  /// Return the yield to pay and the remaining forfeit
+  ///
+  pub fn settle_forfeit(
+    yeild: TokenAmount,
+    forfeit: TokenAmount,
+    last_delay: u64,
+    delay: u64,
+  ) -> (TokenAmount, TokenAmount) {
+    if delay <= last_delay {
+      return (yeild, forfeit);
+    }
+    (
+      TokenAmount(yeild.0.saturating_sub(forfeit.0)),
+      TokenAmount(0),
+    )
+  }
+
+  ///
+  /// Fold elapsed periods into the reward accumulator
+  ///
+  pub fn accumulate(
+    acc: ScaledAmount,
+    reward: u64,
+    floor: ScaledAmount,
+    total_shares: Shares,
+    elapsed: u64,
+    precision: u128,
+  ) -> Option<ScaledAmount> {
+    let (fraction, _) = Self::fractionalize_reward(reward, floor, total_shares, precision)?;
+    (BigInt::from(acc.0) + fraction * BigInt::from(elapsed))
+      .to_u128()
+      .map(ScaledAmount)
+  }
+
+  ///
+  /// Reward accrued by shares at the accumulator
+  ///
+  pub fn accrue(shares: Shares, acc: ScaledAmount, precision: u128) -> Option<ScaledDebt> {
+    (BigInt::from(shares.0) * BigInt::from(acc.0) / BigInt::from(precision))
+      .to_u128()
+      .map(ScaledDebt)
+  }
+
+  ///
+  /// Harvest then move a position to next_shares at the accumulator
+  /// Return the yield and the new debt
+  ///
+  pub fn settle_accumulated(
+    shares: Shares,
+    next_shares: Shares,
+    debt: ScaledDebt,
+    acc: ScaledAmount,
+    precision: u128,
+  ) -> Option<(TokenAmount, ScaledDebt)> {
+    let yeild = Self::accrue(shares, acc, precision)?.yield_since(debt)?;
+    Some((yeild, Self::accrue(next_shares, acc, precision)?))
+  }
+
+  ///
+  /// Accumulator equivalent to the compensation at the current delay
+  /// Every stored debt stays valid across the migration
+  ///
+  pub fn migrate_to_accumulator(
+    reward: u64,
+    floor: ScaledAmount,
+    total_shares: Shares,
+    compensation: ScaledComp,
+    delay: u64,
+    precision: u128,
+  ) -> Option<ScaledAmount> {
+    if total_shares.0 == 0 {
+      return Some(ScaledAmount(0));
+    }
+    let (fraction, _) = Self::fractionalize_reward(reward, floor, total_shares, precision)?;
+    (fraction * BigInt::from(delay) + BigInt::from(compensation.0))
+      .to_u128()
+      .map(ScaledAmount)
+  }
+
+  ///
+  /// Estimate the yield owed to all stakers at the accumulator
+  ///
+  pub fn estimate_accumulated_liability(
+    total_shares: Shares,
+    acc: ScaledAmount,
+    total_debt: ScaledDebt,
+    precision: u128,
+  ) -> Option<ScaledDebt> {
+    let accrued = Self::accrue(total_shares, acc, precision)?;
+    Some(ScaledDebt(accrued.0.saturating_sub(total_debt.0)))
+  }
+
+  ///
+  /// Reward paid to all shares over the given periods
+  ///
+  pub fn estimate_emission(
+    reward: u64,
+    floor: ScaledAmount,
+    total_shares: Shares,
+    periods: u64,
+    precision: u128,
+  ) -> Option<TokenAmount> {
+    let (fraction, precision) = Self::fractionalize_reward(reward, floor, total_shares, precision)?;
+    (fraction * BigInt::from(total_shares.0) * BigInt::from(periods) / precision)
+      .to_u64()
+      .map(TokenAmount)
+  }
+
+  ///
+  /// Keep the reward of the last max_periods only for a dormant position
+  ///
+  pub fn clamp_yield(yeild: TokenAmount, elapsed: u64, max_periods: u64) -> Option<TokenAmount> {
+    if max_periods == 0 || elapsed <= max_periods {
+      return Some(yeild);
+    }
+    (BigInt::from(yeild.0) * BigInt::from(max_periods) / BigInt::from(elapsed))
+      .to_u64()
+      .map(TokenAmount)
+  }
+
+  ///
+  /// Linearly unlocked part of a vesting amount
+  ///
+  pub fn unlock_vesting(amount: u64, start: i64, duration: u64, now: i64) -> Option<u64> {
+    let elapsed = now.checked_sub(start)?.max(0) as u64;
+    if elapsed >= duration {
+      return Some(amount);
+    }
+    (BigInt::from(amount) * BigInt::from(elapsed) / BigInt::from(duration)).to_u64()
+  }
+
+  ///
+  /// Duration of the schedule merging a new amount into what is still locked,
+  /// the end is weighted by the amounts so it never passes the later of both ends
+  ///
+  pub fn merge_vesting(locked: u64, remaining: u64, amount: u64, duration: u64) -> Option<u64> {
+    let total = BigInt::from(locked) + BigInt::from(amount);
+    if total == BigInt::from(0u64) {
+      return Some(duration);
+    }
+    let weighted = BigInt::from(locked) * BigInt::from(remaining)
+      + BigInt::from(amount) * BigInt::from(duration);
+    (weighted / total).to_u64()
+  }
+
+  ///
+  /// Estimate the yield owed to all stakers but not harvested yet
+  ///
+  pub fn estimate_liability(
+    reward: u64,
+    floor: ScaledAmount,
+    total_shares: Shares,
+    compensation: ScaledComp,
+    delay: u64,
+    total_debt: ScaledDebt,
+    precision: u128,
+  ) -> Option<ScaledDebt> {
+    // Convert to big integer
+    let total_shares_big = BigInt::from(total_shares.0);
+    let compensation = BigInt::from(compensation.0);
+    let delay = BigInt::from(delay);
+    let total_debt = BigInt::from(total_debt.0);
+    // Compute the accrued debt of all shares
+    let (current_fraction, precision) =
+      Self::fractionalize_reward(reward, floor, total_shares, precision)?;
+    let accrued = (current_fraction * delay + compensation) * total_shares_big / precision;
+    if accrued <= total_debt {
+      return Some(ScaledDebt(0));
+    }
+    (accrued - total_debt).to_u128().map(ScaledDebt)
+  }
+
   ///
   /// Harvest all
   ///
   pub fn fully_harvest(
-    shares: u64,
-    debt: u128,
-    compensation: i128,
+    shares: Shares,
+    debt: ScaledDebt,
+    compensation: ScaledComp,
     delay: u64,
     reward: u64,
-    current_total_shares: u64,
-    next_total_shares: u64,
-  ) -> Option<(u64, u128, i128)> {
+    floor: ScaledAmount,
+    current_total_shares: Shares,
+    next_total_shares: Shares,
+    precision: u128,
+  ) -> Option<(Shares, ScaledDebt, ScaledComp)> {
     if current_total_shares != next_total_shares {
       return None;
     }
     // Convert to big integer
-    let shares = BigInt::from(shares);
-    let compensation = BigInt::from(compensation);
+    let shares = BigInt::from(shares.0);
+    let compensation = BigInt::from(compensation.0);
     let delay = BigInt::from(delay);
     // Compute current & next fraction = reward / total shares
-    let (current_fraction, precision) = Self::fractionalize_reward(reward, current_total_shares)?;
+    let (current_fraction, precision) =
+      Self::fractionalize_reward(reward, floor, current_total_shares, precision)?;
     // Compute next states
     let new_debt = ((current_fraction.clone() * delay.clone() + compensation.clone())
       * shares.clone()
       / precision.clone())
     .to_u128()?;
-    if debt > new_debt {
+    if debt.0 > new_debt {
       return None;
     }
-    Some((shares.to_u64()?, new_debt, compensation.to_i128()?))
+    Some((
+      Shares(shares.to_u64()?),
+      ScaledDebt(new_debt),
+      ScaledComp(compensation.to_i128()?),
+    ))
   }
 
   ///
   /// The unstake_pattern is only called when fully harvested
   ///
   pub fn fully_unstake(
-    shares: u64,
-    debt: u128,
-    compensation: i128,
+    shares: Shares,
+    debt: ScaledDebt,
+    compensation: ScaledComp,
     delay: u64,
     reward: u64,
-    current_total_shares: u64,
-    next_total_shares: u64,
-  ) -> Option<(u64, u128, i128)> {
+    floor: ScaledAmount,
+    current_total_shares: Shares,
+    next_total_shares: Shares,
+    precision: u128,
+  ) -> Option<(Shares, ScaledDebt, ScaledComp)> {
     if next_total_shares > current_total_shares {
       return None;
     }
     // Convert to big integer
-    let shares = BigInt::from(shares);
-    let compensation = BigInt::from(compensation);
+    let shares = BigInt::from(shares.0);
+    let compensation = BigInt::from(compensation.0);
     let delay = BigInt::from(delay);
     // Compute current & next fraction = reward / total shares
-    let (current_fraction, precision) = Self::fractionalize_reward(reward, current_total_shares)?;
-    let (next_fraction, _) = Self::fractionalize_reward(reward, next_total_shares)?;
+    let (next_fraction, _) =
+      Self::fractionalize_reward(reward, floor, next_total_shares, precision)?;
+    let (current_fraction, precision) =
+      Self::fractionalize_reward(reward, floor, current_total_shares, precision)?;
     // Whether harvested
     let expected_debt = ((current_fraction.clone() * delay.clone() + compensation.clone())
       * shares.clone()
       / precision.clone())
     .to_u128()?;
-    if debt != expected_debt {
+    if debt.0 != expected_debt {
       return None;
     }
-    // Compute next states
-    let new_compensation = if next_fraction == BigInt::from(0u64) {
+    // Compute next states, a zero fraction alone may just be truncated
+    let new_compensation = if next_total_shares.0 == 0 {
       BigInt::from(0u64)
     } else {
       compensation.clone() - (next_fraction.clone() - current_fraction.clone()) * delay.clone()
     };
-    Some((0, 0, new_compensation.to_i128()?))
+    Some((
+      Shares(0),
+      ScaledDebt(0),
+      ScaledComp(new_compensation.to_i128()?),
+    ))
   }
 
   ///
   /// The stake_pattern is only called when fully unstaked
   ///
   pub fn fully_stake(
-    shares: u64,
-    debt: u128,
-    compensation: i128,
+    shares: Shares,
+    debt: ScaledDebt,
+    compensation: ScaledComp,
     delay: u64,
     reward: u64,
-    current_total_shares: u64,
-    next_total_shares: u64,
-  ) -> Option<(u64, u128, i128)> {
-    if current_total_shares > next_total_shares || debt != 0 {
+    floor: ScaledAmount,
+    current_total_shares: Shares,
+    next_total_shares: Shares,
+    precision: u128,
+  ) -> Option<(Shares, ScaledDebt, ScaledComp)> {
+    if current_total_shares > next_total_shares || debt.0 != 0 {
       return None;
     }
     // Convert to big integer
-    let compensation = BigInt::from(compensation);
+    let compensation = BigInt::from(compensation.0);
     let delay = BigInt::from(delay);
     // Compute current & next fraction = reward / total shares
-    let (current_fraction, precision) = Self::fractionalize_reward(reward, current_total_shares)?;
-    let (next_fraction, _) = Self::fractionalize_reward(reward, next_total_shares)?;
-    // Compute next states
-    let new_compensation = if current_fraction == BigInt::from(0u64) {
+    let (next_fraction, _) =
+      Self::fractionalize_reward(reward, floor, next_total_shares, precision)?;
+    let (current_fraction, precision) =
+      Self::fractionalize_reward(reward, floor, current_total_shares, precision)?;
+    // Compute next states, reset only from an empty pool
+    let new_compensation = if current_total_shares.0 == 0 {
       BigInt::from(0u64)
     } else {
       compensation.clone() + (current_fraction.clone() - next_fraction.clone()) * delay.clone()
     };
+    // The debt is all the shares accrued at the new compensation, so a fresh
+    // position claims nothing earned before it, whatever the inherited compensation
     let new_debt = (next_fraction.clone() * delay.clone() + new_compensation.clone())
-      * shares.clone()
+      * BigInt::from(shares.0)
       / precision.clone();
-    Some((shares, new_debt.to_u128()?, new_compensation.to_i128()?))
+    Some((
+      shares,
+      ScaledDebt(new_debt.to_u128()?),
+      ScaledComp(new_compensation.to_i128()?),
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_merge_vesting_stays_between_both_ends() {
+    // Nothing locked takes the new duration
+    assert_eq!(Pattern::merge_vesting(0, 0, 100, 600), Some(600));
+    assert_eq!(Pattern::merge_vesting(0, 0, 0, 600), Some(600));
+    // Equal amounts meet halfway
+    assert_eq!(Pattern::merge_vesting(100, 200, 100, 600), Some(400));
+    for (locked, remaining, amount, duration) in [
+      (1u64, 1u64, u64::MAX, 600u64),
+      (u64::MAX, 600, 1, 1),
+      (500, 100, 7, 86400),
+      (3, 86400, 500, 100),
+    ]
+    .iter()
+    {
+      let merged = Pattern::merge_vesting(*locked, *remaining, *amount, *duration).unwrap();
+      assert!(merged <= (*remaining).max(*duration));
+      assert!(merged >= (*remaining).min(*duration));
+    }
+  }
+
+  const P: u128 = crate::PRECISION;
+
+  // Yield of a legacy position harvested at the delay
+  fn legacy_yield(
+    reward: u64,
+    floor: ScaledAmount,
+    total_shares: Shares,
+    compensation: ScaledComp,
+    shares: Shares,
+    debt: ScaledDebt,
+    delay: u64,
+  ) -> u128 {
+    let (_, new_debt, _) = Pattern::fully_harvest(
+      shares,
+      debt,
+      compensation,
+      delay,
+      reward,
+      floor,
+      total_shares,
+      total_shares,
+      P,
+    )
+    .unwrap();
+    new_debt.0 - debt.0
+  }
+
+  // Yield of the same position once migrated at from_delay, harvested at the delay
+  fn accumulated_yield(
+    reward: u64,
+    floor: ScaledAmount,
+    total_shares: Shares,
+    acc: ScaledAmount,
+    from_delay: u64,
+    shares: Shares,
+    debt: ScaledDebt,
+    delay: u64,
+  ) -> u128 {
+    let acc = Pattern::accumulate(acc, reward, floor, total_shares, delay - from_delay, P).unwrap();
+    let (yeild, _) = Pattern::settle_accumulated(shares, shares, debt, acc, P).unwrap();
+    yeild.0 as u128
+  }
+
+  #[test]
+  fn test_migration_pays_what_the_compensation_would() {
+    for (reward, floor, first, second) in [
+      (100u64, 0u128, 1000u64, 3u64),
+      (7, 0, 1, 1_000_000_007),
+      (1, 0, 1_000_000_000_000, 999_999_999_999),
+      (
+        1,
+        5 * P / 1_000_000_000_000_000,
+        1_000_000_000_000,
+        999_999_999_999,
+      ),
+    ]
+    .iter()
+    {
+      let floor = ScaledAmount(*floor);
+      // The first position stakes at genesis, the second joins at delay 5
+      let (first_shares, first_debt, compensation) = Pattern::fully_stake(
+        Shares(*first),
+        ScaledDebt(0),
+        ScaledComp(0),
+        0,
+        *reward,
+        floor,
+        Shares(0),
+        Shares(*first),
+        P,
+      )
+      .unwrap();
+      let total = Shares(first + second);
+      let (second_shares, second_debt, compensation) = Pattern::fully_stake(
+        Shares(*second),
+        ScaledDebt(0),
+        compensation,
+        5,
+        *reward,
+        floor,
+        Shares(*first),
+        total,
+        P,
+      )
+      .unwrap();
+      let acc = Pattern::migrate_to_accumulator(*reward, floor, total, compensation, 9, P).unwrap();
+      for delay in [9u64, 10, 20, 1000].iter() {
+        for (shares, debt) in [(first_shares, first_debt), (second_shares, second_debt)].iter() {
+          assert_eq!(
+            legacy_yield(*reward, floor, total, compensation, *shares, *debt, *delay),
+            accumulated_yield(*reward, floor, total, acc, 9, *shares, *debt, *delay)
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_migration_after_an_unstake_pays_what_the_compensation_would() {
+    let (reward, floor) = (100u64, ScaledAmount(0));
+    let (_, _, compensation) = Pattern::fully_stake(
+      Shares(1000),
+      ScaledDebt(0),
+      ScaledComp(0),
+      0,
+      reward,
+      floor,
+      Shares(0),
+      Shares(1000),
+      P,
+    )
+    .unwrap();
+    let (shares, debt, compensation) = Pattern::fully_stake(
+      Shares(3),
+      ScaledDebt(0),
+      compensation,
+      5,
+      reward,
+      floor,
+      Shares(1000),
+      Shares(1003),
+      P,
+    )
+    .unwrap();
+    // The first position harvests then leaves at delay 7
+    let (first_shares, first_debt, compensation) = Pattern::fully_harvest(
+      Shares(1000),
+      ScaledDebt(0),
+      compensation,
+      7,
+      reward,
+      floor,
+      Shares(1003),
+      Shares(1003),
+      P,
+    )
+    .unwrap();
+    let (_, _, compensation) = Pattern::fully_unstake(
+      first_shares,
+      first_debt,
+      compensation,
+      7,
+      reward,
+      floor,
+      Shares(1003),
+      Shares(3),
+      P,
+    )
+    .unwrap();
+    let acc =
+      Pattern::migrate_to_accumulator(reward, floor, Shares(3), compensation, 9, P).unwrap();
+    for delay in [9u64, 10, 20, 1000].iter() {
+      assert_eq!(
+        legacy_yield(reward, floor, Shares(3), compensation, shares, debt, *delay),
+        accumulated_yield(reward, floor, Shares(3), acc, 9, shares, debt, *delay)
+      );
+    }
+  }
+
+  #[test]
+  fn test_migration_of_a_large_negative_compensation_fails() {
+    let compensation = ScaledComp(-100 * 10 * P as i128 - 1);
+    assert_eq!(
+      Pattern::migrate_to_accumulator(100, ScaledAmount(0), Shares(1), compensation, 10, P),
+      None
+    );
+    // An empty pool restarts the accumulator whatever the compensation
+    assert_eq!(
+      Pattern::migrate_to_accumulator(100, ScaledAmount(0), Shares(0), compensation, 10, P),
+      Some(ScaledAmount(0))
+    );
+  }
+
+  // Compensation of a stake under the former rule, reset by any zero fraction
+  fn stake_compensation_reset_on_zero_fraction(
+    compensation: ScaledComp,
+    delay: u64,
+    reward: u64,
+    current_total_shares: Shares,
+    next_total_shares: Shares,
+    precision: u128,
+  ) -> ScaledComp {
+    let floor = ScaledAmount(0);
+    let (current_fraction, _) =
+      Pattern::fractionalize_reward(reward, floor, current_total_shares, precision).unwrap();
+    if current_fraction == BigInt::from(0u64) {
+      return ScaledComp(0);
+    }
+    let (_, _, compensation) = Pattern::fully_stake(
+      Shares(0),
+      ScaledDebt(0),
+      compensation,
+      delay,
+      reward,
+      floor,
+      current_total_shares,
+      next_total_shares,
+      precision,
+    )
+    .unwrap();
+    compensation
+  }
+
+  #[test]
+  fn test_compensation_reset_only_from_an_empty_pool() {
+    // A precision small enough to truncate the fraction of a few shares
+    let (reward, floor, precision) = (1u64, ScaledAmount(0), 1000u128);
+    let stake = |shares: u64, compensation: ScaledComp, delay: u64, current: u64| {
+      Pattern::fully_stake(
+        Shares(shares),
+        ScaledDebt(0),
+        compensation,
+        delay,
+        reward,
+        floor,
+        Shares(current),
+        Shares(current + shares),
+        precision,
+      )
+      .unwrap()
+    };
+    // The first position earns 2/1000 a share for 4 periods alone
+    let (first, first_debt, compensation) = stake(500, ScaledComp(0), 0, 0);
+    let (_, _, compensation) = stake(1500, compensation, 4, 500);
+    let accrued = |compensation: ScaledComp, delay: u64, total: u64| {
+      let (_, debt, _) = Pattern::fully_harvest(
+        first,
+        first_debt,
+        compensation,
+        delay,
+        reward,
+        floor,
+        Shares(total),
+        Shares(total),
+        precision,
+      )
+      .unwrap();
+      debt.0 - first_debt.0
+    };
+    assert_eq!(accrued(compensation, 4, 2000), 4);
+    // Another stake while the fraction truncates to zero
+    let (_, _, kept) = stake(1, compensation, 5, 2000);
+    let reset = stake_compensation_reset_on_zero_fraction(
+      compensation,
+      5,
+      reward,
+      Shares(2000),
+      Shares(2001),
+      precision,
+    );
+    // The former rule wiped what the first position had earned
+    assert_eq!(accrued(kept, 10, 2001), 4);
+    assert_eq!(accrued(reset, 10, 2001), 0);
+    // Both rules agree while the fraction does not truncate
+    let (_, _, compensation) = stake(1, ScaledComp(0), 3, 500);
+    assert_eq!(
+      compensation,
+      stake_compensation_reset_on_zero_fraction(
+        ScaledComp(0),
+        3,
+        reward,
+        Shares(500),
+        Shares(501),
+        precision
+      )
+    );
+    // And from an empty pool, both restart
+    let (_, _, compensation) = stake(1, ScaledComp(-7), 3, 0);
+    assert_eq!(compensation, ScaledComp(0));
+  }
+
+  #[test]
+  fn test_rescaled_compensation_harvests_alike_at_a_coarser_precision() {
+    const COARSE: u128 = 1_000_000_000_000;
+    let (reward, floor) = (100u64, ScaledAmount(0));
+    let stake = |shares: u64, compensation: ScaledComp, delay: u64, current: u64| {
+      Pattern::fully_stake(
+        Shares(shares),
+        ScaledDebt(0),
+        compensation,
+        delay,
+        reward,
+        floor,
+        Shares(current),
+        Shares(current + shares),
+        P,
+      )
+      .unwrap()
+    };
+    // The second position joins at delay 5, the precision moves from 1e18 to 1e12 at delay 9
+    let (first, first_debt, compensation) = stake(1000, ScaledComp(0), 0, 0);
+    let (second, second_debt, compensation) = stake(3, compensation, 5, 1000);
+    let total = Shares(1003);
+    let rescaled = Pattern::rescale_compensation(compensation, P as u64, COARSE as u64).unwrap();
+    assert_eq!(rescaled.0, compensation.0 / (P / COARSE) as i128);
+    for delay in [9u64, 10, 20, 1000].iter() {
+      for (shares, debt) in [(first, first_debt), (second, second_debt)].iter() {
+        let harvest = |compensation: ScaledComp, precision: u128| {
+          let (_, new_debt, _) = Pattern::fully_harvest(
+            *shares,
+            *debt,
+            compensation,
+            *delay,
+            reward,
+            floor,
+            total,
+            total,
+            precision,
+          )
+          .unwrap();
+          new_debt.0 - debt.0
+        };
+        let fine = harvest(compensation, P);
+        let coarse = harvest(rescaled, COARSE);
+        assert!(
+          (fine as i128 - coarse as i128).abs() <= 1,
+          "{} for {} at delay {}",
+          coarse,
+          fine,
+          delay
+        );
+      }
+    }
   }
 }
@@ -12,3 +12,82 @@ impl Boolean for Pubkey {
     return Pubkey::new_from_array(c);
   }
 }
+
+///
+/// Mint of wrapped SOL, So11111111111111111111111111111111111111112
+///
+pub const NATIVE_MINT: Pubkey = Pubkey::new_from_array([
+  6, 155, 136, 87, 254, 171, 129, 132, 251, 104, 127, 99, 70, 24, 192, 53, 218, 196, 57, 220, 26,
+  235, 59, 85, 152, 160, 240, 0, 0, 0, 0, 1,
+]);
+
+///
+/// Associated token account of a wallet
+/// The token program is a parameter so Token-2022 mints derive against their own program
+///
+pub fn associated_token_address(
+  wallet: &Pubkey,
+  mint: &Pubkey,
+  splt_program_id: &Pubkey,
+  splata_program_id: &Pubkey,
+) -> Pubkey {
+  let (key, _) = Pubkey::find_program_address(
+    &[
+      &wallet.to_bytes(),
+      &splt_program_id.to_bytes(),
+      &mint.to_bytes(),
+    ],
+    splata_program_id,
+  );
+  key
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::{from_hex, splata_program_id, splt_program_id};
+  use std::str::FromStr;
+
+  // Derived by the spl-associated-token-account rules for the wallet [w; 32] and mint [2; 32]
+  const SPLT_ADDRESSES: [(u8, &str); 2] = [
+    (
+      1,
+      "b0637fe98337d49a714c7f159ae0a3abb5525a699707f7cb7444b952e0f6853c",
+    ),
+    // Bump 249
+    (
+      3,
+      "f5f98bbcb888abb4a2d8abc0a227a2abc3d1f28af2d4cc900ed684516c8bea59",
+    ),
+  ];
+  const TOKEN_2022_ADDRESSES: [(u8, &str); 2] = [
+    (
+      1,
+      "4e4535da754ba891b08324461125d98db4b63d3f6fffe996c8d24e99f4aa0af6",
+    ),
+    // Bump 254
+    (
+      6,
+      "2ca3f181c3f4765ef791427ba63519ee9ec30f3632aa10f589fed080e6c291c0",
+    ),
+  ];
+
+  #[test]
+  fn test_associated_token_address_matches_the_official_derivation() {
+    let token_2022_program_id =
+      Pubkey::from_str("TokenzQdBNbLqP5VEhdkAS6EPFLZ8LtSRHTSoE4t5k4").unwrap();
+    let mint = Pubkey::new_from_array([2; 32]);
+    for (splt_program_id, addresses) in [
+      (splt_program_id(), SPLT_ADDRESSES),
+      (token_2022_program_id, TOKEN_2022_ADDRESSES),
+    ]
+    .iter()
+    {
+      for (wallet, address) in addresses.iter() {
+        let wallet = Pubkey::new_from_array([*wallet; 32]);
+        let key = associated_token_address(&wallet, &mint, splt_program_id, &splata_program_id());
+        assert_eq!(key.to_bytes().to_vec(), from_hex(address));
+      }
+    }
+  }
+}
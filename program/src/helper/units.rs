@@ -0,0 +1,87 @@
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::convert::TryFrom;
+
+///
+/// Amount scaled by the precision (fractional rewards, floors)
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScaledAmount(pub u128);
+
+///
+/// Amount in base units of a token
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TokenAmount(pub u64);
+
+impl ScaledAmount {
+  /// Drop the precision, rounding down
+  pub fn to_token_amount(self, precision: u128) -> Option<TokenAmount> {
+    u64::try_from(self.0.checked_div(precision)?)
+      .ok()
+      .map(TokenAmount)
+  }
+}
+
+///
+/// Scale numerator / denominator by the precision, rounding down
+///
+pub fn fraction_from_parts(
+  numerator: u64,
+  denominator: u64,
+  precision: u128,
+) -> Option<ScaledAmount> {
+  if denominator == 0 {
+    return None;
+  }
+  // Wide precisions overflow u128 before the division
+  (BigInt::from(numerator) * BigInt::from(precision) / BigInt::from(denominator))
+    .to_u128()
+    .map(ScaledAmount)
+}
+
+///
+/// Compensation in SEN / share, for display only
+///
+pub fn compensation_to_f64_for_display(compensation: i128, precision: u128) -> f64 {
+  compensation as f64 / precision as f64
+}
+
+///
+/// Shares of a stake pool
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Shares(pub u64);
+
+///
+/// Accrued reward of a position, precision already divided out
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScaledDebt(pub u128);
+
+///
+/// Compensation of a stake pool, scaled by the precision
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScaledComp(pub i128);
+
+impl Shares {
+  /// Shares are minted 1:1 with the staked token
+  pub fn from_tokens(amount: TokenAmount) -> Self {
+    Shares(amount.0)
+  }
+  pub fn checked_add(self, other: Shares) -> Option<Shares> {
+    self.0.checked_add(other.0).map(Shares)
+  }
+  pub fn checked_sub(self, other: Shares) -> Option<Shares> {
+    self.0.checked_sub(other.0).map(Shares)
+  }
+}
+
+impl ScaledDebt {
+  /// Yield accrued since a previous debt
+  pub fn yield_since(self, previous: ScaledDebt) -> Option<TokenAmount> {
+    let yeild = self.0.checked_sub(previous.0)?;
+    u64::try_from(yeild).ok().map(TokenAmount)
+  }
+}
@@ -1,23 +1,184 @@
 use crate::error::AppError;
+use crate::schema::distribution::MAX_DISTRIBUTION_CHILDREN;
+use crate::PRECISION_EXP;
 use solana_program::program_error::ProgramError;
 use std::convert::TryInto;
 
+///
+/// Most pools per batch, so a batch fits in a legacy transaction
+///
+pub const MAX_TRANSFER_OWNERSHIP_BATCH: u8 = 16;
+pub const MAX_TVL_BATCH: u8 = 16;
+
+///
+/// Leads a staker instruction sent with a client version, the version byte follows.
+/// No instruction tag takes this value
+///
+pub const CLIENT_VERSION_PREFIX: u8 = 0xff;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum AppInstruction {
-  InitializeStakePool { reward: u64, period: u64 },
+  InitializeStakePool {
+    reward: u64,
+    period: u64,
+    genesis_timestamp: i64,
+    allow_pre_stake: bool,
+    pool_seed: Option<[u8; 32]>,
+    precision_exp: u8,
+  },
   InitializeAccounts,
-  Stake { amount: u64 },
-  Unstake { amount: u64 },
-  Harvest,
+  Stake {
+    amount: u64,
+    reference: Option<u64>,
+    idempotency_key: Option<[u8; 16]>,
+  },
+  Unstake {
+    amount: u64,
+    reference: Option<u64>,
+    idempotency_key: Option<[u8; 16]>,
+  },
+  // The memo flag may be followed by the idempotency key
+  Harvest {
+    memo: bool,
+    idempotency_key: Option<[u8; 16]>,
+  },
   FreezeStakePool,
   ThawStakePool,
-  Seed { amount: u64 },
-  Unseed { amount: u64 },
+  Seed {
+    amount: u64,
+  },
+  Unseed {
+    amount: u64,
+  },
   TransferStakePoolOwnership,
   CloseDebt,
   CloseStakePool,
+  RescaleCompensation {
+    old_precision: u64,
+    new_precision: u64,
+  },
+  GetStoredDebt,
+  CrankSeed,
+  UpdateCrankSeed {
+    max_crank_amount: u64,
+    crank_interval: u64,
+  },
+  InitializeContribution,
+  WithdrawSeed {
+    amount: u64,
+  },
+  RepairShareMint,
+  InitializeRegistry,
+  RegisterExistingPool,
+  DelistPool {
+    index: u64,
+  },
+  SetRewardFloor {
+    min_reward_per_share: u128,
+  },
+  SetUnseedLimit {
+    max_unseed_bps_per_day: u16,
+  },
+  GetRewardMintInfo,
+  SetUnseedDestination,
+  SetHarvestInterval {
+    min_harvest_interval: u64,
+  },
+  GetEffectiveReward,
+  HarvestAndSeed,
+  UpdatePool,
+  MigrateStakePool,
+  CloneStakePool {
+    genesis_timestamp: i64,
+    pool_seed: Option<[u8; 32]>,
+  },
+  TransferOwnershipBatch {
+    count: u8,
+  },
+  GetClaimable,
+  AllowMint,
+  DisallowMint,
+  GetPoolTiming,
+  ExitPool,
+  SetActivation {
+    activation_timestamp: i64,
+  },
+  SetMaxAccrualPeriods {
+    max_accrual_periods: u64,
+  },
+  GetRunwaySeed {
+    days: u64,
+  },
+  SetCrankTip {
+    crank_tip: u64,
+  },
+  SetDefaultSenAccount,
+  SetMinClientVersion {
+    min_client_version: u8,
+  },
+  SetVestingDuration {
+    vesting_duration: u64,
+  },
+  ClaimVested,
+  GetTvlBatch {
+    count: u8,
+  },
+  IsAccountInitialized,
+  ClearWithdrawOnly,
+  ShiftGenesis {
+    delta_seconds: i64,
+  },
+  GarbageCollectPool,
+  HarvestWithCreate,
+  GetLayout,
+  SetDistribution {
+    count: u8,
+    weights_bps: [u16; MAX_DISTRIBUTION_CHILDREN],
+  },
+  Distribute {
+    amount: u64,
+  },
+  HarvestSol,
+  InitializeSecurityCouncil,
 }
 impl AppInstruction {
+  // Staker instructions, which may carry a client version
+  fn is_user_tag(tag: u8) -> bool {
+    matches!(tag, 1 | 2 | 3 | 4 | 28 | 37 | 45 | 51 | 55)
+  }
+
+  // Optional client reference, echoed to the logs, then optional idempotency key
+  fn unpack_reference(rest: &[u8]) -> Result<(Option<u64>, Option<[u8; 16]>), ProgramError> {
+    let reference = match rest.len() {
+      16 | 32 => rest
+        .get(8..16)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u64::from_le_bytes),
+      8 | 24 => None,
+      _ => return Err(AppError::InvalidInstruction.into()),
+    };
+    let idempotency_key = match rest.len() {
+      24 | 32 => rest
+        .get(rest.len() - 16..)
+        .and_then(|slice| slice.try_into().ok()),
+      _ => None,
+    };
+    Ok((reference, idempotency_key))
+  }
+
+  // Split the client version prefix off a staker instruction,
+  // an absent prefix reads as version zero. Other instructions carry none.
+  pub fn split_client_version(instruction: &[u8]) -> Result<(&[u8], Option<u8>), ProgramError> {
+    match instruction {
+      [CLIENT_VERSION_PREFIX, version, data @ ..] => match data.first() {
+        Some(&tag) if Self::is_user_tag(tag) => Ok((data, Some(*version))),
+        _ => Err(AppError::InvalidInstruction.into()),
+      },
+      [tag, ..] if Self::is_user_tag(*tag) => Ok((instruction, Some(0))),
+      _ => Ok((instruction, None)),
+    }
+  }
+
   pub fn unpack(instruction: &[u8]) -> Result<Self, ProgramError> {
     let (&tag, rest) = instruction
       .split_first()
@@ -34,7 +195,38 @@ impl AppInstruction {
           .and_then(|slice| slice.try_into().ok())
           .map(u64::from_le_bytes)
           .ok_or(AppError::InvalidInstruction)?;
-        Self::InitializeStakePool { reward, period }
+        // Optional launch time, zero means now
+        let (genesis_timestamp, allow_pre_stake) = match rest.len() {
+          16 => (0, false),
+          25 | 26 | 57 | 58 => (
+            rest
+              .get(16..24)
+              .and_then(|slice| slice.try_into().ok())
+              .map(i64::from_le_bytes)
+              .ok_or(AppError::InvalidInstruction)?,
+            match rest[24] {
+              0 => false,
+              1 => true,
+              _ => return Err(AppError::InvalidInstruction.into()),
+            },
+          ),
+          _ => return Err(AppError::InvalidInstruction.into()),
+        };
+        // Optional seed of a derived stake pool
+        let pool_seed = rest.get(25..57).and_then(|slice| slice.try_into().ok());
+        // Optional precision exponent last, the default otherwise
+        let precision_exp = match rest.len() {
+          26 | 58 => rest[rest.len() - 1],
+          _ => PRECISION_EXP,
+        };
+        Self::InitializeStakePool {
+          reward,
+          period,
+          genesis_timestamp,
+          allow_pre_stake,
+          pool_seed,
+          precision_exp,
+        }
       }
       1 => Self::InitializeAccounts,
       2 => {
@@ -43,7 +235,12 @@ impl AppInstruction {
           .and_then(|slice| slice.try_into().ok())
           .map(u64::from_le_bytes)
           .ok_or(AppError::InvalidInstruction)?;
-        Self::Stake { amount }
+        let (reference, idempotency_key) = Self::unpack_reference(rest)?;
+        Self::Stake {
+          amount,
+          reference,
+          idempotency_key,
+        }
       }
       3 => {
         let amount = rest
@@ -51,9 +248,31 @@ impl AppInstruction {
           .and_then(|slice| slice.try_into().ok())
           .map(u64::from_le_bytes)
           .ok_or(AppError::InvalidInstruction)?;
-        Self::Unstake { amount }
+        let (reference, idempotency_key) = Self::unpack_reference(rest)?;
+        Self::Unstake {
+          amount,
+          reference,
+          idempotency_key,
+        }
+      }
+      4 => {
+        // Optional memo of the harvest in the owner's wallet history
+        let memo = match rest.first() {
+          None | Some(0) => false,
+          Some(1) => true,
+          _ => return Err(AppError::InvalidInstruction.into()),
+        };
+        // Optional idempotency key after the memo flag
+        let idempotency_key = match rest.len() {
+          0 | 1 => None,
+          17 => rest.get(1..17).and_then(|slice| slice.try_into().ok()),
+          _ => return Err(AppError::InvalidInstruction.into()),
+        };
+        Self::Harvest {
+          memo,
+          idempotency_key,
+        }
       }
-      4 => Self::Harvest,
       5 => Self::FreezeStakePool,
       6 => Self::ThawStakePool,
       7 => {
@@ -75,7 +294,752 @@ impl AppInstruction {
       9 => Self::TransferStakePoolOwnership,
       10 => Self::CloseDebt,
       11 => Self::CloseStakePool,
+      12 => {
+        let old_precision = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        let new_precision = rest
+          .get(8..16)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::RescaleCompensation {
+          old_precision,
+          new_precision,
+        }
+      }
+      13 => Self::GetStoredDebt,
+      14 => Self::CrankSeed,
+      15 => {
+        let max_crank_amount = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        let crank_interval = rest
+          .get(8..16)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::UpdateCrankSeed {
+          max_crank_amount,
+          crank_interval,
+        }
+      }
+      16 => Self::InitializeContribution,
+      17 => {
+        let amount = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::WithdrawSeed { amount }
+      }
+      18 => Self::RepairShareMint,
+      19 => Self::InitializeRegistry,
+      20 => Self::RegisterExistingPool,
+      21 => {
+        let index = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::DelistPool { index }
+      }
+      22 => {
+        let min_reward_per_share = rest
+          .get(..16)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u128::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::SetRewardFloor {
+          min_reward_per_share,
+        }
+      }
+      23 => {
+        let max_unseed_bps_per_day = rest
+          .get(..2)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u16::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::SetUnseedLimit {
+          max_unseed_bps_per_day,
+        }
+      }
+      24 => Self::GetRewardMintInfo,
+      25 => Self::SetUnseedDestination,
+      26 => {
+        let min_harvest_interval = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::SetHarvestInterval {
+          min_harvest_interval,
+        }
+      }
+      27 => Self::GetEffectiveReward,
+      28 => Self::HarvestAndSeed,
+      29 => Self::UpdatePool,
+      30 => Self::MigrateStakePool,
+      31 => {
+        // Zero means now
+        let genesis_timestamp = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(i64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        // Optional seed of a derived stake pool
+        let pool_seed = rest.get(8..40).and_then(|slice| slice.try_into().ok());
+        Self::CloneStakePool {
+          genesis_timestamp,
+          pool_seed,
+        }
+      }
+      32 => {
+        let count = *rest.first().ok_or(AppError::InvalidInstruction)?;
+        Self::TransferOwnershipBatch { count }
+      }
+      33 => Self::GetClaimable,
+      34 => Self::AllowMint,
+      35 => Self::DisallowMint,
+      36 => Self::GetPoolTiming,
+      37 => Self::ExitPool,
+      38 => {
+        let activation_timestamp = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(i64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::SetActivation {
+          activation_timestamp,
+        }
+      }
+      39 => {
+        let max_accrual_periods = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::SetMaxAccrualPeriods {
+          max_accrual_periods,
+        }
+      }
+      40 => {
+        let days = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::GetRunwaySeed { days }
+      }
+      41 => {
+        let crank_tip = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::SetCrankTip { crank_tip }
+      }
+      42 => Self::SetDefaultSenAccount,
+      43 => {
+        let min_client_version = *rest.first().ok_or(AppError::InvalidInstruction)?;
+        Self::SetMinClientVersion { min_client_version }
+      }
+      44 => {
+        let vesting_duration = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::SetVestingDuration { vesting_duration }
+      }
+      45 => Self::ClaimVested,
+      46 => {
+        let count = *rest.first().ok_or(AppError::InvalidInstruction)?;
+        Self::GetTvlBatch { count }
+      }
+      47 => Self::IsAccountInitialized,
+      48 => Self::ClearWithdrawOnly,
+      49 => {
+        let delta_seconds = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(i64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::ShiftGenesis { delta_seconds }
+      }
+      50 => Self::GarbageCollectPool,
+      51 => Self::HarvestWithCreate,
+      52 => Self::GetLayout,
+      53 => {
+        let count = *rest.first().ok_or(AppError::InvalidInstruction)?;
+        // Exactly count weights follow
+        if count as usize > MAX_DISTRIBUTION_CHILDREN || rest.len() != 1 + 2 * count as usize {
+          return Err(AppError::InvalidInstruction.into());
+        }
+        let mut weights_bps = [0; MAX_DISTRIBUTION_CHILDREN];
+        for (weight, bytes) in weights_bps.iter_mut().zip(rest[1..].chunks(2)) {
+          *weight = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+        Self::SetDistribution { count, weights_bps }
+      }
+      54 => {
+        let amount = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(AppError::InvalidInstruction)?;
+        Self::Distribute { amount }
+      }
+      55 => Self::HarvestSol,
+      56 => Self::InitializeSecurityCouncil,
       _ => return Err(AppError::InvalidInstruction.into()),
     })
   }
+
+  // Length of the packed instruction data, tag included
+  pub fn packed_len(&self) -> usize {
+    self.pack().len()
+  }
+
+  // Instruction data as unpack reads it
+  pub fn pack(&self) -> Vec<u8> {
+    let mut data = vec![];
+    match self {
+      Self::InitializeStakePool {
+        reward,
+        period,
+        genesis_timestamp,
+        allow_pre_stake,
+        pool_seed,
+        precision_exp,
+      } => {
+        data.push(0);
+        data.extend_from_slice(&reward.to_le_bytes());
+        data.extend_from_slice(&period.to_le_bytes());
+        data.extend_from_slice(&genesis_timestamp.to_le_bytes());
+        data.push(*allow_pre_stake as u8);
+        if let Some(pool_seed) = pool_seed {
+          data.extend_from_slice(pool_seed);
+        }
+        if *precision_exp != PRECISION_EXP {
+          data.push(*precision_exp);
+        }
+      }
+      Self::InitializeAccounts => data.push(1),
+      Self::Stake {
+        amount,
+        reference,
+        idempotency_key,
+      }
+      | Self::Unstake {
+        amount,
+        reference,
+        idempotency_key,
+      } => {
+        data.push(match self {
+          Self::Stake { .. } => 2,
+          _ => 3,
+        });
+        data.extend_from_slice(&amount.to_le_bytes());
+        if let Some(reference) = reference {
+          data.extend_from_slice(&reference.to_le_bytes());
+        }
+        if let Some(idempotency_key) = idempotency_key {
+          data.extend_from_slice(idempotency_key);
+        }
+      }
+      Self::Harvest {
+        memo,
+        idempotency_key,
+      } => {
+        data.push(4);
+        match idempotency_key {
+          Some(idempotency_key) => {
+            data.push(*memo as u8);
+            data.extend_from_slice(idempotency_key);
+          }
+          None if *memo => data.push(1),
+          None => {}
+        }
+      }
+      Self::FreezeStakePool => data.push(5),
+      Self::ThawStakePool => data.push(6),
+      Self::Seed { amount } => {
+        data.push(7);
+        data.extend_from_slice(&amount.to_le_bytes());
+      }
+      Self::Unseed { amount } => {
+        data.push(8);
+        data.extend_from_slice(&amount.to_le_bytes());
+      }
+      Self::TransferStakePoolOwnership => data.push(9),
+      Self::CloseDebt => data.push(10),
+      Self::CloseStakePool => data.push(11),
+      Self::RescaleCompensation {
+        old_precision,
+        new_precision,
+      } => {
+        data.push(12);
+        data.extend_from_slice(&old_precision.to_le_bytes());
+        data.extend_from_slice(&new_precision.to_le_bytes());
+      }
+      Self::GetStoredDebt => data.push(13),
+      Self::CrankSeed => data.push(14),
+      Self::UpdateCrankSeed {
+        max_crank_amount,
+        crank_interval,
+      } => {
+        data.push(15);
+        data.extend_from_slice(&max_crank_amount.to_le_bytes());
+        data.extend_from_slice(&crank_interval.to_le_bytes());
+      }
+      Self::InitializeContribution => data.push(16),
+      Self::WithdrawSeed { amount } => {
+        data.push(17);
+        data.extend_from_slice(&amount.to_le_bytes());
+      }
+      Self::RepairShareMint => data.push(18),
+      Self::InitializeRegistry => data.push(19),
+      Self::RegisterExistingPool => data.push(20),
+      Self::DelistPool { index } => {
+        data.push(21);
+        data.extend_from_slice(&index.to_le_bytes());
+      }
+      Self::SetRewardFloor {
+        min_reward_per_share,
+      } => {
+        data.push(22);
+        data.extend_from_slice(&min_reward_per_share.to_le_bytes());
+      }
+      Self::SetUnseedLimit {
+        max_unseed_bps_per_day,
+      } => {
+        data.push(23);
+        data.extend_from_slice(&max_unseed_bps_per_day.to_le_bytes());
+      }
+      Self::GetRewardMintInfo => data.push(24),
+      Self::SetUnseedDestination => data.push(25),
+      Self::SetHarvestInterval {
+        min_harvest_interval,
+      } => {
+        data.push(26);
+        data.extend_from_slice(&min_harvest_interval.to_le_bytes());
+      }
+      Self::GetEffectiveReward => data.push(27),
+      Self::HarvestAndSeed => data.push(28),
+      Self::UpdatePool => data.push(29),
+      Self::MigrateStakePool => data.push(30),
+      Self::CloneStakePool {
+        genesis_timestamp,
+        pool_seed,
+      } => {
+        data.push(31);
+        data.extend_from_slice(&genesis_timestamp.to_le_bytes());
+        if let Some(pool_seed) = pool_seed {
+          data.extend_from_slice(pool_seed);
+        }
+      }
+      Self::TransferOwnershipBatch { count } => {
+        data.push(32);
+        data.push(*count);
+      }
+      Self::GetClaimable => data.push(33),
+      Self::AllowMint => data.push(34),
+      Self::DisallowMint => data.push(35),
+      Self::GetPoolTiming => data.push(36),
+      Self::ExitPool => data.push(37),
+      Self::SetActivation {
+        activation_timestamp,
+      } => {
+        data.push(38);
+        data.extend_from_slice(&activation_timestamp.to_le_bytes());
+      }
+      Self::SetMaxAccrualPeriods {
+        max_accrual_periods,
+      } => {
+        data.push(39);
+        data.extend_from_slice(&max_accrual_periods.to_le_bytes());
+      }
+      Self::GetRunwaySeed { days } => {
+        data.push(40);
+        data.extend_from_slice(&days.to_le_bytes());
+      }
+      Self::SetCrankTip { crank_tip } => {
+        data.push(41);
+        data.extend_from_slice(&crank_tip.to_le_bytes());
+      }
+      Self::SetDefaultSenAccount => data.push(42),
+      Self::SetMinClientVersion { min_client_version } => {
+        data.push(43);
+        data.push(*min_client_version);
+      }
+      Self::SetVestingDuration { vesting_duration } => {
+        data.push(44);
+        data.extend_from_slice(&vesting_duration.to_le_bytes());
+      }
+      Self::ClaimVested => data.push(45),
+      Self::GetTvlBatch { count } => {
+        data.push(46);
+        data.push(*count);
+      }
+      Self::IsAccountInitialized => data.push(47),
+      Self::ClearWithdrawOnly => data.push(48),
+      Self::ShiftGenesis { delta_seconds } => {
+        data.push(49);
+        data.extend_from_slice(&delta_seconds.to_le_bytes());
+      }
+      Self::GarbageCollectPool => data.push(50),
+      Self::HarvestWithCreate => data.push(51),
+      Self::GetLayout => data.push(52),
+      Self::SetDistribution { count, weights_bps } => {
+        data.push(53);
+        data.push(*count);
+        for weight in weights_bps.iter().take(*count as usize) {
+          data.extend_from_slice(&weight.to_le_bytes());
+        }
+      }
+      Self::Distribute { amount } => {
+        data.push(54);
+        data.extend_from_slice(&amount.to_le_bytes());
+      }
+      Self::HarvestSol => data.push(55),
+      Self::InitializeSecurityCouncil => data.push(56),
+    }
+    data
+  }
+
+  // Instruction data of a staker instruction sent by a versioned client
+  pub fn pack_with_client_version(&self, client_version: u8) -> Vec<u8> {
+    let mut data = vec![CLIENT_VERSION_PREFIX, client_version];
+    data.extend_from_slice(&self.pack());
+    data
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  fn decode(data: &[u8]) -> Result<(AppInstruction, Option<u8>), ProgramError> {
+    let (data, version) = AppInstruction::split_client_version(data)?;
+    Ok((AppInstruction::unpack(data)?, version))
+  }
+
+  #[test]
+  fn test_stake_payload_lengths_are_never_read_as_a_version() {
+    let amount = 7u64.to_le_bytes();
+    let reference = 9u64.to_le_bytes();
+    let key = [3u8; 16];
+    for tag in [2u8, 3].iter() {
+      for (with_reference, with_key) in
+        [(false, false), (true, false), (false, true), (true, true)].iter()
+      {
+        let mut data = vec![*tag];
+        data.extend_from_slice(&amount);
+        if *with_reference {
+          data.extend_from_slice(&reference);
+        }
+        if *with_key {
+          data.extend_from_slice(&key);
+        }
+        let (ix, version) = decode(&data).unwrap();
+        let (ix_amount, ix_reference, ix_key) = match ix {
+          AppInstruction::Stake {
+            amount,
+            reference,
+            idempotency_key,
+          }
+          | AppInstruction::Unstake {
+            amount,
+            reference,
+            idempotency_key,
+          } => (amount, reference, idempotency_key),
+          _ => panic!("unexpected instruction"),
+        };
+        assert_eq!(version, Some(0));
+        assert_eq!(ix_amount, 7);
+        assert_eq!(ix_reference, if *with_reference { Some(9) } else { None });
+        assert_eq!(ix_key, if *with_key { Some(key) } else { None });
+        // The same payload behind the prefix decodes alike
+        let mut versioned = vec![CLIENT_VERSION_PREFIX, 5];
+        versioned.extend_from_slice(&data);
+        assert_eq!(decode(&versioned).unwrap(), (ix, Some(5)));
+        // A trailing byte is a malformed payload, not a version
+        data.push(5);
+        assert_eq!(
+          decode(&data).unwrap_err(),
+          AppError::InvalidInstruction.into()
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn test_stake_reference_encodes_after_the_amount() {
+    // Tag, amount then reference, all little-endian
+    let ix = AppInstruction::Unstake {
+      amount: 0x0102,
+      reference: Some(0x0a0b),
+      idempotency_key: None,
+    };
+    let data = ix.pack();
+    assert_eq!(
+      data,
+      vec![3, 2, 1, 0, 0, 0, 0, 0, 0, 11, 10, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(AppInstruction::unpack(&data).unwrap(), ix);
+    // The legacy 9-byte payload reads as no reference
+    assert_eq!(
+      AppInstruction::unpack(&data[..9]).unwrap(),
+      AppInstruction::Unstake {
+        amount: 0x0102,
+        reference: None,
+        idempotency_key: None,
+      }
+    );
+  }
+
+  #[test]
+  fn test_overlong_stake_payloads_are_rejected() {
+    for tag in [2u8, 3].iter() {
+      for len in [10, 16, 18, 40, 41, 256].iter() {
+        let mut data = vec![0u8; *len];
+        data[0] = *tag;
+        assert_eq!(
+          AppInstruction::unpack(&data).unwrap_err(),
+          AppError::InvalidInstruction.into(),
+          "{} bytes",
+          len
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn test_harvest_memo_is_never_read_as_a_version() {
+    assert_eq!(
+      decode(&[4, 1]).unwrap(),
+      (
+        AppInstruction::Harvest {
+          memo: true,
+          idempotency_key: None
+        },
+        Some(0)
+      )
+    );
+    assert_eq!(
+      decode(&[CLIENT_VERSION_PREFIX, 1, 4]).unwrap(),
+      (
+        AppInstruction::Harvest {
+          memo: false,
+          idempotency_key: None
+        },
+        Some(1)
+      )
+    );
+    assert!(decode(&[4, 1, 1]).is_err());
+  }
+
+  #[test]
+  fn test_client_version_prefix_is_for_staker_instructions_only() {
+    // Owner instructions carry no version
+    assert_eq!(
+      AppInstruction::split_client_version(&[5]).unwrap(),
+      (&[5u8][..], None)
+    );
+    assert_eq!(
+      AppInstruction::split_client_version(&[CLIENT_VERSION_PREFIX, 1, 5]).unwrap_err(),
+      AppError::InvalidInstruction.into()
+    );
+    assert_eq!(
+      AppInstruction::split_client_version(&[CLIENT_VERSION_PREFIX, 1]).unwrap_err(),
+      AppError::InvalidInstruction.into()
+    );
+    assert_eq!(
+      AppInstruction::unpack(&[CLIENT_VERSION_PREFIX]).unwrap_err(),
+      AppError::InvalidInstruction.into()
+    );
+  }
+
+  pub(crate) fn samples() -> Vec<AppInstruction> {
+    let mut weights_bps = [0; MAX_DISTRIBUTION_CHILDREN];
+    weights_bps[0] = 4000;
+    weights_bps[1] = 6000;
+    vec![
+      AppInstruction::InitializeStakePool {
+        reward: 10,
+        period: 60,
+        genesis_timestamp: 0,
+        allow_pre_stake: false,
+        pool_seed: None,
+        precision_exp: PRECISION_EXP,
+      },
+      AppInstruction::InitializeStakePool {
+        reward: 10,
+        period: 60,
+        genesis_timestamp: -5,
+        allow_pre_stake: true,
+        pool_seed: Some([9; 32]),
+        precision_exp: 24,
+      },
+      AppInstruction::InitializeStakePool {
+        reward: 10,
+        period: 60,
+        genesis_timestamp: 1000,
+        allow_pre_stake: true,
+        pool_seed: None,
+        precision_exp: 30,
+      },
+      AppInstruction::InitializeAccounts,
+      AppInstruction::Stake {
+        amount: 1,
+        reference: Some(2),
+        idempotency_key: Some([3; 16]),
+      },
+      AppInstruction::Unstake {
+        amount: u64::MAX,
+        reference: None,
+        idempotency_key: Some([1; 16]),
+      },
+      AppInstruction::FreezeStakePool,
+      AppInstruction::ThawStakePool,
+      AppInstruction::Seed { amount: 5 },
+      AppInstruction::Unseed { amount: 6 },
+      AppInstruction::TransferStakePoolOwnership,
+      AppInstruction::CloseDebt,
+      AppInstruction::CloseStakePool,
+      AppInstruction::RescaleCompensation {
+        old_precision: 18,
+        new_precision: 24,
+      },
+      AppInstruction::GetStoredDebt,
+      AppInstruction::CrankSeed,
+      AppInstruction::UpdateCrankSeed {
+        max_crank_amount: 7,
+        crank_interval: 8,
+      },
+      AppInstruction::InitializeContribution,
+      AppInstruction::WithdrawSeed { amount: 9 },
+      AppInstruction::RepairShareMint,
+      AppInstruction::InitializeRegistry,
+      AppInstruction::RegisterExistingPool,
+      AppInstruction::DelistPool { index: 3 },
+      AppInstruction::SetRewardFloor {
+        min_reward_per_share: u128::MAX,
+      },
+      AppInstruction::SetUnseedLimit {
+        max_unseed_bps_per_day: 500,
+      },
+      AppInstruction::GetRewardMintInfo,
+      AppInstruction::SetUnseedDestination,
+      AppInstruction::SetHarvestInterval {
+        min_harvest_interval: 60,
+      },
+      AppInstruction::GetEffectiveReward,
+      AppInstruction::HarvestAndSeed,
+      AppInstruction::UpdatePool,
+      AppInstruction::MigrateStakePool,
+      AppInstruction::CloneStakePool {
+        genesis_timestamp: 0,
+        pool_seed: None,
+      },
+      AppInstruction::CloneStakePool {
+        genesis_timestamp: 7,
+        pool_seed: Some([2; 32]),
+      },
+      AppInstruction::TransferOwnershipBatch { count: 4 },
+      AppInstruction::GetClaimable,
+      AppInstruction::AllowMint,
+      AppInstruction::DisallowMint,
+      AppInstruction::GetPoolTiming,
+      AppInstruction::ExitPool,
+      AppInstruction::SetActivation {
+        activation_timestamp: -1,
+      },
+      AppInstruction::SetMaxAccrualPeriods {
+        max_accrual_periods: 12,
+      },
+      AppInstruction::GetRunwaySeed { days: 30 },
+      AppInstruction::SetCrankTip { crank_tip: 1 },
+      AppInstruction::SetDefaultSenAccount,
+      AppInstruction::SetMinClientVersion {
+        min_client_version: 2,
+      },
+      AppInstruction::SetVestingDuration {
+        vesting_duration: 86400,
+      },
+      AppInstruction::ClaimVested,
+      AppInstruction::GetTvlBatch { count: 2 },
+      AppInstruction::IsAccountInitialized,
+      AppInstruction::ClearWithdrawOnly,
+      AppInstruction::ShiftGenesis {
+        delta_seconds: -3600,
+      },
+      AppInstruction::GarbageCollectPool,
+      AppInstruction::HarvestWithCreate,
+      AppInstruction::GetLayout,
+      AppInstruction::SetDistribution {
+        count: 2,
+        weights_bps,
+      },
+      AppInstruction::Distribute { amount: 100 },
+      AppInstruction::HarvestSol,
+      AppInstruction::InitializeSecurityCouncil,
+    ]
+  }
+
+  #[test]
+  fn test_every_instruction_round_trips() {
+    for ix in samples() {
+      let data = ix.pack();
+      assert_eq!(data.len(), ix.packed_len());
+      assert_eq!(AppInstruction::unpack(&data).unwrap(), ix);
+      let expected_version = if AppInstruction::is_user_tag(data[0]) {
+        Some(0)
+      } else {
+        None
+      };
+      assert_eq!(decode(&data).unwrap(), (ix, expected_version));
+    }
+  }
+
+  #[test]
+  fn test_harvest_round_trips_every_memo_key_and_version() {
+    for memo in [false, true].iter() {
+      for idempotency_key in [None, Some([0; 16]), Some([1; 16]), Some([0xff; 16])].iter() {
+        for client_version in [None, Some(0), Some(1), Some(4), Some(17), Some(0xff)].iter() {
+          let ix = AppInstruction::Harvest {
+            memo: *memo,
+            idempotency_key: *idempotency_key,
+          };
+          let data = match client_version {
+            Some(client_version) => ix.pack_with_client_version(*client_version),
+            None => ix.pack(),
+          };
+          assert_eq!(
+            decode(&data).unwrap(),
+            (ix, Some(client_version.unwrap_or(0)))
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_staker_instructions_round_trip_every_version() {
+    for ix in samples() {
+      let data = ix.pack();
+      if !AppInstruction::is_user_tag(data[0]) {
+        continue;
+      }
+      for client_version in 0..=u8::MAX {
+        let data = ix.pack_with_client_version(client_version);
+        assert_eq!(decode(&data).unwrap(), (ix.clone(), Some(client_version)));
+      }
+    }
+  }
 }
@@ -0,0 +1,17 @@
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+pub struct IMEMO {}
+
+impl IMEMO {
+  ///
+  /// Memo
+  ///
+  pub fn memo(memo: &str, program_id: Pubkey) -> Result<Instruction, ProgramError> {
+    // No signer is required, the memo is the whole data
+    Ok(Instruction {
+      program_id,
+      accounts: vec![],
+      data: memo.as_bytes().to_vec(),
+    })
+  }
+}
@@ -24,14 +24,15 @@ impl ISPLATA {
     // Build data
     let data = Vec::with_capacity(size_of::<Self>());
     // Build accounts
-    let mut accounts = Vec::with_capacity(7);
-    accounts.push(AccountMeta::new(funding_acc, true));
-    accounts.push(AccountMeta::new(target_acc, false));
-    accounts.push(AccountMeta::new_readonly(owner, false));
-    accounts.push(AccountMeta::new_readonly(mint_acc, false));
-    accounts.push(AccountMeta::new_readonly(system_program, false));
-    accounts.push(AccountMeta::new_readonly(splt_program, false));
-    accounts.push(AccountMeta::new_readonly(sysvar_rent_acc, false));
+    let accounts = vec![
+      AccountMeta::new(funding_acc, true),
+      AccountMeta::new(target_acc, false),
+      AccountMeta::new_readonly(owner, false),
+      AccountMeta::new_readonly(mint_acc, false),
+      AccountMeta::new_readonly(system_program, false),
+      AccountMeta::new_readonly(splt_program, false),
+      AccountMeta::new_readonly(sysvar_rent_acc, false),
+    ];
     // Return
     Ok(Instruction {
       program_id,
@@ -28,9 +28,10 @@ impl ISPLT {
     data.push(1);
     data.extend_from_slice(&freeze_authority.to_bytes());
     // Build accounts
-    let mut accounts = Vec::with_capacity(2);
-    accounts.push(AccountMeta::new(mint_acc, false));
-    accounts.push(AccountMeta::new_readonly(sysvar_rent_acc, false));
+    let accounts = vec![
+      AccountMeta::new(mint_acc, false),
+      AccountMeta::new_readonly(sysvar_rent_acc, false),
+    ];
     // Return
     Ok(Instruction {
       program_id,
@@ -53,11 +54,12 @@ impl ISPLT {
     // InitializeAccount - Code 1
     data.push(1);
     // Build accounts
-    let mut accounts = Vec::with_capacity(4);
-    accounts.push(AccountMeta::new(target_acc, false));
-    accounts.push(AccountMeta::new_readonly(mint_acc, false));
-    accounts.push(AccountMeta::new_readonly(owner, false));
-    accounts.push(AccountMeta::new_readonly(sysvar_rent_acc, false));
+    let accounts = vec![
+      AccountMeta::new(target_acc, false),
+      AccountMeta::new_readonly(mint_acc, false),
+      AccountMeta::new_readonly(owner, false),
+      AccountMeta::new_readonly(sysvar_rent_acc, false),
+    ];
     // Return
     Ok(Instruction {
       program_id,
@@ -81,10 +83,11 @@ impl ISPLT {
     data.push(3);
     data.extend_from_slice(&amount.to_le_bytes());
     // Build accounts
-    let mut accounts = Vec::with_capacity(3);
-    accounts.push(AccountMeta::new(src_acc, false));
-    accounts.push(AccountMeta::new(dst_acc, false));
-    accounts.push(AccountMeta::new_readonly(owner, true));
+    let accounts = vec![
+      AccountMeta::new(src_acc, false),
+      AccountMeta::new(dst_acc, false),
+      AccountMeta::new_readonly(owner, true),
+    ];
     // Return
     Ok(Instruction {
       program_id,
@@ -108,10 +111,11 @@ impl ISPLT {
     data.push(7);
     data.extend_from_slice(&amount.to_le_bytes());
     // Build accounts
-    let mut accounts = Vec::with_capacity(3);
-    accounts.push(AccountMeta::new(mint_acc, false));
-    accounts.push(AccountMeta::new(dst_acc, false));
-    accounts.push(AccountMeta::new_readonly(owner, true));
+    let accounts = vec![
+      AccountMeta::new(mint_acc, false),
+      AccountMeta::new(dst_acc, false),
+      AccountMeta::new_readonly(owner, true),
+    ];
     // Return
     Ok(Instruction {
       program_id,
@@ -135,10 +139,45 @@ impl ISPLT {
     data.push(8);
     data.extend_from_slice(&amount.to_le_bytes());
     // Build accounts
-    let mut accounts = Vec::with_capacity(3);
-    accounts.push(AccountMeta::new(src_acc, false));
-    accounts.push(AccountMeta::new(mint_acc, false));
-    accounts.push(AccountMeta::new_readonly(owner, true));
+    let accounts = vec![
+      AccountMeta::new(src_acc, false),
+      AccountMeta::new(mint_acc, false),
+      AccountMeta::new_readonly(owner, true),
+    ];
+    // Return
+    Ok(Instruction {
+      program_id,
+      accounts,
+      data,
+    })
+  }
+  ///
+  /// Set authority
+  ///
+  pub fn set_authority(
+    authority_type: u8,
+    target_acc: Pubkey,
+    new_authority: Option<Pubkey>,
+    owner: Pubkey,
+    program_id: Pubkey,
+  ) -> Result<Instruction, ProgramError> {
+    // Build data
+    let mut data = Vec::with_capacity(size_of::<Self>());
+    // SetAuthority - Code 6
+    data.push(6);
+    data.push(authority_type);
+    match new_authority {
+      Some(new_authority) => {
+        data.push(1);
+        data.extend_from_slice(&new_authority.to_bytes());
+      }
+      None => data.push(0),
+    }
+    // Build accounts
+    let accounts = vec![
+      AccountMeta::new(target_acc, false),
+      AccountMeta::new_readonly(owner, true),
+    ];
     // Return
     Ok(Instruction {
       program_id,
@@ -160,10 +199,11 @@ impl ISPLT {
     // Transfer - Code 9
     data.push(9);
     // Build accounts
-    let mut accounts = Vec::with_capacity(3);
-    accounts.push(AccountMeta::new(src_acc, false));
-    accounts.push(AccountMeta::new(dst_acc, false));
-    accounts.push(AccountMeta::new_readonly(owner, true));
+    let accounts = vec![
+      AccountMeta::new(src_acc, false),
+      AccountMeta::new(dst_acc, false),
+      AccountMeta::new_readonly(owner, true),
+    ];
     // Return
     Ok(Instruction {
       program_id,
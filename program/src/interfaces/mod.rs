@@ -1,4 +1,6 @@
+pub mod imemo;
 pub mod isplata;
 pub mod isplt;
+pub mod xmemo;
 pub mod xsplata;
 pub mod xsplt;
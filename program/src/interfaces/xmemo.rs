@@ -0,0 +1,25 @@
+use crate::interfaces::imemo::IMEMO;
+use solana_program::{
+  account_info::AccountInfo, entrypoint::ProgramResult, program::invoke, pubkey::Pubkey,
+};
+
+///
+/// SPL Memo program, MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr
+///
+pub const MEMO_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+  5, 74, 83, 90, 153, 41, 33, 6, 77, 36, 232, 113, 96, 218, 56, 124, 124, 53, 181, 221, 188, 146,
+  187, 129, 228, 31, 168, 64, 65, 5, 68, 141,
+]);
+
+pub struct XMEMO {}
+
+impl XMEMO {
+  ///
+  /// Memo
+  ///
+  pub fn memo<'a>(memo: &str, memo_program: &AccountInfo<'a>) -> ProgramResult {
+    let ix = IMEMO::memo(memo, *memo_program.key)?;
+    invoke(&ix, &[memo_program.clone()])?;
+    Ok(())
+  }
+}
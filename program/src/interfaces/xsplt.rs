@@ -1,6 +1,6 @@
 use crate::interfaces::isplt::ISPLT;
 use solana_program::{
-  account_info::AccountInfo, entrypoint::ProgramResult, program::invoke_signed,
+  account_info::AccountInfo, entrypoint::ProgramResult, program::invoke_signed, pubkey::Pubkey,
 };
 
 pub struct XSPLT {}
@@ -159,6 +159,31 @@ impl XSPLT {
     Ok(())
   }
   ///
+  /// Set authority
+  ///
+  pub fn set_authority<'a>(
+    authority_type: u8,
+    target_acc: &AccountInfo<'a>,
+    new_authority: Option<Pubkey>,
+    owner: &AccountInfo<'a>,
+    splt_program: &AccountInfo<'a>,
+    seed: &[&[&[u8]]],
+  ) -> ProgramResult {
+    let ix = ISPLT::set_authority(
+      authority_type,
+      *target_acc.key,
+      new_authority,
+      *owner.key,
+      *splt_program.key,
+    )?;
+    invoke_signed(
+      &ix,
+      &[target_acc.clone(), owner.clone(), splt_program.clone()],
+      seed,
+    )?;
+    Ok(())
+  }
+  ///
   /// Close account
   ///
   pub fn close_account<'a>(
@@ -1,5 +1,8 @@
 #![feature(array_map, array_zip)]
 
+#[cfg(feature = "client")]
+pub mod client;
+pub mod context;
 pub mod entrypoint;
 pub mod error;
 pub mod helper;
@@ -7,3 +10,16 @@ pub mod instruction;
 pub mod interfaces;
 pub mod processor;
 pub mod schema;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+
+///
+/// Precision of fractional rewards and compensation
+///
+pub const PRECISION: u128 = 1000000000000000000; // 10^18
+
+///
+/// Bounds of the precision exponent chosen per stake pool
+///
+pub const PRECISION_EXP: u8 = 18;
+pub const MAX_PRECISION_EXP: u8 = 36;
@@ -1,26 +1,60 @@
+use crate::context::{
+  account_specs, AllowMintContext, ClaimVestedContext, ClearWithdrawOnlyContext,
+  CloneStakePoolContext, CloseDebtContext, CloseStakePoolContext, CrankSeedContext,
+  DelistPoolContext, DisallowMintContext, DistributeContext, ExitPoolContext,
+  GarbageCollectPoolContext, GetClaimableContext, GetEffectiveRewardContext, GetPoolTimingContext,
+  GetRewardMintInfoContext, GetRunwaySeedContext, GetStoredDebtContext, HarvestAndSeedContext,
+  HarvestContext, HarvestSolContext, HarvestWithCreateContext, InitializeAccountsContext,
+  InitializeContributionContext, InitializeRegistryContext, InitializeSecurityCouncilContext,
+  InitializeStakePoolContext, IsAccountInitializedContext, RegisterExistingPoolContext,
+  RepairShareMintContext, SeedContext, SetDefaultSenAccountContext, SetDistributionContext,
+  SetUnseedDestinationContext, StakeContext, StakePoolOwnerContext, TransferOwnershipBatchContext,
+  TransferStakePoolOwnershipContext, UnseedContext, UnstakeContext, UpdatePoolContext,
+  WithdrawSeedContext,
+};
 use crate::error::AppError;
-use crate::helper::{pattern::Pattern, pubutil::Boolean};
-use crate::instruction::AppInstruction;
-use crate::interfaces::{xsplata::XSPLATA, xsplt::XSPLT};
+use crate::helper::{
+  events,
+  params::PoolParams,
+  pattern::Pattern,
+  pubutil::{associated_token_address, Boolean, NATIVE_MINT},
+  units::{ScaledAmount, ScaledComp, ScaledDebt, Shares, TokenAmount},
+};
+use crate::instruction::{AppInstruction, MAX_TRANSFER_OWNERSHIP_BATCH, MAX_TVL_BATCH};
+use crate::interfaces::{
+  xmemo::{MEMO_PROGRAM_ID, XMEMO},
+  xsplata::XSPLATA,
+  xsplt::XSPLT,
+};
 use crate::schema::{
   account::Account,
+  contribution::Contribution,
+  council::SecurityCouncil,
   debt::Debt,
+  distribution::{DistributionConfig, MAX_DISTRIBUTION_CHILDREN},
+  layout::{self, DEBT_LAYOUT, STAKE_POOL_LAYOUT},
   mint::Mint,
-  stake_pool::{StakePool, StakePoolState},
+  registry::{MintWhitelist, Registry, RegistryPage, MAX_ALLOWED_MINTS, REGISTRY_PAGE_SIZE},
+  stake_pool::{
+    StakePool, StakePoolState, ACCUMULATOR_VERSION, GC_IDLE_INTERVAL, GC_INCENTIVE_BPS,
+    MAX_GENESIS_DELAY, STATE_CHANGE_INTERVAL, UNSEED_WINDOW,
+  },
 };
 use solana_program::{
-  account_info::{next_account_info, AccountInfo},
+  account_info::AccountInfo,
   clock::Clock,
   entrypoint::ProgramResult,
   msg,
   program::{invoke, invoke_signed},
   program_error::ProgramError,
+  program_option::COption,
   program_pack::{IsInitialized, Pack},
   pubkey::{Pubkey, PubkeyError},
   rent::Rent,
   system_instruction,
   sysvar::Sysvar,
 };
+use std::fmt::Display;
 
 pub struct Processor {}
 
@@ -30,11 +64,32 @@ impl Processor {
     accounts: &[AccountInfo],
     instruction_data: &[u8],
   ) -> ProgramResult {
+    let (instruction_data, client_version) =
+      AppInstruction::split_client_version(instruction_data)?;
     let instruction = AppInstruction::unpack(instruction_data)?;
+    if let Some(client_version) = client_version {
+      Self::is_supported_client(client_version, &instruction, program_id, accounts)?;
+    }
     match instruction {
-      AppInstruction::InitializeStakePool { reward, period } => {
+      AppInstruction::InitializeStakePool {
+        reward,
+        period,
+        genesis_timestamp,
+        allow_pre_stake,
+        pool_seed,
+        precision_exp,
+      } => {
         msg!("Calling InitializeStakePool function");
-        Self::initialize_stake_pool(reward, period, program_id, accounts)
+        Self::initialize_stake_pool(
+          reward,
+          period,
+          genesis_timestamp,
+          allow_pre_stake,
+          pool_seed,
+          precision_exp,
+          program_id,
+          accounts,
+        )
       }
 
       AppInstruction::InitializeAccounts {} => {
@@ -42,19 +97,30 @@ impl Processor {
         Self::initialize_accounts(program_id, accounts)
       }
 
-      AppInstruction::Stake { amount } => {
+      AppInstruction::Stake {
+        amount,
+        reference,
+        idempotency_key,
+      } => {
         msg!("Calling Stake function");
-        Self::stake(amount, program_id, accounts)
+        Self::stake(amount, reference, idempotency_key, program_id, accounts)
       }
 
-      AppInstruction::Unstake { amount } => {
+      AppInstruction::Unstake {
+        amount,
+        reference,
+        idempotency_key,
+      } => {
         msg!("Calling Unstake function");
-        Self::unstake(amount, program_id, accounts)
+        Self::unstake(amount, reference, idempotency_key, program_id, accounts)
       }
 
-      AppInstruction::Harvest {} => {
+      AppInstruction::Harvest {
+        memo,
+        idempotency_key,
+      } => {
         msg!("Calling Harvest function");
-        Self::harvest(program_id, accounts)
+        Self::harvest(memo, idempotency_key, program_id, accounts)
       }
 
       AppInstruction::FreezeStakePool {} => {
@@ -91,148 +157,300 @@ impl Processor {
         msg!("Calling CloseStakePool function");
         Self::close_stake_pool(program_id, accounts)
       }
+
+      AppInstruction::RescaleCompensation {
+        old_precision,
+        new_precision,
+      } => {
+        msg!("Calling RescaleCompensation function");
+        Self::rescale_compensation(old_precision, new_precision, program_id, accounts)
+      }
+
+      AppInstruction::GetStoredDebt {} => {
+        msg!("Calling GetStoredDebt function");
+        Self::get_stored_debt(program_id, accounts)
+      }
+
+      AppInstruction::CrankSeed {} => {
+        msg!("Calling CrankSeed function");
+        Self::crank_seed(program_id, accounts)
+      }
+
+      AppInstruction::UpdateCrankSeed {
+        max_crank_amount,
+        crank_interval,
+      } => {
+        msg!("Calling UpdateCrankSeed function");
+        Self::update_crank_seed(max_crank_amount, crank_interval, program_id, accounts)
+      }
+
+      AppInstruction::InitializeContribution {} => {
+        msg!("Calling InitializeContribution function");
+        Self::initialize_contribution(program_id, accounts)
+      }
+
+      AppInstruction::WithdrawSeed { amount } => {
+        msg!("Calling WithdrawSeed function");
+        Self::withdraw_seed(amount, program_id, accounts)
+      }
+
+      AppInstruction::RepairShareMint {} => {
+        msg!("Calling RepairShareMint function");
+        Self::repair_share_mint(program_id, accounts)
+      }
+
+      AppInstruction::InitializeRegistry {} => {
+        msg!("Calling InitializeRegistry function");
+        Self::initialize_registry(program_id, accounts)
+      }
+
+      AppInstruction::RegisterExistingPool {} => {
+        msg!("Calling RegisterExistingPool function");
+        Self::register_existing_pool(program_id, accounts)
+      }
+
+      AppInstruction::DelistPool { index } => {
+        msg!("Calling DelistPool function");
+        Self::delist_pool(index, program_id, accounts)
+      }
+
+      AppInstruction::SetRewardFloor {
+        min_reward_per_share,
+      } => {
+        msg!("Calling SetRewardFloor function");
+        Self::set_reward_floor(min_reward_per_share, program_id, accounts)
+      }
+
+      AppInstruction::SetUnseedLimit {
+        max_unseed_bps_per_day,
+      } => {
+        msg!("Calling SetUnseedLimit function");
+        Self::set_unseed_limit(max_unseed_bps_per_day, program_id, accounts)
+      }
+
+      AppInstruction::GetRewardMintInfo {} => {
+        msg!("Calling GetRewardMintInfo function");
+        Self::get_reward_mint_info(program_id, accounts)
+      }
+
+      AppInstruction::SetUnseedDestination {} => {
+        msg!("Calling SetUnseedDestination function");
+        Self::set_unseed_destination(program_id, accounts)
+      }
+
+      AppInstruction::SetHarvestInterval {
+        min_harvest_interval,
+      } => {
+        msg!("Calling SetHarvestInterval function");
+        Self::set_harvest_interval(min_harvest_interval, program_id, accounts)
+      }
+
+      AppInstruction::GetEffectiveReward {} => {
+        msg!("Calling GetEffectiveReward function");
+        Self::get_effective_reward(program_id, accounts)
+      }
+
+      AppInstruction::HarvestAndSeed {} => {
+        msg!("Calling HarvestAndSeed function");
+        Self::harvest_and_seed(program_id, accounts)
+      }
+
+      AppInstruction::UpdatePool {} => {
+        msg!("Calling UpdatePool function");
+        Self::update_stake_pool(program_id, accounts)
+      }
+
+      AppInstruction::MigrateStakePool {} => {
+        msg!("Calling MigrateStakePool function");
+        Self::migrate_stake_pool(program_id, accounts)
+      }
+
+      AppInstruction::CloneStakePool {
+        genesis_timestamp,
+        pool_seed,
+      } => {
+        msg!("Calling CloneStakePool function");
+        Self::clone_stake_pool(genesis_timestamp, pool_seed, program_id, accounts)
+      }
+
+      AppInstruction::TransferOwnershipBatch { count } => {
+        msg!("Calling TransferOwnershipBatch function");
+        Self::transfer_ownership_batch(count, program_id, accounts)
+      }
+
+      AppInstruction::GetClaimable {} => {
+        msg!("Calling GetClaimable function");
+        Self::get_claimable(program_id, accounts)
+      }
+
+      AppInstruction::AllowMint {} => {
+        msg!("Calling AllowMint function");
+        Self::allow_mint(program_id, accounts)
+      }
+
+      AppInstruction::DisallowMint {} => {
+        msg!("Calling DisallowMint function");
+        Self::disallow_mint(program_id, accounts)
+      }
+
+      AppInstruction::GetPoolTiming {} => {
+        msg!("Calling GetPoolTiming function");
+        Self::get_pool_timing(program_id, accounts)
+      }
+
+      AppInstruction::ExitPool {} => {
+        msg!("Calling ExitPool function");
+        Self::exit_pool(program_id, accounts)
+      }
+
+      AppInstruction::SetActivation {
+        activation_timestamp,
+      } => {
+        msg!("Calling SetActivation function");
+        Self::set_activation(activation_timestamp, program_id, accounts)
+      }
+
+      AppInstruction::SetMaxAccrualPeriods {
+        max_accrual_periods,
+      } => {
+        msg!("Calling SetMaxAccrualPeriods function");
+        Self::set_max_accrual_periods(max_accrual_periods, program_id, accounts)
+      }
+
+      AppInstruction::GetRunwaySeed { days } => {
+        msg!("Calling GetRunwaySeed function");
+        Self::get_runway_seed(days, program_id, accounts)
+      }
+
+      AppInstruction::SetCrankTip { crank_tip } => {
+        msg!("Calling SetCrankTip function");
+        Self::set_crank_tip(crank_tip, program_id, accounts)
+      }
+
+      AppInstruction::SetDefaultSenAccount {} => {
+        msg!("Calling SetDefaultSenAccount function");
+        Self::set_default_sen_account(program_id, accounts)
+      }
+
+      AppInstruction::SetMinClientVersion { min_client_version } => {
+        msg!("Calling SetMinClientVersion function");
+        Self::set_min_client_version(min_client_version, program_id, accounts)
+      }
+
+      AppInstruction::SetVestingDuration { vesting_duration } => {
+        msg!("Calling SetVestingDuration function");
+        Self::set_vesting_duration(vesting_duration, program_id, accounts)
+      }
+
+      AppInstruction::ClaimVested {} => {
+        msg!("Calling ClaimVested function");
+        Self::claim_vested(program_id, accounts)
+      }
+
+      AppInstruction::GetTvlBatch { count } => {
+        msg!("Calling GetTvlBatch function");
+        Self::get_tvl_batch(count, program_id, accounts)
+      }
+
+      AppInstruction::IsAccountInitialized {} => {
+        msg!("Calling IsAccountInitialized function");
+        Self::is_account_initialized(program_id, accounts)
+      }
+
+      AppInstruction::ClearWithdrawOnly {} => {
+        msg!("Calling ClearWithdrawOnly function");
+        Self::clear_withdraw_only(program_id, accounts)
+      }
+
+      AppInstruction::ShiftGenesis { delta_seconds } => {
+        msg!("Calling ShiftGenesis function");
+        Self::shift_genesis(delta_seconds, program_id, accounts)
+      }
+
+      AppInstruction::GarbageCollectPool {} => {
+        msg!("Calling GarbageCollectPool function");
+        Self::garbage_collect_pool(program_id, accounts)
+      }
+
+      AppInstruction::HarvestWithCreate {} => {
+        msg!("Calling HarvestWithCreate function");
+        Self::harvest_with_create(program_id, accounts)
+      }
+
+      AppInstruction::GetLayout {} => {
+        msg!("Calling GetLayout function");
+        Self::get_layout(program_id, accounts)
+      }
+
+      AppInstruction::SetDistribution { count, weights_bps } => {
+        msg!("Calling SetDistribution function");
+        Self::set_distribution(count, weights_bps, program_id, accounts)
+      }
+
+      AppInstruction::Distribute { amount } => {
+        msg!("Calling Distribute function");
+        Self::distribute(amount, program_id, accounts)
+      }
+
+      AppInstruction::HarvestSol {} => {
+        msg!("Calling HarvestSol function");
+        Self::harvest_sol(program_id, accounts)
+      }
+
+      AppInstruction::InitializeSecurityCouncil {} => {
+        msg!("Calling InitializeSecurityCouncil function");
+        Self::initialize_security_council(program_id, accounts)
+      }
     }
   }
 
   pub fn initialize_stake_pool(
     reward: u64,
     period: u64,
+    genesis_timestamp: i64,
+    allow_pre_stake: bool,
+    pool_seed: Option<[u8; 32]>,
+    precision_exp: u8,
     program_id: &Pubkey,
     accounts: &[AccountInfo],
   ) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let payer = next_account_info(accounts_iter)?;
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let mint_share_acc = next_account_info(accounts_iter)?;
-    let proof_acc = next_account_info(accounts_iter)?; // program_id xor treasurer xor stake_pool_id
-
-    let mint_token_acc = next_account_info(accounts_iter)?;
-    let treasury_token_acc = next_account_info(accounts_iter)?;
-
-    let mint_sen_acc = next_account_info(accounts_iter)?;
-    let treasury_sen_acc = next_account_info(accounts_iter)?;
-    let treasurer = next_account_info(accounts_iter)?;
-    let system_program = next_account_info(accounts_iter)?;
-    let splt_program = next_account_info(accounts_iter)?;
-    let sysvar_rent_acc = next_account_info(accounts_iter)?;
-    let splata_program = next_account_info(accounts_iter)?;
-
-    // Rent stake pool account
-    Self::alloc_account(
-      StakePool::LEN,
-      stake_pool_acc,
-      payer,
+    let template = StakePool {
+      reward,
+      period,
+      allow_pre_stake,
+      precision_exp,
+      ..StakePool::default()
+    };
+    Self::create_stake_pool(
+      &template,
+      genesis_timestamp,
+      pool_seed,
       program_id,
-      sysvar_rent_acc,
-      system_program,
-      &[],
-    )?;
-    // Rent mint share account
-    Self::alloc_account(
-      Mint::LEN,
-      mint_share_acc,
-      payer,
-      splt_program.key,
-      sysvar_rent_acc,
-      system_program,
-      &[],
-    )?;
-
-    Self::is_program(program_id, &[stake_pool_acc])?;
-    Self::is_program(splt_program.key, &[mint_share_acc])?;
-    Self::is_signer(&[payer, stake_pool_acc, mint_share_acc])?;
-
-    let mut stake_pool_data = StakePool::unpack_unchecked(&stake_pool_acc.data.borrow())?;
-    let mint_share_data = Mint::unpack_unchecked(&mint_share_acc.data.borrow())?;
-    let seed: &[&[&[u8]]] = &[&[&Self::safe_seed(stake_pool_acc, treasurer, program_id)?[..]]];
-    if stake_pool_data.is_initialized() || mint_share_data.is_initialized() {
-      return Err(AppError::ConstructorOnce.into());
-    }
-    if *proof_acc.key != program_id.xor(&(stake_pool_acc.key.xor(treasurer.key))) {
-      return Err(AppError::UnmatchedPool.into());
-    }
-    if reward == 0 {
-      return Err(AppError::ZeroValue.into());
-    }
-
-    // Initialize treasury token
-    XSPLATA::initialize_account(
-      payer,
-      treasury_token_acc,
-      treasurer,
-      mint_token_acc,
-      system_program,
-      splt_program,
-      sysvar_rent_acc,
-      splata_program,
-      &[],
-    )?;
+      accounts,
+    )
+  }
 
-    // Initialize treasury sen
-    XSPLATA::initialize_account(
+  pub fn initialize_accounts(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let InitializeAccountsContext {
       payer,
-      treasury_sen_acc,
-      treasurer,
+      owner,
+      stake_pool_acc,
+      mint_share_acc,
       mint_sen_acc,
+      reward_acc,
+      share_acc,
+      debt_acc,
       system_program,
       splt_program,
       sysvar_rent_acc,
       splata_program,
-      &[],
-    )?;
-
-    // Initialize mint share
-    let mint_token_data = Mint::unpack_unchecked(&mint_token_acc.data.borrow())?;
-    XSPLT::initialize_mint(
-      mint_token_data.decimals,
-      mint_share_acc,
-      treasurer,
-      proof_acc,
-      sysvar_rent_acc,
-      splt_program,
-      seed,
-    )?;
-
-    // Update stake pool data
-    stake_pool_data.owner = *owner.key;
-    stake_pool_data.state = StakePoolState::Initialized;
-    stake_pool_data.genesis_timestamp = Self::current_timestamp()?;
-    stake_pool_data.total_shares = 0;
-    stake_pool_data.mint_share = *mint_share_acc.key;
-    stake_pool_data.mint_token = *mint_token_acc.key;
-    stake_pool_data.treasury_token = *treasury_token_acc.key;
-    stake_pool_data.reward = reward;
-    stake_pool_data.period = period;
-    stake_pool_data.compensation = 0;
-    stake_pool_data.mint_sen = *mint_sen_acc.key;
-    stake_pool_data.treasury_sen = *treasury_sen_acc.key;
-    StakePool::pack(stake_pool_data, &mut stake_pool_acc.data.borrow_mut())?;
-
-    Ok(())
-  }
-
-  pub fn initialize_accounts(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let payer = next_account_info(accounts_iter)?;
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let mint_share_acc = next_account_info(accounts_iter)?;
-    let mint_sen_acc = next_account_info(accounts_iter)?;
-
-    let reward_acc = next_account_info(accounts_iter)?;
-    let share_acc = next_account_info(accounts_iter)?;
-    let debt_acc = next_account_info(accounts_iter)?;
-
-    let system_program = next_account_info(accounts_iter)?;
-    let splt_program = next_account_info(accounts_iter)?;
-    let sysvar_rent_acc = next_account_info(accounts_iter)?;
-    let splata_program = next_account_info(accounts_iter)?;
-
-    Self::is_program(program_id, &[stake_pool_acc])?;
-    Self::is_signer(&[payer])?;
+    } = InitializeAccountsContext::load(program_id, accounts)?;
 
-    StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    // Positions in a frozen pool could not be staked into
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if stake_pool_data.is_frozen() {
+      return Err(AppError::FrozenPool.into());
+    }
 
     // Initialize reward account
     if (&reward_acc.data.borrow()).len() == 0 {
@@ -250,7 +468,8 @@ impl Processor {
     }
 
     // Initilized share account
-    if (&share_acc.data.borrow()).len() == 0 {
+    let share_recreated = (&share_acc.data.borrow()).len() == 0;
+    if share_recreated {
       XSPLATA::initialize_account(
         payer,
         share_acc,
@@ -276,6 +495,14 @@ impl Processor {
     if key != *debt_acc.key {
       return Err(AppError::InvalidOwner.into());
     }
+    // The share account was closed under a live debt, re-creating it is enough
+    if debt_acc.owner == program_id {
+      let debt_data = Debt::unpack_unchecked(&debt_acc.data.borrow())?;
+      if share_recreated && debt_data.is_initialized() && debt_data.account == *share_acc.key {
+        return Ok(());
+      }
+      return Err(AppError::ConstructorOnce.into());
+    }
     // Rent debt account
     let seed: &[&[u8]] = &[
       &owner.key.to_bytes(),
@@ -302,49 +529,140 @@ impl Processor {
     debt_data.owner = *owner.key;
     debt_data.account = *share_acc.key;
     debt_data.debt = 0;
+    debt_data.last_delay = 0;
+    debt_data.forfeit = 0;
+    debt_data.pending_shares = 0;
+    debt_data.last_harvest_timestamp = 0;
+    debt_data.last_settled_delay = 0;
+    debt_data.default_sen_account = Pubkey::default();
+    debt_data.vesting_amount = 0;
+    debt_data.vesting_start = 0;
+    debt_data.vesting_duration = 0;
+    debt_data.vested_amount = 0;
     debt_data.is_initialized = true;
-    Debt::pack(debt_data, &mut debt_acc.data.borrow_mut())?;
+    Self::pack_debt(debt_data, debt_acc)?;
 
     Ok(())
   }
 
-  pub fn stake(amount: u64, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let mint_share_acc = next_account_info(accounts_iter)?;
-
-    let src_acc = next_account_info(accounts_iter)?;
-    let treasury_token_acc = next_account_info(accounts_iter)?;
-
-    let share_acc = next_account_info(accounts_iter)?;
-    let debt_acc = next_account_info(accounts_iter)?;
-
-    let dst_sen_acc = next_account_info(accounts_iter)?;
-    let treasury_sen_acc = next_account_info(accounts_iter)?;
-
-    let treasurer = next_account_info(accounts_iter)?;
-    let splt_program = next_account_info(accounts_iter)?;
+  pub fn stake(
+    amount: u64,
+    reference: Option<u64>,
+    idempotency_key: Option<[u8; 16]>,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakeContext {
+      owner,
+      stake_pool_acc,
+      mint_share_acc,
+      src_acc,
+      treasury_token_acc,
+      share_acc,
+      debt_acc,
+      dst_sen_acc,
+      treasury_sen_acc,
+      treasurer,
+      splt_program,
+      mint_token_acc,
+    } = StakeContext::load(program_id, accounts)?;
 
-    Self::is_program(program_id, &[stake_pool_acc, debt_acc])?;
-    Self::is_signer(&[owner])?;
     Self::is_debt_owner(owner, debt_acc, stake_pool_acc, share_acc)?;
 
+    // The share account may have been closed after a full unstake
+    if (&share_acc.data.borrow()).len() == 0 {
+      let len = StakeContext::SPECS.len();
+      match (
+        accounts.get(len),
+        accounts.get(len + 1),
+        accounts.get(len + 2),
+        accounts.get(len + 3),
+      ) {
+        (Some(payer), Some(system_program), Some(sysvar_rent_acc), Some(splata_program)) => {
+          XSPLATA::initialize_account(
+            payer,
+            share_acc,
+            owner,
+            mint_share_acc,
+            system_program,
+            splt_program,
+            sysvar_rent_acc,
+            splata_program,
+            &[],
+          )?
+        }
+        _ => return Err(AppError::ShareAccountMissing.into()),
+      }
+    }
+
     let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
     let share_data = Account::unpack(&share_acc.data.borrow())?;
     let mut debt_data = Debt::unpack(&debt_acc.data.borrow())?;
-    let seed: &[&[&[u8]]] = &[&[&Self::safe_seed(stake_pool_acc, treasurer, program_id)?[..]]];
+    Self::record_idempotency_key(idempotency_key, &mut debt_data)?;
+    let seed: &[&[&[u8]]] =
+      &[&[&Self::treasurer_seed(stake_pool_acc, treasurer, &stake_pool_data)?[..]]];
     if stake_pool_data.mint_share != *mint_share_acc.key
       || stake_pool_data.treasury_token != *treasury_token_acc.key
+      || stake_pool_data.treasury_sen != *treasury_sen_acc.key
     {
       return Err(AppError::UnmatchedPool.into());
     }
+    Self::is_reward_account(dst_sen_acc, &stake_pool_data)?;
+    Self::is_reward_account(treasury_sen_acc, &stake_pool_data)?;
+    if stake_pool_data.mint_token != *mint_token_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    // Shares are minted 1:1 with the staked token
+    let mint_share_data = Mint::unpack(&mint_share_acc.data.borrow())?;
+    let mint_token_data = Mint::unpack(&mint_token_acc.data.borrow())?;
+    if mint_share_data.decimals != mint_token_data.decimals {
+      return Err(AppError::DecimalMismatch.into());
+    }
+    if mint_share_data.mint_authority != COption::Some(*treasurer.key) {
+      return Err(AppError::CorruptedShareMint.into());
+    }
+    // Refuse deposits into a tampered pool, unstake and harvest still let users exit.
+    // Initialization sets the proof as the freeze authority
+    let treasury_token_data = Account::unpack(&treasury_token_acc.data.borrow())?;
+    let proof = program_id.xor(&stake_pool_acc.key.xor(treasurer.key));
+    if mint_share_data.freeze_authority != COption::None
+      && mint_share_data.freeze_authority != COption::Some(proof)
+      && mint_share_data.freeze_authority != COption::Some(*treasurer.key)
+    {
+      return Err(AppError::CorruptedPool.into());
+    }
+    if treasury_token_data.close_authority != COption::None {
+      return Err(AppError::CorruptedPool.into());
+    }
     if stake_pool_data.is_frozen() {
       return Err(AppError::FrozenPool.into());
     }
+    // An underfunded pool takes no new stakes until the owner seeds and clears it
+    if stake_pool_data.withdraw_only {
+      return Err(AppError::WithdrawOnly.into());
+    }
+    // Positions may be opened before genesis only if the pool allows it
+    let launched = Self::current_timestamp()? >= stake_pool_data.genesis_timestamp;
+    if !launched && !stake_pool_data.allow_pre_stake {
+      return Err(AppError::NotLaunched.into());
+    }
+    // Operators may hold stakes back while they seed and verify the pool
+    if Self::current_timestamp()? < stake_pool_data.activation_timestamp {
+      return Err(AppError::PoolNotActive.into());
+    }
     if amount == 0 {
       return Err(AppError::ZeroValue.into());
     }
+    // Shares after the mint must stay backed 1:1 by the treasury after the deposit
+    let backing = treasury_token_data
+      .amount
+      .checked_add(amount)
+      .ok_or(AppError::Overflow)?;
+    for shares in [mint_share_data.supply, stake_pool_data.total_shares].iter() {
+      if shares.checked_add(amount).ok_or(AppError::Overflow)? > backing {
+        return Err(AppError::BackingInvariantViolation.into());
+      }
+    }
 
     // Stake token
     XSPLT::transfer(
@@ -355,60 +673,37 @@ impl Processor {
       splt_program,
       &[],
     )?;
-
-    // Get the basics
-    let shares = share_data.amount;
-    let debt = debt_data.debt;
-    let compensation = stake_pool_data.compensation;
-    let delay = Self::estimate_delay(stake_pool_data)?;
-    let reward = stake_pool_data.reward;
-    let current_total_shares = stake_pool_data.total_shares;
-    // Fully harvest
-    let next_total_shares = current_total_shares; // Harvest doesn't change the total shares
-    let (shares, debt, compensation) = Pattern::fully_harvest(
-      shares,
-      debt,
-      compensation,
-      delay,
-      reward,
-      current_total_shares,
-      next_total_shares,
-    )
-    .ok_or(AppError::Overflow)?;
-    let yeild = debt.checked_sub(debt_data.debt).ok_or(AppError::Overflow)? as u64;
-    // Fully unstake
-    let next_total_shares = current_total_shares
-      .checked_sub(shares)
-      .ok_or(AppError::Overflow)?;
-    let (_, debt, compensation) = Pattern::fully_unstake(
-      shares,
-      debt,
-      compensation,
-      delay,
-      reward,
-      current_total_shares,
-      next_total_shares,
-    )
-    .ok_or(AppError::Overflow)?;
-    // Fully stake
-    let shares = share_data
+    // Shares to mint must match the tokens actually received
+    let received = Account::unpack(&treasury_token_acc.data.borrow())?
       .amount
-      .checked_add(amount)
+      .checked_sub(treasury_token_data.amount)
       .ok_or(AppError::Overflow)?;
-    let current_total_shares = next_total_shares;
-    let next_total_shares = current_total_shares
-      .checked_add(shares)
+    if received != amount {
+      return Err(AppError::TransferAmountMismatch.into());
+    }
+
+    // Harvest then stake
+    let shares = Shares(share_data.amount);
+    let next_shares = shares
+      .checked_add(Shares::from_tokens(TokenAmount(amount)))
       .ok_or(AppError::Overflow)?;
-    let (_, debt, compensation) = Pattern::fully_stake(
-      shares,
-      debt,
-      compensation,
-      delay,
-      reward,
-      current_total_shares,
-      next_total_shares,
-    )
-    .ok_or(AppError::Overflow)?;
+    let yeild = Self::settle(shares, next_shares, &mut debt_data, &mut stake_pool_data)?;
+    let yeild = Self::vest(yeild, &mut debt_data, &mut stake_pool_data)?;
+    // New shares skip the in-progress period, there is none before genesis
+    if launched {
+      let TokenAmount(forfeit) = Pattern::estimate_forfeit(
+        stake_pool_data.reward,
+        ScaledAmount(stake_pool_data.min_reward_per_share),
+        Self::period_shares(&stake_pool_data)?,
+        Shares::from_tokens(TokenAmount(amount)),
+        stake_pool_data.precision(),
+      )
+      .ok_or(AppError::Overflow)?;
+      debt_data.forfeit = debt_data
+        .forfeit
+        .checked_add(forfeit)
+        .ok_or(AppError::Overflow)?;
+    }
 
     // Harvest
     XSPLT::transfer(
@@ -428,110 +723,79 @@ impl Processor {
       splt_program,
       seed,
     )?;
+    Self::is_settled_balance(share_acc, next_shares)?;
 
-    // Debt account
-    debt_data.debt = debt;
-    Debt::pack(debt_data, &mut debt_acc.data.borrow_mut())?;
-    // Stake pool account
-    stake_pool_data.total_shares = next_total_shares;
-    stake_pool_data.compensation = compensation;
-    StakePool::pack(stake_pool_data, &mut stake_pool_acc.data.borrow_mut())?;
+    // Churn statistics
+    stake_pool_data.cumulative_staked = stake_pool_data
+      .cumulative_staked
+      .checked_add(amount as u128)
+      .ok_or(AppError::Overflow)?;
+    Self::check_treasury_cover(treasury_sen_acc, &mut stake_pool_data)?;
+    Self::pack_debt(debt_data, debt_acc)?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+    // Client reference
+    if let Some(reference) = reference {
+      msg!("REFERENCE:{}", reference);
+    }
 
     Ok(())
   }
 
-  pub fn unstake(amount: u64, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let mint_share_acc = next_account_info(accounts_iter)?;
-
-    let dst_acc = next_account_info(accounts_iter)?;
-    let treasury_token_acc = next_account_info(accounts_iter)?;
-
-    let share_acc = next_account_info(accounts_iter)?;
-    let debt_acc = next_account_info(accounts_iter)?;
-
-    let dst_sen_acc = next_account_info(accounts_iter)?;
-    let treasury_sen_acc = next_account_info(accounts_iter)?;
-
-    let treasurer = next_account_info(accounts_iter)?;
-    let splt_program = next_account_info(accounts_iter)?;
+  pub fn unstake(
+    amount: u64,
+    reference: Option<u64>,
+    idempotency_key: Option<[u8; 16]>,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let UnstakeContext {
+      owner,
+      stake_pool_acc,
+      mint_share_acc,
+      dst_acc,
+      treasury_token_acc,
+      share_acc,
+      debt_acc,
+      dst_sen_acc,
+      treasury_sen_acc,
+      treasurer,
+      splt_program,
+    } = UnstakeContext::load(program_id, accounts)?;
 
-    Self::is_program(program_id, &[stake_pool_acc, debt_acc])?;
-    Self::is_signer(&[owner])?;
     Self::is_debt_owner(owner, debt_acc, stake_pool_acc, share_acc)?;
 
     let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
     let share_data = Account::unpack(&share_acc.data.borrow())?;
     let mut debt_data = Debt::unpack(&debt_acc.data.borrow())?;
-    let seed: &[&[&[u8]]] = &[&[&Self::safe_seed(stake_pool_acc, treasurer, program_id)?[..]]];
+    Self::record_idempotency_key(idempotency_key, &mut debt_data)?;
+    let seed: &[&[&[u8]]] =
+      &[&[&Self::treasurer_seed(stake_pool_acc, treasurer, &stake_pool_data)?[..]]];
     if stake_pool_data.mint_share != *mint_share_acc.key
       || stake_pool_data.treasury_token != *treasury_token_acc.key
       || stake_pool_data.treasury_sen != *treasury_sen_acc.key
     {
       return Err(AppError::UnmatchedPool.into());
     }
+    Self::is_reward_account(dst_sen_acc, &stake_pool_data)?;
+    Self::is_reward_account(treasury_sen_acc, &stake_pool_data)?;
     if stake_pool_data.is_frozen() {
       return Err(AppError::FrozenPool.into());
     }
     if amount == 0 {
       return Err(AppError::ZeroValue.into());
     }
+    // Fail before any CPI rather than inside the burn
+    if amount > share_data.amount {
+      return Err(AppError::InsufficientShares.into());
+    }
 
-    // Get the basics
-    let shares = share_data.amount;
-    let debt = debt_data.debt;
-    let compensation = stake_pool_data.compensation;
-    let delay = Self::estimate_delay(stake_pool_data)?;
-    let reward = stake_pool_data.reward;
-    let current_total_shares = stake_pool_data.total_shares;
-    // Fully harvest
-    let next_total_shares = current_total_shares; // Harvest all before unstaking
-    let (shares, debt, compensation) = Pattern::fully_harvest(
-      shares,
-      debt,
-      compensation,
-      delay,
-      reward,
-      current_total_shares,
-      next_total_shares,
-    )
-    .ok_or(AppError::Overflow)?;
-    let yeild = debt.checked_sub(debt_data.debt).ok_or(AppError::Overflow)? as u64;
-    // Fully unstake
-    let next_total_shares = current_total_shares
-      .checked_sub(shares)
-      .ok_or(AppError::Overflow)?;
-    let (_, debt, compensation) = Pattern::fully_unstake(
-      shares,
-      debt,
-      compensation,
-      delay,
-      reward,
-      current_total_shares,
-      next_total_shares,
-    )
-    .ok_or(AppError::Overflow)?;
-    // Fully stake
-    let shares = share_data
-      .amount
-      .checked_sub(amount)
-      .ok_or(AppError::Overflow)?;
-    let current_total_shares = next_total_shares;
-    let next_total_shares = current_total_shares
-      .checked_add(shares)
+    // Harvest then unstake
+    let shares = Shares(share_data.amount);
+    let next_shares = shares
+      .checked_sub(Shares(amount))
       .ok_or(AppError::Overflow)?;
-    let (_, debt, compensation) = Pattern::fully_stake(
-      shares,
-      debt,
-      compensation,
-      delay,
-      reward,
-      current_total_shares,
-      next_total_shares,
-    )
-    .ok_or(AppError::Overflow)?;
+    let yeild = Self::settle(shares, next_shares, &mut debt_data, &mut stake_pool_data)?;
+    let yeild = Self::vest(yeild, &mut debt_data, &mut stake_pool_data)?;
 
     // Harvest
     XSPLT::transfer(
@@ -544,6 +808,8 @@ impl Processor {
     )?;
     // Unstake token
     XSPLT::burn(amount, share_acc, mint_share_acc, owner, splt_program, &[])?;
+    Self::is_settled_balance(share_acc, next_shares)?;
+    let treasury_token_amount = Account::unpack(&treasury_token_acc.data.borrow())?.amount;
     XSPLT::transfer(
       amount,
       treasury_token_acc,
@@ -552,72 +818,98 @@ impl Processor {
       splt_program,
       seed,
     )?;
+    // The principal returned must match the shares burnt
+    let returned = treasury_token_amount
+      .checked_sub(Account::unpack(&treasury_token_acc.data.borrow())?.amount)
+      .ok_or(AppError::Overflow)?;
+    if returned != amount {
+      return Err(AppError::TransferAmountMismatch.into());
+    }
 
-    // Debt account
-    debt_data.debt = debt;
-    Debt::pack(debt_data, &mut debt_acc.data.borrow_mut())?;
-    // Stake pool account
-    stake_pool_data.total_shares = next_total_shares;
-    stake_pool_data.compensation = compensation;
-    StakePool::pack(stake_pool_data, &mut stake_pool_acc.data.borrow_mut())?;
+    // Churn statistics
+    stake_pool_data.cumulative_unstaked = stake_pool_data
+      .cumulative_unstaked
+      .checked_add(returned as u128)
+      .ok_or(AppError::Overflow)?;
+    Self::check_treasury_cover(treasury_sen_acc, &mut stake_pool_data)?;
+    Self::pack_debt(debt_data, debt_acc)?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+    // Client reference
+    if let Some(reference) = reference {
+      msg!("REFERENCE:{}", reference);
+    }
 
     Ok(())
   }
 
-  pub fn harvest(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let mint_share_acc = next_account_info(accounts_iter)?;
-
-    let share_acc = next_account_info(accounts_iter)?;
-    let debt_acc = next_account_info(accounts_iter)?;
-
-    let dst_sen_acc = next_account_info(accounts_iter)?;
-    let treasury_sen_acc = next_account_info(accounts_iter)?;
-
-    let treasurer = next_account_info(accounts_iter)?;
-    let splt_program = next_account_info(accounts_iter)?;
+  pub fn harvest(
+    memo: bool,
+    idempotency_key: Option<[u8; 16]>,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let HarvestContext {
+      owner,
+      stake_pool_acc,
+      mint_share_acc,
+      share_acc,
+      debt_acc,
+      dst_sen_acc,
+      treasury_sen_acc,
+      treasurer,
+      splt_program,
+    } = HarvestContext::load(program_id, accounts)?;
 
-    Self::is_program(program_id, &[stake_pool_acc, debt_acc])?;
-    Self::is_signer(&[owner])?;
     Self::is_debt_owner(owner, debt_acc, stake_pool_acc, share_acc)?;
 
     let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
     let share_data = Account::unpack(&share_acc.data.borrow())?;
     let mut debt_data = Debt::unpack(&debt_acc.data.borrow())?;
-    let seed: &[&[&[u8]]] = &[&[&Self::safe_seed(stake_pool_acc, treasurer, program_id)?[..]]];
+    // Keepers need no signature to pay the owner's own default account
+    let to_default = debt_data.default_sen_account != Pubkey::default()
+      && debt_data.default_sen_account == *dst_sen_acc.key;
+    if !to_default {
+      Self::is_signer(&[owner])?;
+    }
+    // Only the owner spends its keys, a keeper could otherwise evict or burn them
+    if owner.is_signer {
+      Self::record_idempotency_key(idempotency_key, &mut debt_data)?;
+    }
+    let seed: &[&[&[u8]]] =
+      &[&[&Self::treasurer_seed(stake_pool_acc, treasurer, &stake_pool_data)?[..]]];
     if stake_pool_data.mint_share != *mint_share_acc.key
       || stake_pool_data.treasury_sen != *treasury_sen_acc.key
     {
       return Err(AppError::UnmatchedPool.into());
     }
+    Self::is_reward_account(dst_sen_acc, &stake_pool_data)?;
+    Self::is_reward_account(treasury_sen_acc, &stake_pool_data)?;
     if stake_pool_data.is_frozen() {
       return Err(AppError::FrozenPool.into());
     }
-
-    // Get the basics
-    let shares = share_data.amount;
-    let debt = debt_data.debt;
-    let compensation = stake_pool_data.compensation;
-    let delay = Self::estimate_delay(stake_pool_data)?;
-    let reward = stake_pool_data.reward;
-    let current_total_shares = stake_pool_data.total_shares;
-    // Fully harvest
-    let next_total_shares = current_total_shares; // Harvest doesn't change the total shares
-    let (_, debt, compensation) = Pattern::fully_harvest(
-      shares,
-      debt,
-      compensation,
-      delay,
-      reward,
-      current_total_shares,
-      next_total_shares,
-    )
-    .ok_or(AppError::Overflow)?;
-    let yeild = debt.checked_sub(debt_data.debt).ok_or(AppError::Overflow)? as u64;
+    // Stake and unstake also harvest but are not rate limited
+    Self::is_unlocked(
+      debt_data.last_harvest_timestamp,
+      stake_pool_data.min_harvest_interval,
+    )?;
 
     // Harvest
+    let shares = Shares(share_data.amount);
+    let yeild = Self::settle(shares, shares, &mut debt_data, &mut stake_pool_data)?;
+    // Only the owner's harvests start its interval, a keeper could otherwise lock it out
+    if owner.is_signer {
+      debt_data.last_harvest_timestamp = Self::current_timestamp()?;
+    }
+    // Tell a pool that has not credited any period from an empty claim
+    if yeild == 0 {
+      if Self::estimate_delay(stake_pool_data)? == 0 {
+        msg!("ZERO_YIELD:NOT_ACCRUED");
+      } else {
+        msg!("ZERO_YIELD:NOTHING_CLAIMABLE");
+      }
+    }
+    let yeild = Self::vest(yeild, &mut debt_data, &mut stake_pool_data)?;
+    // Any account of the reward mint may receive the yield, not only the owner's
     XSPLT::transfer(
       yeild,
       treasury_sen_acc,
@@ -626,60 +918,142 @@ impl Processor {
       splt_program,
       seed,
     )?;
+    events::emit_harvested(stake_pool_acc.key, debt_acc.key, dst_sen_acc.key, yeild);
+    if memo {
+      let memo_program = accounts
+        .get(HarvestContext::SPECS.len())
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+      // Keepers may call without the owner, never let them pick the program
+      if *memo_program.key != MEMO_PROGRAM_ID {
+        return Err(AppError::IncorrectProgramId.into());
+      }
+      XMEMO::memo(
+        &format!("senfarm:harvest:{}:{}", stake_pool_acc.key, yeild),
+        memo_program,
+      )?;
+    }
 
-    // Debt account
-    debt_data.debt = debt;
-    Debt::pack(debt_data, &mut debt_acc.data.borrow_mut())?;
-    // Stake pool account
-    stake_pool_data.total_shares = next_total_shares;
-    stake_pool_data.compensation = compensation;
-    StakePool::pack(stake_pool_data, &mut stake_pool_acc.data.borrow_mut())?;
+    Self::check_treasury_cover(treasury_sen_acc, &mut stake_pool_data)?;
+    Self::pack_debt(debt_data, debt_acc)?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn harvest_and_seed(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let HarvestAndSeedContext {
+      owner,
+      stake_pool_acc,
+      mint_share_acc,
+      share_acc,
+      debt_acc,
+      treasury_sen_acc,
+    } = HarvestAndSeedContext::load(program_id, accounts)?;
+
+    // Only the pool owner may turn its own yield into seed
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+    Self::is_debt_owner(owner, debt_acc, stake_pool_acc, share_acc)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let share_data = Account::unpack(&share_acc.data.borrow())?;
+    let mut debt_data = Debt::unpack(&debt_acc.data.borrow())?;
+    if stake_pool_data.mint_share != *mint_share_acc.key
+      || stake_pool_data.treasury_sen != *treasury_sen_acc.key
+    {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    if stake_pool_data.is_frozen() {
+      return Err(AppError::FrozenPool.into());
+    }
+    // A vesting yield is owed to the staker, it cannot be seeded back
+    if stake_pool_data.vesting_duration != 0 {
+      return Err(AppError::VestingEnabled.into());
+    }
+    Self::is_unlocked(
+      debt_data.last_harvest_timestamp,
+      stake_pool_data.min_harvest_interval,
+    )?;
+
+    // Harvest then seed back, the yield simply stays in the treasury
+    let shares = Shares(share_data.amount);
+    let yeild = Self::settle(shares, shares, &mut debt_data, &mut stake_pool_data)?;
+    debt_data.last_harvest_timestamp = Self::current_timestamp()?;
+    Self::record_admin_action(
+      "SEED",
+      0,
+      yeild,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+
+    Self::check_treasury_cover(treasury_sen_acc, &mut stake_pool_data)?;
+    Self::pack_debt(debt_data, debt_acc)?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
 
     Ok(())
   }
 
   pub fn freeze_stake_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
 
-    Self::is_program(program_id, &[stake_pool_acc])?;
-    Self::is_signer(&[owner])?;
     Self::is_stake_pool_owner(owner, stake_pool_acc)?;
 
     let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::is_unlocked(stake_pool_data.last_state_change, STATE_CHANGE_INTERVAL)?;
+    let state = format!("{:?}", stake_pool_data.state);
     stake_pool_data.state = StakePoolState::Frozen;
-    StakePool::pack(stake_pool_data, &mut stake_pool_acc.data.borrow_mut())?;
+    Self::record_admin_action(
+      "FREEZE",
+      state,
+      format!("{:?}", stake_pool_data.state),
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.last_state_change = Self::current_timestamp()?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
 
     Ok(())
   }
 
   pub fn thaw_stake_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
 
-    Self::is_program(program_id, &[stake_pool_acc])?;
-    Self::is_signer(&[owner])?;
     Self::is_stake_pool_owner(owner, stake_pool_acc)?;
 
     let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::is_unlocked(stake_pool_data.last_state_change, STATE_CHANGE_INTERVAL)?;
+    let state = format!("{:?}", stake_pool_data.state);
     stake_pool_data.state = StakePoolState::Initialized;
-    StakePool::pack(stake_pool_data, &mut stake_pool_acc.data.borrow_mut())?;
+    Self::record_admin_action(
+      "THAW",
+      state,
+      format!("{:?}", stake_pool_data.state),
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.last_state_change = Self::current_timestamp()?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
 
     Ok(())
   }
 
   pub fn seed(amount: u64, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let src_sen_acc = next_account_info(accounts_iter)?;
-    let treasury_sen_acc = next_account_info(accounts_iter)?;
-    let splt_program = next_account_info(accounts_iter)?;
-
-    Self::is_program(program_id, &[stake_pool_acc])?;
-    Self::is_signer(&[owner])?;
+    let SeedContext {
+      owner,
+      stake_pool_acc,
+      src_sen_acc,
+      treasury_sen_acc,
+      splt_program,
+    } = SeedContext::load(program_id, accounts)?;
 
     let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
     if stake_pool_data.treasury_sen != *treasury_sen_acc.key {
@@ -699,31 +1073,78 @@ impl Processor {
       &[],
     )?;
 
+    // Anyone may seed, the event is logged but the read-only pool keeps its count
+    events::emit_admin_action(
+      "SEED",
+      stake_pool_acc.key,
+      owner.key,
+      0,
+      amount,
+      stake_pool_data.admin_action_count,
+      Self::current_timestamp()?,
+    );
+
+    // Record the contribution if a ledger is provided
+    if let Some(contribution_acc) = accounts.get(SeedContext::SPECS.len()) {
+      Self::is_program(program_id, &[contribution_acc])?;
+      let mut contribution_data = Contribution::unpack(&contribution_acc.data.borrow())?;
+      if contribution_data.stake_pool != *stake_pool_acc.key
+        || contribution_data.owner != *owner.key
+      {
+        return Err(AppError::InvalidOwner.into());
+      }
+      contribution_data.amount = contribution_data
+        .amount
+        .checked_add(amount)
+        .ok_or(AppError::Overflow)?;
+      Contribution::pack(contribution_data, &mut contribution_acc.data.borrow_mut())?;
+    }
+
     Ok(())
   }
 
   pub fn unseed(amount: u64, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let dst_sen_acc = next_account_info(accounts_iter)?;
-    let treasury_sen_acc = next_account_info(accounts_iter)?;
-    let treasurer = next_account_info(accounts_iter)?;
-    let splt_program = next_account_info(accounts_iter)?;
+    let UnseedContext {
+      owner,
+      stake_pool_acc,
+      dst_sen_acc,
+      treasury_sen_acc,
+      treasurer,
+      splt_program,
+    } = UnseedContext::load(program_id, accounts)?;
 
-    Self::is_program(program_id, &[stake_pool_acc])?;
-    Self::is_signer(&[owner])?;
     Self::is_stake_pool_owner(owner, stake_pool_acc)?;
 
-    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
-    let seed: &[&[&[u8]]] = &[&[&Self::safe_seed(stake_pool_acc, treasurer, program_id)?[..]]];
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let seed: &[&[&[u8]]] =
+      &[&[&Self::treasurer_seed(stake_pool_acc, treasurer, &stake_pool_data)?[..]]];
     if stake_pool_data.treasury_sen != *treasury_sen_acc.key {
       return Err(AppError::UnmatchedPool.into());
     }
+    Self::is_reward_account(dst_sen_acc, &stake_pool_data)?;
+    if stake_pool_data.unseed_destination != Pubkey::default()
+      && stake_pool_data.unseed_destination != *dst_sen_acc.key
+    {
+      return Err(AppError::UnauthorizedDestination.into());
+    }
     if amount == 0 {
       return Err(AppError::ZeroValue.into());
     }
 
+    Self::track_unseed(amount, treasury_sen_acc, &mut stake_pool_data)?;
+    // Stakers must still be paid after the withdrawal, frozen or not
+    Self::is_solvent_after(amount, treasury_sen_acc, stake_pool_data)?;
+    let treasury_sen_data = Account::unpack(&treasury_sen_acc.data.borrow())?;
+    Self::record_admin_action(
+      "UNSEED",
+      treasury_sen_data.amount,
+      treasury_sen_data.amount - amount,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
     // Withdraw SEN to treasury
     XSPLT::transfer(
       amount,
@@ -741,60 +1162,64 @@ impl Processor {
     program_id: &Pubkey,
     accounts: &[AccountInfo],
   ) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let new_owner = next_account_info(accounts_iter)?;
+    let TransferStakePoolOwnershipContext {
+      owner,
+      stake_pool_acc,
+      new_owner,
+    } = TransferStakePoolOwnershipContext::load(program_id, accounts)?;
 
-    Self::is_program(program_id, &[stake_pool_acc])?;
-    Self::is_signer(&[owner])?;
     Self::is_stake_pool_owner(owner, stake_pool_acc)?;
 
     // Update stake pool data
     let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::record_admin_action(
+      "TRANSFER_OWNERSHIP",
+      stake_pool_data.owner,
+      new_owner.key,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
     stake_pool_data.owner = *new_owner.key;
-    StakePool::pack(stake_pool_data, &mut stake_pool_acc.data.borrow_mut())?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
 
     Ok(())
   }
 
   pub fn close_debt(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let share_acc = next_account_info(accounts_iter)?;
-    let debt_acc = next_account_info(accounts_iter)?;
-    let dst_acc = next_account_info(accounts_iter)?;
-
-    Self::is_program(program_id, &[stake_pool_acc, debt_acc])?;
-    Self::is_signer(&[owner])?;
+    let CloseDebtContext {
+      owner,
+      stake_pool_acc,
+      share_acc,
+      debt_acc,
+      dst_acc,
+    } = CloseDebtContext::load(program_id, accounts)?;
+
     Self::is_debt_owner(owner, debt_acc, stake_pool_acc, share_acc)?;
 
     let mut debt_data = Debt::unpack(&debt_acc.data.borrow())?;
     if debt_data.debt != 0 || share_acc.lamports() != 0 {
       return Err(AppError::ZeroValue.into());
     }
+    if debt_data.vesting_amount != 0 || debt_data.vested_amount != 0 {
+      return Err(AppError::VestingPending.into());
+    }
 
-    let debt_starting_lamports = debt_acc.lamports();
-    **dst_acc.lamports.borrow_mut() = debt_starting_lamports
-      .checked_add(dst_acc.lamports())
-      .ok_or(AppError::Overflow)?;
-    **debt_acc.lamports.borrow_mut() = 0;
+    Self::close_account(debt_acc, dst_acc)?;
 
     debt_data.debt = 0;
-    Debt::pack(debt_data, &mut debt_acc.data.borrow_mut())?;
+    Self::pack_debt(debt_data, debt_acc)?;
 
     Ok(())
   }
 
   pub fn close_stake_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let owner = next_account_info(accounts_iter)?;
-    let stake_pool_acc = next_account_info(accounts_iter)?;
-    let dst_acc = next_account_info(accounts_iter)?;
+    let CloseStakePoolContext {
+      owner,
+      stake_pool_acc,
+      dst_acc,
+    } = CloseStakePoolContext::load(program_id, accounts)?;
 
-    Self::is_program(program_id, &[stake_pool_acc])?;
-    Self::is_signer(&[owner])?;
     Self::is_stake_pool_owner(owner, stake_pool_acc)?;
 
     let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
@@ -802,65 +1227,2226 @@ impl Processor {
       return Err(AppError::ZeroValue.into());
     }
 
-    let stake_pool_starting_lamports = stake_pool_acc.lamports();
-    **dst_acc.lamports.borrow_mut() = stake_pool_starting_lamports
-      .checked_add(dst_acc.lamports())
-      .ok_or(AppError::Overflow)?;
-    **stake_pool_acc.lamports.borrow_mut() = 0;
+    Self::close_account(stake_pool_acc, dst_acc)?;
 
     Ok(())
   }
 
-  ///
-  /// Utilities
-  ///
+  pub fn rescale_compensation(
+    old_precision: u64,
+    new_precision: u64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
 
-  pub fn is_program(program_id: &Pubkey, accounts: &[&AccountInfo]) -> ProgramResult {
-    for acc in &mut accounts.iter() {
-      if acc.owner != program_id {
-        return Err(AppError::IncorrectProgramId.into());
-      }
-    }
-    Ok(())
-  }
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
 
-  pub fn is_signer(accounts: &[&AccountInfo]) -> ProgramResult {
-    for acc in &mut accounts.iter() {
-      if !acc.is_signer {
-        return Err(AppError::InvalidOwner.into());
-      }
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if !stake_pool_data.is_frozen() {
+      return Err(AppError::UnfrozenPool.into());
     }
-    Ok(())
-  }
-
-  pub fn is_stake_pool_owner(owner: &AccountInfo, stake_pool_acc: &AccountInfo) -> ProgramResult {
-    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
-    if stake_pool_data.owner != *owner.key {
-      return Err(AppError::InvalidOwner.into());
+    if old_precision == 0 || new_precision == 0 {
+      return Err(AppError::ZeroValue.into());
     }
+
+    // Rescale compensation to the new precision
+    let ScaledComp(compensation) = Pattern::rescale_compensation(
+      ScaledComp(stake_pool_data.compensation),
+      old_precision,
+      new_precision,
+    )
+    .ok_or(AppError::Overflow)?;
+    msg!(
+      "Compensation: {} -> {}",
+      stake_pool_data.compensation,
+      compensation
+    );
+
+    // Update stake pool data
+    Self::record_admin_action(
+      "RESCALE_COMPENSATION",
+      stake_pool_data.compensation,
+      compensation,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.compensation = compensation;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
     Ok(())
   }
 
-  pub fn is_debt_owner(
-    owner: &AccountInfo,
-    debt_acc: &AccountInfo,
-    stake_pool_acc: &AccountInfo,
-    share_acc: &AccountInfo,
-  ) -> ProgramResult {
+  pub fn get_stored_debt(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let GetStoredDebtContext { debt_acc } = GetStoredDebtContext::load(program_id, accounts)?;
+
     let debt_data = Debt::unpack(&debt_acc.data.borrow())?;
-    if debt_data.stake_pool != *stake_pool_acc.key
-      || debt_data.owner != *owner.key
-      || debt_data.account != *share_acc.key
-    {
-      return Err(AppError::InvalidOwner.into());
-    }
+    msg!("STORED_DEBT:{}", debt_data.debt);
+
     Ok(())
   }
 
-  pub fn safe_seed(
-    seed_acc: &AccountInfo,
-    expected_acc: &AccountInfo,
-    program_id: &Pubkey,
+  pub fn crank_seed(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let CrankSeedContext {
+      stake_pool_acc,
+      src_sen_acc,
+      treasury_sen_acc,
+      treasurer,
+      splt_program,
+    } = CrankSeedContext::load(program_id, accounts)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let src_sen_data = Account::unpack(&src_sen_acc.data.borrow())?;
+    let seed: &[&[&[u8]]] =
+      &[&[&Self::treasurer_seed(stake_pool_acc, treasurer, &stake_pool_data)?[..]]];
+    if stake_pool_data.treasury_sen != *treasury_sen_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    if src_sen_data.mint != stake_pool_data.mint_sen {
+      return Err(AppError::UnmatchedTreasurySen.into());
+    }
+    let (delegate, allowance) = src_sen_data
+      .effective_delegate()
+      .ok_or(AppError::InvalidOwner)?;
+    if delegate != *treasurer.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    Self::is_unlocked(
+      stake_pool_data.last_crank_timestamp,
+      stake_pool_data.crank_interval,
+    )?;
+
+    // Pull at most max_crank_amount within the remaining allowance and balance
+    let amount = stake_pool_data
+      .max_crank_amount
+      .min(allowance)
+      .min(src_sen_data.amount);
+    if amount == 0 {
+      return Err(AppError::ZeroValue.into());
+    }
+
+    // Deposit SEN to treasury
+    XSPLT::transfer(
+      amount,
+      src_sen_acc,
+      treasury_sen_acc,
+      treasurer,
+      splt_program,
+      seed,
+    )?;
+
+    // Update stake pool data
+    stake_pool_data.last_crank_timestamp = Self::current_timestamp()?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    // A successful crank always moved SEN, the interval bounds the tips
+    let len = CrankSeedContext::SPECS.len();
+    if let (Some(cranker), Some(dst_sen_acc)) = (accounts.get(len), accounts.get(len + 1)) {
+      Self::tip_cranker(
+        cranker,
+        dst_sen_acc,
+        treasury_sen_acc,
+        treasurer,
+        splt_program,
+        stake_pool_acc,
+        &stake_pool_data,
+      )?;
+    }
+
+    Ok(())
+  }
+
+  pub fn update_crank_seed(
+    max_crank_amount: u64,
+    crank_interval: u64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    // Update stake pool data
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::record_admin_action(
+      "SET_MAX_CRANK_AMOUNT",
+      stake_pool_data.max_crank_amount,
+      max_crank_amount,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    Self::record_admin_action(
+      "SET_CRANK_INTERVAL",
+      stake_pool_data.crank_interval,
+      crank_interval,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.max_crank_amount = max_crank_amount;
+    stake_pool_data.crank_interval = crank_interval;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn initialize_contribution(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let InitializeContributionContext {
+      payer,
+      owner,
+      stake_pool_acc,
+      contribution_acc,
+      system_program,
+      sysvar_rent_acc,
+    } = InitializeContributionContext::load(program_id, accounts)?;
+
+    StakePool::unpack(&stake_pool_acc.data.borrow())?;
+
+    // Validate contribution account address
+    let (key, bump_seed) = Pubkey::find_program_address(
+      &[
+        &owner.key.to_bytes(),
+        &stake_pool_acc.key.to_bytes(),
+        b"contribution",
+      ],
+      program_id,
+    );
+    if key != *contribution_acc.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    // Rent contribution account
+    let seed: &[&[u8]] = &[
+      &owner.key.to_bytes(),
+      &stake_pool_acc.key.to_bytes(),
+      b"contribution",
+      &[bump_seed],
+    ];
+    Self::alloc_account(
+      Contribution::LEN,
+      contribution_acc,
+      payer,
+      program_id,
+      sysvar_rent_acc,
+      system_program,
+      &[seed],
+    )?;
+
+    // Assign data
+    let mut contribution_data = Contribution::unpack_unchecked(&contribution_acc.data.borrow())?;
+    if contribution_data.is_initialized() {
+      return Err(AppError::ConstructorOnce.into());
+    }
+    contribution_data.stake_pool = *stake_pool_acc.key;
+    contribution_data.owner = *owner.key;
+    contribution_data.amount = 0;
+    contribution_data.is_initialized = true;
+    Contribution::pack(contribution_data, &mut contribution_acc.data.borrow_mut())?;
+
+    Ok(())
+  }
+
+  pub fn withdraw_seed(
+    amount: u64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let WithdrawSeedContext {
+      owner,
+      stake_pool_acc,
+      contribution_acc,
+      dst_sen_acc,
+      treasury_sen_acc,
+      treasurer,
+      splt_program,
+    } = WithdrawSeedContext::load(program_id, accounts)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let mut contribution_data = Contribution::unpack(&contribution_acc.data.borrow())?;
+    let seed: &[&[&[u8]]] =
+      &[&[&Self::treasurer_seed(stake_pool_acc, treasurer, &stake_pool_data)?[..]]];
+    if stake_pool_data.treasury_sen != *treasury_sen_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    if contribution_data.stake_pool != *stake_pool_acc.key || contribution_data.owner != *owner.key
+    {
+      return Err(AppError::InvalidOwner.into());
+    }
+    // A pinned destination holds for every withdrawal of seed
+    Self::is_reward_account(dst_sen_acc, &stake_pool_data)?;
+    if stake_pool_data.unseed_destination != Pubkey::default()
+      && stake_pool_data.unseed_destination != *dst_sen_acc.key
+    {
+      return Err(AppError::UnauthorizedDestination.into());
+    }
+    if amount == 0 {
+      return Err(AppError::ZeroValue.into());
+    }
+    if amount > contribution_data.amount {
+      return Err(AppError::ExceedLimit.into());
+    }
+
+    // Contributors share the unseed window of the owner
+    Self::track_unseed(amount, treasury_sen_acc, &mut stake_pool_data)?;
+    // Stakers must still be paid after the withdrawal
+    Self::is_solvent_after(amount, treasury_sen_acc, stake_pool_data)?;
+    let treasury_sen_data = Account::unpack(&treasury_sen_acc.data.borrow())?;
+    Self::record_admin_action(
+      "WITHDRAW_SEED",
+      treasury_sen_data.amount,
+      treasury_sen_data.amount - amount,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    // Withdraw SEN from treasury
+    XSPLT::transfer(
+      amount,
+      treasury_sen_acc,
+      dst_sen_acc,
+      treasurer,
+      splt_program,
+      seed,
+    )?;
+
+    // Contribution account
+    contribution_data.amount = contribution_data
+      .amount
+      .checked_sub(amount)
+      .ok_or(AppError::Overflow)?;
+    Contribution::pack(contribution_data, &mut contribution_acc.data.borrow_mut())?;
+
+    Ok(())
+  }
+
+  pub fn repair_share_mint(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let RepairShareMintContext {
+      owner,
+      stake_pool_acc,
+      mint_share_acc,
+      authority,
+      council,
+      council_acc,
+      treasurer,
+      splt_program,
+    } = RepairShareMintContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+    Self::is_security_council(council, council_acc, program_id)?;
+
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let mint_share_data = Mint::unpack(&mint_share_acc.data.borrow())?;
+    Self::treasurer_seed(stake_pool_acc, treasurer, &stake_pool_data)?;
+    if stake_pool_data.mint_share != *mint_share_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    if mint_share_data.mint_authority != COption::Some(*authority.key) {
+      return Err(AppError::InvalidOwner.into());
+    }
+
+    // Restore the treasurer as the mint authority (MintTokens - Code 0)
+    XSPLT::set_authority(
+      0,
+      mint_share_acc,
+      Some(*treasurer.key),
+      authority,
+      splt_program,
+      &[],
+    )?;
+
+    Ok(())
+  }
+
+  pub fn initialize_security_council(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let InitializeSecurityCouncilContext {
+      payer,
+      council,
+      council_acc,
+      system_program,
+      sysvar_rent_acc,
+    } = InitializeSecurityCouncilContext::load(program_id, accounts)?;
+
+    // Validate security council account address
+    let (key, bump_seed) = Pubkey::find_program_address(&[b"security_council"], program_id);
+    if key != *council_acc.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    // Rent security council account
+    let seed: &[&[u8]] = &[b"security_council", &[bump_seed]];
+    Self::alloc_account(
+      SecurityCouncil::LEN,
+      council_acc,
+      payer,
+      program_id,
+      sysvar_rent_acc,
+      system_program,
+      &[seed],
+    )?;
+
+    // Assign data
+    let mut council_data = SecurityCouncil::unpack_unchecked(&council_acc.data.borrow())?;
+    if council_data.is_initialized() {
+      return Err(AppError::ConstructorOnce.into());
+    }
+    council_data.council = *council.key;
+    council_data.is_initialized = true;
+    SecurityCouncil::pack(council_data, &mut council_acc.data.borrow_mut())?;
+
+    Ok(())
+  }
+
+  pub fn initialize_registry(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let InitializeRegistryContext {
+      payer,
+      authority,
+      registry_acc,
+      system_program,
+      sysvar_rent_acc,
+    } = InitializeRegistryContext::load(program_id, accounts)?;
+
+    // Validate registry account address
+    let (key, bump_seed) = Pubkey::find_program_address(&[b"registry"], program_id);
+    if key != *registry_acc.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    // Rent registry account
+    let seed: &[&[u8]] = &[b"registry", &[bump_seed]];
+    Self::alloc_account(
+      Registry::LEN,
+      registry_acc,
+      payer,
+      program_id,
+      sysvar_rent_acc,
+      system_program,
+      &[seed],
+    )?;
+
+    // Assign data
+    let mut registry_data = Registry::unpack_unchecked(&registry_acc.data.borrow())?;
+    if registry_data.is_initialized() {
+      return Err(AppError::ConstructorOnce.into());
+    }
+    registry_data.authority = *authority.key;
+    registry_data.total_pools = 0;
+    registry_data.is_initialized = true;
+    Registry::pack(registry_data, &mut registry_acc.data.borrow_mut())?;
+
+    Ok(())
+  }
+
+  pub fn register_existing_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let RegisterExistingPoolContext {
+      authority,
+      payer,
+      stake_pool_acc,
+      registry_acc,
+      registry_page_acc,
+      system_program,
+      sysvar_rent_acc,
+    } = RegisterExistingPoolContext::load(program_id, accounts)?;
+
+    StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let registry_data = Registry::unpack(&registry_acc.data.borrow())?;
+    if registry_data.authority != *authority.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+
+    Self::register_pool(
+      stake_pool_acc,
+      registry_acc,
+      registry_page_acc,
+      payer,
+      system_program,
+      sysvar_rent_acc,
+      program_id,
+    )?;
+
+    Ok(())
+  }
+
+  pub fn delist_pool(index: u64, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let DelistPoolContext {
+      authority,
+      stake_pool_acc,
+      registry_acc,
+      registry_page_acc,
+    } = DelistPoolContext::load(program_id, accounts)?;
+
+    let registry_data = Registry::unpack(&registry_acc.data.borrow())?;
+    let mut page_data = RegistryPage::unpack(&registry_page_acc.data.borrow())?;
+    let page = index / REGISTRY_PAGE_SIZE as u64;
+    let slot = (index % REGISTRY_PAGE_SIZE as u64) as usize;
+    if page_data.registry != *registry_acc.key || page_data.index != page {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    if page_data.pools[slot] != *stake_pool_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    // Either the registry authority or the pool owner can delist
+    if registry_data.authority != *authority.key {
+      Self::is_program(program_id, &[stake_pool_acc])?;
+      let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+      if stake_pool_data.owner != *authority.key {
+        return Err(AppError::InvalidOwner.into());
+      }
+    }
+
+    // Tombstone the entry
+    page_data.pools[slot] = Pubkey::default();
+    RegistryPage::pack(page_data, &mut registry_page_acc.data.borrow_mut())?;
+
+    Ok(())
+  }
+
+  pub fn set_reward_floor(
+    min_reward_per_share: u128,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::record_admin_action(
+      "SET_REWARD_FLOOR",
+      stake_pool_data.min_reward_per_share,
+      min_reward_per_share,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    Self::rerate(
+      stake_pool_data.reward,
+      min_reward_per_share,
+      &mut stake_pool_data,
+    )?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn set_unseed_limit(
+    max_unseed_bps_per_day: u16,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if max_unseed_bps_per_day > 10000 {
+      return Err(AppError::ExceedLimit.into());
+    }
+
+    // Update stake pool data
+    Self::record_admin_action(
+      "SET_UNSEED_LIMIT",
+      stake_pool_data.max_unseed_bps_per_day,
+      max_unseed_bps_per_day,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.max_unseed_bps_per_day = max_unseed_bps_per_day;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn get_reward_mint_info(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let GetRewardMintInfoContext {
+      stake_pool_acc,
+      treasury_sen_acc,
+      mint_sen_acc,
+    } = GetRewardMintInfoContext::load(program_id, accounts)?;
+
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let treasury_sen_data = Account::unpack(&treasury_sen_acc.data.borrow())?;
+    if stake_pool_data.treasury_sen != *treasury_sen_acc.key
+      || treasury_sen_data.mint != *mint_sen_acc.key
+    {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    if stake_pool_data.mint_sen != treasury_sen_data.mint {
+      return Err(AppError::UnmatchedTreasurySen.into());
+    }
+
+    let mint_sen_data = Mint::unpack(&mint_sen_acc.data.borrow())?;
+    msg!("REWARD_MINT:{}", stake_pool_data.mint_sen);
+    msg!("DECIMALS:{}", mint_sen_data.decimals);
+    msg!("SUPPLY:{}", mint_sen_data.supply);
+
+    Ok(())
+  }
+
+  pub fn set_unseed_destination(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let SetUnseedDestinationContext {
+      owner,
+      stake_pool_acc,
+      unseed_destination,
+    } = SetUnseedDestinationContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if *unseed_destination.key != Pubkey::default() {
+      Self::is_reward_account(unseed_destination, &stake_pool_data)?;
+    }
+
+    // Update stake pool data
+    Self::record_admin_action(
+      "SET_UNSEED_DESTINATION",
+      stake_pool_data.unseed_destination,
+      unseed_destination.key,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.unseed_destination = *unseed_destination.key;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn set_harvest_interval(
+    min_harvest_interval: u64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    // Update stake pool data
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::record_admin_action(
+      "SET_HARVEST_INTERVAL",
+      stake_pool_data.min_harvest_interval,
+      min_harvest_interval,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.min_harvest_interval = min_harvest_interval;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn get_effective_reward(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let GetEffectiveRewardContext { stake_pool_acc } =
+      GetEffectiveRewardContext::load(program_id, accounts)?;
+
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    // No reward schedule yet, the stored reward is always in effect
+    msg!("EFFECTIVE_REWARD:{}", stake_pool_data.reward);
+    msg!("PERIOD:{}", stake_pool_data.period);
+    // A nonzero reward may still pay nothing at the current total shares
+    let truncated = Self::is_reward_truncated(stake_pool_data)?;
+    msg!("REWARD_TRUNCATED:{}", truncated);
+    // Paid to all shares next period, the floor applied
+    let TokenAmount(emission) = Pattern::estimate_emission(
+      stake_pool_data.reward,
+      ScaledAmount(stake_pool_data.min_reward_per_share),
+      Shares(stake_pool_data.total_shares),
+      1,
+      stake_pool_data.precision(),
+    )
+    .ok_or(AppError::Overflow)?;
+    msg!("EFFECTIVE_EMISSION:{}", emission);
+
+    Ok(())
+  }
+
+  pub fn update_stake_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let UpdatePoolContext { stake_pool_acc } = UpdatePoolContext::load(program_id, accounts)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if !stake_pool_data.is_accumulator() {
+      return Err(AppError::InvalidVersion.into());
+    }
+
+    // Fold the elapsed periods
+    let last_update_delay = stake_pool_data.last_update_delay;
+    Self::update_pool(&mut stake_pool_data)?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    // Tip the cranker only if a period was folded
+    let len = UpdatePoolContext::SPECS.len();
+    if let (
+      Some(cranker),
+      Some(dst_sen_acc),
+      Some(treasury_sen_acc),
+      Some(treasurer),
+      Some(splt_program),
+    ) = (
+      accounts.get(len),
+      accounts.get(len + 1),
+      accounts.get(len + 2),
+      accounts.get(len + 3),
+      accounts.get(len + 4),
+    ) {
+      if stake_pool_data.last_update_delay != last_update_delay {
+        Self::tip_cranker(
+          cranker,
+          dst_sen_acc,
+          treasury_sen_acc,
+          treasurer,
+          splt_program,
+          stake_pool_acc,
+          &stake_pool_data,
+        )?;
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn migrate_stake_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if stake_pool_data.is_accumulator() {
+      return Err(AppError::InvalidVersion.into());
+    }
+
+    // Start the accumulator where the compensation stands
+    let delay = Self::estimate_delay(stake_pool_data)?;
+    let acc = Pattern::migrate_to_accumulator(
+      stake_pool_data.reward,
+      ScaledAmount(stake_pool_data.min_reward_per_share),
+      Shares(stake_pool_data.total_shares),
+      ScaledComp(stake_pool_data.compensation),
+      delay,
+      stake_pool_data.precision(),
+    )
+    .ok_or(AppError::Overflow)?;
+
+    // Update stake pool data
+    Self::record_admin_action(
+      "MIGRATE",
+      stake_pool_data.version,
+      ACCUMULATOR_VERSION,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.version = ACCUMULATOR_VERSION;
+    stake_pool_data.acc_reward_per_share = acc.0;
+    stake_pool_data.last_update_delay = delay;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn clone_stake_pool(
+    genesis_timestamp: i64,
+    pool_seed: Option<[u8; 32]>,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let CloneStakePoolContext { template_acc } = CloneStakePoolContext::load(program_id, accounts)?;
+
+    let template = StakePool::unpack(&template_acc.data.borrow())?;
+    let accounts = &accounts[CloneStakePoolContext::SPECS.len()..];
+    Self::create_stake_pool(
+      &template,
+      genesis_timestamp,
+      pool_seed,
+      program_id,
+      accounts,
+    )
+  }
+
+  pub fn transfer_ownership_batch(
+    count: u8,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let TransferOwnershipBatchContext { owner } =
+      TransferOwnershipBatchContext::load(program_id, accounts)?;
+
+    let len = TransferOwnershipBatchContext::SPECS.len();
+    Self::is_batch(count, MAX_TRANSFER_OWNERSHIP_BATCH, len, 2, accounts)?;
+    let pairs = &accounts[len..];
+
+    // Every pool must be owned by the signer, otherwise the whole batch fails
+    for pair in pairs.chunks(2).take(count as usize) {
+      let (stake_pool_acc, new_owner) = (&pair[0], &pair[1]);
+      Self::is_program(program_id, &[stake_pool_acc])?;
+      Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+      // Update stake pool data
+      let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+      Self::record_admin_action(
+        "TRANSFER_OWNERSHIP",
+        stake_pool_data.owner,
+        new_owner.key,
+        owner,
+        stake_pool_acc,
+        &mut stake_pool_data,
+      )?;
+      stake_pool_data.owner = *new_owner.key;
+      Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+    }
+
+    Ok(())
+  }
+
+  pub fn get_claimable(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let GetClaimableContext {
+      stake_pool_acc,
+      share_acc,
+      debt_acc,
+    } = GetClaimableContext::load(program_id, accounts)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let share_data = Account::unpack(&share_acc.data.borrow())?;
+    let mut debt_data = Debt::unpack(&debt_acc.data.borrow())?;
+    if debt_data.stake_pool != *stake_pool_acc.key || debt_data.account != *share_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+
+    // Settle on copies, nothing is written back
+    let shares = Shares(share_data.amount);
+    let yeild = Self::settle(shares, shares, &mut debt_data, &mut stake_pool_data)?;
+    msg!("CLAIMABLE:{}", yeild);
+
+    Ok(())
+  }
+
+  pub fn allow_mint(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let AllowMintContext {
+      authority,
+      payer,
+      registry_acc,
+      mint_whitelist_acc,
+      mint_token_acc,
+      system_program,
+      sysvar_rent_acc,
+    } = AllowMintContext::load(program_id, accounts)?;
+
+    let registry_data = Registry::unpack(&registry_acc.data.borrow())?;
+    if registry_data.authority != *authority.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    if *mint_token_acc.key == Pubkey::default() {
+      return Err(AppError::InvalidMint.into());
+    }
+
+    // Validate mint whitelist address
+    let (key, bump_seed) = Pubkey::find_program_address(
+      &[&registry_acc.key.to_bytes(), b"mint_whitelist"],
+      program_id,
+    );
+    if key != *mint_whitelist_acc.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    // Rent mint whitelist account on the first allowed mint
+    if mint_whitelist_acc.owner != program_id {
+      let seed: &[&[u8]] = &[
+        &registry_acc.key.to_bytes(),
+        b"mint_whitelist",
+        &[bump_seed],
+      ];
+      Self::alloc_account(
+        MintWhitelist::LEN,
+        mint_whitelist_acc,
+        payer,
+        program_id,
+        sysvar_rent_acc,
+        system_program,
+        &[seed],
+      )?;
+      let mut mint_whitelist_data =
+        MintWhitelist::unpack_unchecked(&mint_whitelist_acc.data.borrow())?;
+      mint_whitelist_data.registry = *registry_acc.key;
+      mint_whitelist_data.mints = [Pubkey::default(); MAX_ALLOWED_MINTS];
+      mint_whitelist_data.is_initialized = true;
+      MintWhitelist::pack(
+        mint_whitelist_data,
+        &mut mint_whitelist_acc.data.borrow_mut(),
+      )?;
+    }
+
+    // Take the first free slot
+    let mut mint_whitelist_data = MintWhitelist::unpack(&mint_whitelist_acc.data.borrow())?;
+    if mint_whitelist_data.is_allowed(mint_token_acc.key) {
+      return Ok(());
+    }
+    let slot = mint_whitelist_data
+      .mints
+      .iter()
+      .position(|mint| *mint == Pubkey::default())
+      .ok_or(AppError::ExceedLimit)?;
+    mint_whitelist_data.mints[slot] = *mint_token_acc.key;
+    MintWhitelist::pack(
+      mint_whitelist_data,
+      &mut mint_whitelist_acc.data.borrow_mut(),
+    )?;
+
+    Ok(())
+  }
+
+  pub fn disallow_mint(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let DisallowMintContext {
+      authority,
+      registry_acc,
+      mint_whitelist_acc,
+      mint_token_acc,
+    } = DisallowMintContext::load(program_id, accounts)?;
+
+    let registry_data = Registry::unpack(&registry_acc.data.borrow())?;
+    if registry_data.authority != *authority.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    let mut mint_whitelist_data = MintWhitelist::unpack(&mint_whitelist_acc.data.borrow())?;
+    if mint_whitelist_data.registry != *registry_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+
+    // Tombstone the entry, existing pools are not affected
+    let slot = mint_whitelist_data
+      .mints
+      .iter()
+      .position(|mint| mint == mint_token_acc.key)
+      .ok_or(AppError::MintNotAllowed)?;
+    mint_whitelist_data.mints[slot] = Pubkey::default();
+    MintWhitelist::pack(
+      mint_whitelist_data,
+      &mut mint_whitelist_acc.data.borrow_mut(),
+    )?;
+
+    Ok(())
+  }
+
+  pub fn get_pool_timing(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let GetPoolTimingContext { stake_pool_acc } = GetPoolTimingContext::load(program_id, accounts)?;
+
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    // Pools scheduled in the future are zero seconds old
+    let age = Self::current_timestamp()?
+      .saturating_sub(stake_pool_data.genesis_timestamp)
+      .max(0);
+    msg!("AGE_SECONDS:{}", age);
+    let delay = Self::estimate_delay(stake_pool_data)?;
+    let next_accrual_timestamp = stake_pool_data
+      .next_accrual_timestamp(delay)
+      .ok_or(AppError::Overflow)?;
+    msg!("ACCRUED_PERIODS:{}", delay);
+    msg!("NEXT_ACCRUAL_TIMESTAMP:{}", next_accrual_timestamp);
+    // Pools have no end yet, they are perpetual
+    msg!("REMAINING_SECONDS:{}", 0);
+
+    Ok(())
+  }
+
+  pub fn exit_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let ExitPoolContext {
+      owner,
+      stake_pool_acc,
+      share_acc,
+      debt_acc,
+      splt_program,
+      ..
+    } = ExitPoolContext::load(program_id, accounts)?;
+
+    Self::is_debt_owner(owner, debt_acc, stake_pool_acc, share_acc)?;
+
+    if (&share_acc.data.borrow()).len() != 0 {
+      // Unstake all, the yield is harvested on the way
+      let share_data = Account::unpack(&share_acc.data.borrow())?;
+      if share_data.amount > 0 {
+        Self::unstake(share_data.amount, None, None, program_id, accounts)?;
+      }
+      // Close the emptied share account
+      XSPLT::close_account(share_acc, owner, owner, splt_program, &[])?;
+    }
+
+    // Close debt account, unless it still holds a vesting schedule to claim
+    let debt_data = Debt::unpack(&debt_acc.data.borrow())?;
+    // Nothing may be owed once every share is unstaked
+    if debt_data.debt != 0 {
+      return Err(AppError::InconsistentState.into());
+    }
+    if debt_data.vesting_amount != 0 || debt_data.vested_amount != 0 {
+      msg!("VESTING_PENDING");
+      return Ok(());
+    }
+    Self::close_account(debt_acc, owner)?;
+
+    Ok(())
+  }
+
+  pub fn set_activation(
+    activation_timestamp: i64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    // Update stake pool data
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::record_admin_action(
+      "SET_ACTIVATION",
+      stake_pool_data.activation_timestamp,
+      activation_timestamp,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.activation_timestamp = activation_timestamp;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn set_max_accrual_periods(
+    max_accrual_periods: u64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    // Update stake pool data
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::record_admin_action(
+      "SET_MAX_ACCRUAL_PERIODS",
+      stake_pool_data.max_accrual_periods,
+      max_accrual_periods,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.max_accrual_periods = max_accrual_periods;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn get_runway_seed(
+    days: u64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let GetRunwaySeedContext {
+      stake_pool_acc,
+      treasury_sen_acc,
+    } = GetRunwaySeedContext::load(program_id, accounts)?;
+
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let treasury_sen_data = Account::unpack(&treasury_sen_acc.data.borrow())?;
+    if stake_pool_data.treasury_sen != *treasury_sen_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+
+    // Emission of the coming periods (86400 seconds a day) at the current reward and total shares
+    let periods = days.checked_mul(86400).ok_or(AppError::Overflow)? / stake_pool_data.period;
+    let TokenAmount(emission) = Pattern::estimate_emission(
+      stake_pool_data.reward,
+      ScaledAmount(stake_pool_data.min_reward_per_share),
+      Shares(stake_pool_data.total_shares),
+      periods,
+      stake_pool_data.precision(),
+    )
+    .ok_or(AppError::Overflow)?;
+    // The treasury already owes the accrued but unharvested yield
+    let liability = Self::estimate_liability(stake_pool_data)?;
+    let required = (emission as u128)
+      .checked_add(liability)
+      .ok_or(AppError::Overflow)?;
+    let top_up = required.saturating_sub(treasury_sen_data.amount as u128);
+    msg!("RUNWAY_SEED:{}", top_up);
+
+    Ok(())
+  }
+
+  pub fn set_crank_tip(
+    crank_tip: u64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    // Update stake pool data
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::record_admin_action(
+      "SET_CRANK_TIP",
+      stake_pool_data.crank_tip,
+      crank_tip,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.crank_tip = crank_tip;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn set_default_sen_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let SetDefaultSenAccountContext {
+      owner,
+      stake_pool_acc,
+      debt_acc,
+      sen_acc,
+    } = SetDefaultSenAccountContext::load(program_id, accounts)?;
+
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let mut debt_data = Debt::unpack(&debt_acc.data.borrow())?;
+    if debt_data.stake_pool != *stake_pool_acc.key || debt_data.owner != *owner.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    if *sen_acc.key != Pubkey::default() {
+      Self::is_reward_account(sen_acc, &stake_pool_data)?;
+    }
+
+    // Update debt data
+    debt_data.default_sen_account = *sen_acc.key;
+    Self::pack_debt(debt_data, debt_acc)?;
+
+    Ok(())
+  }
+
+  pub fn set_min_client_version(
+    min_client_version: u8,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    // Update stake pool data
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::record_admin_action(
+      "SET_MIN_CLIENT_VERSION",
+      stake_pool_data.min_client_version,
+      min_client_version,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.min_client_version = min_client_version;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn set_vesting_duration(
+    vesting_duration: u64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    // Update stake pool data, running schedules keep their duration
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    Self::record_admin_action(
+      "SET_VESTING_DURATION",
+      stake_pool_data.vesting_duration,
+      vesting_duration,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.vesting_duration = vesting_duration;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn claim_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let ClaimVestedContext {
+      owner,
+      stake_pool_acc,
+      debt_acc,
+      dst_sen_acc,
+      treasury_sen_acc,
+      treasurer,
+      splt_program,
+    } = ClaimVestedContext::load(program_id, accounts)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let mut debt_data = Debt::unpack(&debt_acc.data.borrow())?;
+    // The share account may be gone, the debt alone binds the owner
+    if debt_data.stake_pool != *stake_pool_acc.key || debt_data.owner != *owner.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    let seed: &[&[&[u8]]] =
+      &[&[&Self::treasurer_seed(stake_pool_acc, treasurer, &stake_pool_data)?[..]]];
+    if stake_pool_data.treasury_sen != *treasury_sen_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    Self::is_reward_account(dst_sen_acc, &stake_pool_data)?;
+
+    // Claim the unlocked part
+    Self::release_vesting(&mut debt_data)?;
+    let amount = debt_data.vested_amount;
+    if amount == 0 {
+      return Err(AppError::ZeroValue.into());
+    }
+    debt_data.vested_amount = 0;
+    stake_pool_data.total_vesting = stake_pool_data
+      .total_vesting
+      .checked_sub(amount)
+      .ok_or(AppError::Overflow)?;
+    XSPLT::transfer(
+      amount,
+      treasury_sen_acc,
+      dst_sen_acc,
+      treasurer,
+      splt_program,
+      seed,
+    )?;
+
+    Self::pack_debt(debt_data, debt_acc)?;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn get_tvl_batch(count: u8, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    Self::is_batch(count, MAX_TVL_BATCH, 0, 2, accounts)?;
+
+    // Sum the shares and the deposits of the chosen pools
+    let mut total_shares: u128 = 0;
+    let mut total_tokens: u128 = 0;
+    for pair in accounts.chunks(2).take(count as usize) {
+      let (stake_pool_acc, treasury_token_acc) = (&pair[0], &pair[1]);
+      Self::is_program(program_id, &[stake_pool_acc])?;
+      let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+      if stake_pool_data.treasury_token != *treasury_token_acc.key {
+        return Err(AppError::UnmatchedPool.into());
+      }
+      let treasury_token_data = Account::unpack(&treasury_token_acc.data.borrow())?;
+      total_shares = total_shares
+        .checked_add(stake_pool_data.total_shares as u128)
+        .ok_or(AppError::Overflow)?;
+      total_tokens = total_tokens
+        .checked_add(treasury_token_data.amount as u128)
+        .ok_or(AppError::Overflow)?;
+    }
+    msg!("TVL_SHARES:{}", total_shares);
+    msg!("TVL_TOKENS:{}", total_tokens);
+
+    Ok(())
+  }
+
+  pub fn is_account_initialized(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let IsAccountInitializedContext {
+      owner,
+      stake_pool_acc,
+      debt_acc,
+    } = IsAccountInitializedContext::load(program_id, accounts)?;
+
+    // Validate debt account address
+    let (key, _) = Pubkey::find_program_address(
+      &[
+        &owner.key.to_bytes(),
+        &stake_pool_acc.key.to_bytes(),
+        &program_id.to_bytes(),
+      ],
+      program_id,
+    );
+    if key != *debt_acc.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    // Unallocated or all-zero data reads as not initialized
+    let debt_data = if debt_acc.owner == program_id && debt_acc.data_len() == Debt::LEN {
+      Debt::unpack_unchecked(&debt_acc.data.borrow())?
+    } else {
+      Debt::default()
+    };
+    let initialized = debt_data.is_initialized();
+    let matched =
+      initialized && debt_data.stake_pool == *stake_pool_acc.key && debt_data.owner == *owner.key;
+    msg!("INITIALIZED:{},MATCHED:{}", initialized, matched);
+
+    Ok(())
+  }
+
+  pub fn clear_withdraw_only(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let ClearWithdrawOnlyContext {
+      owner,
+      stake_pool_acc,
+      treasury_sen_acc,
+    } = ClearWithdrawOnlyContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if stake_pool_data.treasury_sen != *treasury_sen_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    // Only a treasury seeded back over the next period may reopen stakes
+    let treasury_sen_data = Account::unpack(&treasury_sen_acc.data.borrow())?;
+    let obligation = Self::next_period_obligation(stake_pool_data)?;
+    if (treasury_sen_data.amount as u128) < obligation {
+      return Err(AppError::InsufficientFunds.into());
+    }
+
+    // Update stake pool data
+    Self::record_admin_action(
+      "CLEAR_WITHDRAW_ONLY",
+      stake_pool_data.withdraw_only,
+      false,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.withdraw_only = false;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn shift_genesis(
+    delta_seconds: i64,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let StakePoolOwnerContext {
+      owner,
+      stake_pool_acc,
+    } = StakePoolOwnerContext::load(program_id, accounts)?;
+
+    Self::is_stake_pool_owner(owner, stake_pool_acc)?;
+
+    let mut stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if !stake_pool_data.is_frozen() {
+      return Err(AppError::UnfrozenPool.into());
+    }
+    let genesis_timestamp = stake_pool_data
+      .genesis_timestamp
+      .checked_add(delta_seconds)
+      .ok_or(AppError::Overflow)?;
+    // Only a clock recovery, a pool cannot be sent back before its launch
+    if genesis_timestamp > Self::current_timestamp()? {
+      return Err(AppError::InvalidGenesis.into());
+    }
+    // The accumulator cannot give back the periods it already credited
+    let mut shifted = stake_pool_data;
+    shifted.genesis_timestamp = genesis_timestamp;
+    if stake_pool_data.is_accumulator()
+      && Self::estimate_delay(shifted)? < stake_pool_data.last_update_delay
+    {
+      return Err(AppError::InvalidGenesis.into());
+    }
+
+    // Update stake pool data
+    Self::record_admin_action(
+      "SHIFT_GENESIS",
+      stake_pool_data.genesis_timestamp,
+      genesis_timestamp,
+      owner,
+      stake_pool_acc,
+      &mut stake_pool_data,
+    )?;
+    stake_pool_data.genesis_timestamp = genesis_timestamp;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    Ok(())
+  }
+
+  pub fn garbage_collect_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let GarbageCollectPoolContext {
+      caller,
+      owner,
+      stake_pool_acc,
+      mint_share_acc,
+      treasury_token_acc,
+      treasury_sen_acc,
+      treasurer,
+      splt_program,
+    } = GarbageCollectPoolContext::load(program_id, accounts)?;
+
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    let seed: &[&[&[u8]]] =
+      &[&[&Self::treasurer_seed(stake_pool_acc, treasurer, &stake_pool_data)?[..]]];
+    if stake_pool_data.owner != *owner.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    if stake_pool_data.mint_share != *mint_share_acc.key
+      || stake_pool_data.treasury_token != *treasury_token_acc.key
+      || stake_pool_data.treasury_sen != *treasury_sen_acc.key
+    {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    // Only an emptied pool its owner left alone may be collected
+    let treasury_token_data = Account::unpack(&treasury_token_acc.data.borrow())?;
+    let treasury_sen_data = Account::unpack(&treasury_sen_acc.data.borrow())?;
+    if stake_pool_data.total_shares != 0
+      || treasury_token_data.amount != 0
+      || treasury_sen_data.amount != 0
+    {
+      return Err(AppError::PoolNotEmpty.into());
+    }
+    Self::is_unlocked(stake_pool_data.last_admin_action, GC_IDLE_INTERVAL)?;
+
+    // Close the treasuries into the stake pool account
+    for treasury_acc in [treasury_token_acc, treasury_sen_acc].iter() {
+      XSPLT::close_account(treasury_acc, stake_pool_acc, treasurer, splt_program, seed)?;
+    }
+    // The share mint cannot be closed, no one may mint from it again (MintTokens - Code 0)
+    XSPLT::set_authority(0, mint_share_acc, None, treasurer, splt_program, seed)?;
+
+    // Pay the caller its share of the rent, the owner gets the rest
+    let lamports = stake_pool_acc.lamports();
+    let incentive = (lamports as u128 * GC_INCENTIVE_BPS as u128 / 10000) as u64;
+    let caller_lamports = caller
+      .lamports()
+      .checked_add(incentive)
+      .ok_or(AppError::Overflow)?;
+    **stake_pool_acc.lamports.borrow_mut() = lamports - incentive;
+    **caller.lamports.borrow_mut() = caller_lamports;
+    msg!("GARBAGE_COLLECTED:incentive={}", incentive);
+    Self::close_account(stake_pool_acc, owner)?;
+
+    Ok(())
+  }
+
+  pub fn harvest_with_create(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let HarvestWithCreateContext {
+      owner,
+      stake_pool_acc,
+      dst_sen_acc,
+      splt_program,
+      payer,
+      mint_sen_acc,
+      system_program,
+      sysvar_rent_acc,
+      splata_program,
+      ..
+    } = HarvestWithCreateContext::load(program_id, accounts)?;
+
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if stake_pool_data.mint_sen != *mint_sen_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    // Only the owner's canonical SEN account may be created on its behalf
+    let key = associated_token_address(
+      owner.key,
+      mint_sen_acc.key,
+      splt_program.key,
+      splata_program.key,
+    );
+    if key != *dst_sen_acc.key {
+      return Err(AppError::UnmatchedAssociatedAccount.into());
+    }
+    if dst_sen_acc.data_len() == 0 {
+      XSPLATA::initialize_account(
+        payer,
+        dst_sen_acc,
+        owner,
+        mint_sen_acc,
+        system_program,
+        splt_program,
+        sysvar_rent_acc,
+        splata_program,
+        &[],
+      )?;
+    }
+
+    // The leading accounts are those of Harvest
+    Self::harvest(false, None, program_id, accounts)
+  }
+
+  pub fn get_layout(_program_id: &Pubkey, _accounts: &[AccountInfo]) -> ProgramResult {
+    for (name, len, fields) in [
+      ("STAKE_POOL", StakePool::LEN, STAKE_POOL_LAYOUT),
+      ("DEBT", Debt::LEN, DEBT_LAYOUT),
+    ]
+    .iter()
+    {
+      // A layout out of step with the packing must never reach clients
+      if layout::packed_len(fields) != Some(*len) {
+        return Err(AppError::InvariantViolation.into());
+      }
+      msg!("LAYOUT:{}", layout::schema(name, *len, fields));
+    }
+
+    Ok(())
+  }
+
+  pub fn set_distribution(
+    count: u8,
+    weights_bps: [u16; MAX_DISTRIBUTION_CHILDREN],
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let SetDistributionContext {
+      authority,
+      payer,
+      config_acc,
+      system_program,
+      sysvar_rent_acc,
+    } = SetDistributionContext::load(program_id, accounts)?;
+
+    let len = SetDistributionContext::SPECS.len();
+    Self::is_batch(count, MAX_DISTRIBUTION_CHILDREN as u8, len, 1, accounts)?;
+    let children = &accounts[len..];
+    if !DistributionConfig::is_valid_weights(&weights_bps[..count as usize]) {
+      return Err(AppError::InvalidWeights.into());
+    }
+
+    // Validate config account address
+    let (key, bump_seed) =
+      Pubkey::find_program_address(&[&authority.key.to_bytes(), b"distribution"], program_id);
+    if key != *config_acc.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    // Rent config account on the first configuration
+    if config_acc.owner != program_id {
+      let seed: &[&[u8]] = &[&authority.key.to_bytes(), b"distribution", &[bump_seed]];
+      Self::alloc_account(
+        DistributionConfig::LEN,
+        config_acc,
+        payer,
+        program_id,
+        sysvar_rent_acc,
+        system_program,
+        &[seed],
+      )?;
+    }
+    let mut config_data = DistributionConfig::unpack_unchecked(&config_acc.data.borrow())?;
+    if config_data.is_initialized() && config_data.authority != *authority.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+
+    // Children must be stake pools of this program
+    let mut config_children = [Pubkey::default(); MAX_DISTRIBUTION_CHILDREN];
+    for (child, child_acc) in config_children.iter_mut().zip(children.iter()) {
+      Self::is_program(program_id, &[child_acc])?;
+      StakePool::unpack(&child_acc.data.borrow())?;
+      *child = *child_acc.key;
+    }
+
+    // Update config data
+    config_data.authority = *authority.key;
+    config_data.children = config_children;
+    config_data.weights_bps = weights_bps;
+    config_data.count = count;
+    config_data.is_initialized = true;
+    DistributionConfig::pack(config_data, &mut config_acc.data.borrow_mut())?;
+
+    Ok(())
+  }
+
+  pub fn distribute(amount: u64, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let DistributeContext {
+      config_acc,
+      funding_acc,
+      distributor,
+      splt_program,
+    } = DistributeContext::load(program_id, accounts)?;
+
+    let config_data = DistributionConfig::unpack(&config_acc.data.borrow())?;
+    let len = DistributeContext::SPECS.len();
+    Self::is_batch(
+      config_data.count,
+      MAX_DISTRIBUTION_CHILDREN as u8,
+      len,
+      2,
+      accounts,
+    )?;
+    let pairs = &accounts[len..];
+    if amount == 0 {
+      return Err(AppError::ZeroValue.into());
+    }
+    // The funding account is delegated to the distributor of this config
+    let (key, bump_seed) =
+      Pubkey::find_program_address(&[&config_acc.key.to_bytes(), b"distributor"], program_id);
+    if key != *distributor.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    let seed: &[&[&[u8]]] = &[&[&config_acc.key.to_bytes(), b"distributor", &[bump_seed]]];
+
+    // Seed every child its part, all treasuries are checked before any transfer
+    let parts = config_data.split(amount).ok_or(AppError::Overflow)?;
+    for (child, pair) in config_data.children.iter().zip(pairs.chunks(2)) {
+      let (stake_pool_acc, treasury_sen_acc) = (&pair[0], &pair[1]);
+      Self::is_program(program_id, &[stake_pool_acc])?;
+      let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+      if *child != *stake_pool_acc.key || stake_pool_data.treasury_sen != *treasury_sen_acc.key {
+        return Err(AppError::UnmatchedPool.into());
+      }
+    }
+    let current_timestamp = Self::current_timestamp()?;
+    for (part, pair) in parts.iter().zip(pairs.chunks(2)) {
+      let (stake_pool_acc, treasury_sen_acc) = (&pair[0], &pair[1]);
+      if *part == 0 {
+        continue;
+      }
+      XSPLT::transfer(
+        *part,
+        funding_acc,
+        treasury_sen_acc,
+        distributor,
+        splt_program,
+        seed,
+      )?;
+      // Logged as a seed of the child, whose read-only pool keeps its count
+      let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+      events::emit_admin_action(
+        "SEED",
+        stake_pool_acc.key,
+        distributor.key,
+        0,
+        *part,
+        stake_pool_data.admin_action_count,
+        current_timestamp,
+      );
+    }
+
+    Ok(())
+  }
+
+  pub fn harvest_sol(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let HarvestSolContext {
+      owner,
+      stake_pool_acc,
+      dst_sen_acc,
+      splt_program,
+      ..
+    } = HarvestSolContext::load(program_id, accounts)?;
+
+    // Only a wrapped SOL reward can be paid in native SOL
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if stake_pool_data.mint_sen != NATIVE_MINT {
+      return Err(AppError::UnmatchedTreasurySen.into());
+    }
+    // Only an empty account of the owner may be closed, never its main wrapped SOL
+    let dst_sen_data = Account::unpack(&dst_sen_acc.data.borrow())?;
+    if dst_sen_data.owner != *owner.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    if dst_sen_data.amount != 0 {
+      return Err(AppError::NonEmptyTemporaryAccount.into());
+    }
+
+    Self::harvest(false, None, program_id, accounts)?;
+    // Unwrap the yield, with the rent of the temporary account, to the owner
+    XSPLT::close_account(dst_sen_acc, owner, owner, splt_program, &[])?;
+
+    Ok(())
+  }
+
+  ///
+  /// Utilities
+  ///
+
+  // Pools may refuse clients built before a change of the staker instructions
+  pub fn is_supported_client(
+    client_version: u8,
+    instruction: &AppInstruction,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let position = account_specs(instruction)
+      .iter()
+      .position(|spec| spec.name == "stake_pool_acc")
+      .ok_or(AppError::InvalidInstruction)?;
+    let stake_pool_acc = accounts
+      .get(position)
+      .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    Self::is_program(program_id, &[stake_pool_acc])?;
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if client_version < stake_pool_data.min_client_version {
+      msg!(
+        "Required client version {}",
+        stake_pool_data.min_client_version
+      );
+      return Err(AppError::ClientTooOld.into());
+    }
+    Ok(())
+  }
+
+  // Batches are bounded and come with exactly their accounts
+  pub fn is_batch(
+    count: u8,
+    max_count: u8,
+    fixed: usize,
+    group: usize,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    if count == 0 {
+      return Err(AppError::ZeroValue.into());
+    }
+    if count > max_count {
+      msg!("Batch of at most {}", max_count);
+      return Err(AppError::ExceedLimit.into());
+    }
+    let expected = fixed + count as usize * group;
+    if accounts.len() != expected {
+      msg!("Expected {} accounts, got {}", expected, accounts.len());
+      return Err(AppError::BatchAccountsMismatch.into());
+    }
+    Ok(())
+  }
+
+  pub fn is_program(program_id: &Pubkey, accounts: &[&AccountInfo]) -> ProgramResult {
+    for acc in &mut accounts.iter() {
+      if acc.owner != program_id {
+        return Err(AppError::IncorrectProgramId.into());
+      }
+    }
+    Ok(())
+  }
+
+  pub fn is_signer(accounts: &[&AccountInfo]) -> ProgramResult {
+    for acc in &mut accounts.iter() {
+      if !acc.is_signer {
+        return Err(AppError::InvalidOwner.into());
+      }
+    }
+    Ok(())
+  }
+
+  pub fn is_stake_pool_owner(owner: &AccountInfo, stake_pool_acc: &AccountInfo) -> ProgramResult {
+    let stake_pool_data = StakePool::unpack(&stake_pool_acc.data.borrow())?;
+    if stake_pool_data.owner != *owner.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    Ok(())
+  }
+
+  // Accrued but unharvested yield plus one period of emission
+  pub fn next_period_obligation(stake_pool_data: StakePool) -> Result<u128, ProgramError> {
+    let TokenAmount(emission) = Pattern::estimate_emission(
+      stake_pool_data.reward,
+      ScaledAmount(stake_pool_data.min_reward_per_share),
+      Shares(stake_pool_data.total_shares),
+      1,
+      stake_pool_data.precision(),
+    )
+    .ok_or(AppError::Overflow)?;
+    let liability = Self::estimate_liability(stake_pool_data)?;
+    let obligation = liability
+      .checked_add(emission as u128)
+      .ok_or(AppError::Overflow)?;
+    Ok(obligation)
+  }
+
+  // Engage withdraw-only mode once the treasury cannot cover the next period,
+  // the caller packs the stake pool
+  pub fn check_treasury_cover(
+    treasury_sen_acc: &AccountInfo,
+    stake_pool_data: &mut StakePool,
+  ) -> ProgramResult {
+    if stake_pool_data.withdraw_only {
+      return Ok(());
+    }
+    let treasury_sen_data = Account::unpack(&treasury_sen_acc.data.borrow())?;
+    let obligation = Self::next_period_obligation(*stake_pool_data)?;
+    if (treasury_sen_data.amount as u128) < obligation {
+      stake_pool_data.withdraw_only = true;
+      msg!(
+        "WITHDRAW_ONLY:ENGAGED,treasury={},obligation={}",
+        treasury_sen_data.amount,
+        obligation
+      );
+    }
+    Ok(())
+  }
+
+  // Reject a replayed key and remember a new one, absent keys skip the check.
+  // The key is only persisted if the instruction succeeds
+  pub fn record_idempotency_key(
+    idempotency_key: Option<[u8; 16]>,
+    debt_data: &mut Debt,
+  ) -> ProgramResult {
+    if let Some(key) = idempotency_key {
+      // Empty slots of the ring read as the zero key
+      if key == [0; 16] {
+        return Err(AppError::ZeroValue.into());
+      }
+      if debt_data.has_idempotency_key(&key) {
+        return Err(AppError::DuplicateIdempotencyKey.into());
+      }
+      debt_data.record_idempotency_key(key);
+    }
+    Ok(())
+  }
+
+  pub fn is_reward_truncated(stake_pool_data: StakePool) -> Result<bool, ProgramError> {
+    let truncated = Pattern::is_truncated(
+      stake_pool_data.reward,
+      ScaledAmount(stake_pool_data.min_reward_per_share),
+      Shares(stake_pool_data.total_shares),
+      stake_pool_data.precision(),
+    )
+    .ok_or(AppError::Overflow)?;
+    Ok(truncated)
+  }
+
+  // Number the owner action and log it, the caller packs the stake pool
+  pub fn record_admin_action(
+    action: &str,
+    old: impl Display,
+    new: impl Display,
+    owner: &AccountInfo,
+    stake_pool_acc: &AccountInfo,
+    stake_pool_data: &mut StakePool,
+  ) -> ProgramResult {
+    stake_pool_data.admin_action_count = stake_pool_data
+      .admin_action_count
+      .checked_add(1)
+      .ok_or(AppError::Overflow)?;
+    stake_pool_data.last_admin_action = Self::current_timestamp()?;
+    events::emit_admin_action(
+      action,
+      stake_pool_acc.key,
+      owner.key,
+      old,
+      new,
+      stake_pool_data.admin_action_count,
+      stake_pool_data.last_admin_action,
+    );
+    Ok(())
+  }
+
+  // Corrupted state is never persisted in debug and strict builds
+  pub fn pack_stake_pool(
+    stake_pool_data: StakePool,
+    stake_pool_acc: &AccountInfo,
+  ) -> ProgramResult {
+    #[cfg(any(debug_assertions, feature = "strict"))]
+    stake_pool_data.validate()?;
+    StakePool::pack(stake_pool_data, &mut stake_pool_acc.data.borrow_mut())
+  }
+
+  pub fn pack_debt(debt_data: Debt, debt_acc: &AccountInfo) -> ProgramResult {
+    #[cfg(any(debug_assertions, feature = "strict"))]
+    debt_data.validate()?;
+    Debt::pack(debt_data, &mut debt_acc.data.borrow_mut())
+  }
+
+  // The share balance after the mint or burn must be the one settled
+  pub fn is_settled_balance(share_acc: &AccountInfo, shares: Shares) -> ProgramResult {
+    if cfg!(any(debug_assertions, feature = "strict"))
+      && Account::unpack(&share_acc.data.borrow())?.amount != shares.0
+    {
+      return Err(AppError::InvariantViolation.into());
+    }
+    Ok(())
+  }
+
+  pub fn is_debt_owner(
+    owner: &AccountInfo,
+    debt_acc: &AccountInfo,
+    stake_pool_acc: &AccountInfo,
+    share_acc: &AccountInfo,
+  ) -> ProgramResult {
+    let debt_data = Debt::unpack(&debt_acc.data.borrow())?;
+    if debt_data.stake_pool != *stake_pool_acc.key
+      || debt_data.owner != *owner.key
+      || debt_data.account != *share_acc.key
+    {
+      return Err(AppError::InvalidOwner.into());
+    }
+    Ok(())
+  }
+
+  pub fn settle(
+    shares: Shares,
+    next_shares: Shares,
+    debt_data: &mut Debt,
+    stake_pool_data: &mut StakePool,
+  ) -> Result<u64, ProgramError> {
+    // Get the basics
+    let debt = ScaledDebt(debt_data.debt);
+    let delay = Self::estimate_delay(*stake_pool_data)?;
+    let current_total_shares = Shares(stake_pool_data.total_shares);
+    let next_total_shares = current_total_shares
+      .checked_sub(shares)
+      .and_then(|total_shares| total_shares.checked_add(next_shares))
+      .ok_or(AppError::Overflow)?;
+    let (yeild, debt) = if stake_pool_data.is_accumulator() {
+      Self::update_pool(stake_pool_data)?;
+      let acc = ScaledAmount(stake_pool_data.acc_reward_per_share);
+      Pattern::settle_accumulated(shares, next_shares, debt, acc, stake_pool_data.precision())
+        .ok_or(AppError::Overflow)?
+    } else {
+      let compensation = ScaledComp(stake_pool_data.compensation);
+      let reward = stake_pool_data.reward;
+      let floor = ScaledAmount(stake_pool_data.min_reward_per_share);
+      let precision = stake_pool_data.precision();
+      // Fully harvest
+      let (_, harvested_debt, compensation) = Pattern::fully_harvest(
+        shares,
+        debt,
+        compensation,
+        delay,
+        reward,
+        floor,
+        current_total_shares,
+        current_total_shares,
+        precision,
+      )
+      .ok_or(AppError::Overflow)?;
+      let yeild = harvested_debt.yield_since(debt).ok_or(AppError::Overflow)?;
+      if next_shares == shares {
+        stake_pool_data.compensation = compensation.0;
+        (yeild, harvested_debt)
+      } else {
+        // Fully unstake
+        let remaining_total_shares = current_total_shares
+          .checked_sub(shares)
+          .ok_or(AppError::Overflow)?;
+        let (_, debt, compensation) = Pattern::fully_unstake(
+          shares,
+          harvested_debt,
+          compensation,
+          delay,
+          reward,
+          floor,
+          current_total_shares,
+          remaining_total_shares,
+          precision,
+        )
+        .ok_or(AppError::Overflow)?;
+        // Fully stake
+        let (_, debt, compensation) = Pattern::fully_stake(
+          next_shares,
+          debt,
+          compensation,
+          delay,
+          reward,
+          floor,
+          remaining_total_shares,
+          next_total_shares,
+          precision,
+        )
+        .ok_or(AppError::Overflow)?;
+        stake_pool_data.compensation = compensation.0;
+        (yeild, debt)
+      }
+    };
+    // The yield is everything claimable since the last settlement, not an increment
+    let (yeild, TokenAmount(forfeit)) = Pattern::settle_forfeit(
+      yeild,
+      TokenAmount(debt_data.forfeit),
+      debt_data.last_delay,
+      delay,
+    );
+    // Dormant positions lose what accrued beyond the claimable window
+    let elapsed = delay.saturating_sub(debt_data.last_settled_delay);
+    let TokenAmount(clamped) =
+      Pattern::clamp_yield(yeild, elapsed, stake_pool_data.max_accrual_periods)
+        .ok_or(AppError::Overflow)?;
+    if clamped != yeild.0 {
+      msg!("ACCRUAL_CLAMPED:{}", yeild.0 - clamped);
+    }
+    let yeild = clamped;
+
+    // Debt account
+    stake_pool_data.total_debt = stake_pool_data
+      .total_debt
+      .checked_sub(debt_data.debt)
+      .ok_or(AppError::Overflow)?
+      .checked_add(debt.0)
+      .ok_or(AppError::Overflow)?;
+    debt_data.forfeit = forfeit;
+    Self::track_pending(shares, next_shares, delay, debt_data, stake_pool_data)?;
+    if next_shares != shares {
+      debt_data.last_delay = delay;
+    }
+    debt_data.last_settled_delay = delay;
+    debt_data.debt = debt.0;
+    // Stake pool account
+    stake_pool_data.total_shares = next_total_shares.0;
+    // The reward silently stops accruing once the shares dilute it below one unit
+    if Self::is_reward_truncated(*stake_pool_data)? {
+      msg!("REWARD_TRUNCATED:{}", next_total_shares.0);
+    }
+
+    Ok(yeild)
+  }
+
+  pub fn update_pool(stake_pool_data: &mut StakePool) -> ProgramResult {
+    let delay = Self::estimate_delay(*stake_pool_data)?;
+    let elapsed = delay.saturating_sub(stake_pool_data.last_update_delay);
+    let reward = stake_pool_data.reward;
+    let floor = ScaledAmount(stake_pool_data.min_reward_per_share);
+    let precision = stake_pool_data.precision();
+    // The period in progress at the last update pays the shares it began with,
+    // the following ones saw no change and pay the total shares
+    let acc = Pattern::accumulate(
+      ScaledAmount(stake_pool_data.acc_reward_per_share),
+      reward,
+      floor,
+      Self::period_shares(stake_pool_data)?,
+      elapsed.min(1),
+      precision,
+    )
+    .and_then(|acc| {
+      Pattern::accumulate(
+        acc,
+        reward,
+        floor,
+        Shares(stake_pool_data.total_shares),
+        elapsed.saturating_sub(1),
+        precision,
+      )
+    })
+    .ok_or(AppError::Overflow)?;
+    stake_pool_data.acc_reward_per_share = acc.0;
+    stake_pool_data.last_update_delay = delay;
+    if elapsed > 0 {
+      stake_pool_data.pending_shares = 0;
+      stake_pool_data.departed_shares = 0;
+    }
+    Ok(())
+  }
+
+  // Move the pool to a new reward and floor, past periods keep the ones they were accrued with
+  pub fn rerate(
+    reward: u64,
+    min_reward_per_share: u128,
+    stake_pool_data: &mut StakePool,
+  ) -> ProgramResult {
+    if stake_pool_data.is_accumulator() {
+      Self::update_pool(stake_pool_data)?;
+    } else {
+      let delay = Self::estimate_delay(*stake_pool_data)?;
+      let compensation = Pattern::rerate_compensation(
+        (
+          stake_pool_data.reward,
+          ScaledAmount(stake_pool_data.min_reward_per_share),
+        ),
+        (reward, ScaledAmount(min_reward_per_share)),
+        Shares(stake_pool_data.total_shares),
+        ScaledComp(stake_pool_data.compensation),
+        delay,
+        stake_pool_data.precision(),
+      )
+      .ok_or(AppError::Overflow)?;
+      stake_pool_data.compensation = compensation.0;
+    }
+    stake_pool_data.reward = reward;
+    stake_pool_data.min_reward_per_share = min_reward_per_share;
+    Ok(())
+  }
+
+  // Shares the in-progress period began with, the divisor of its reward
+  pub fn period_shares(stake_pool_data: &StakePool) -> Result<Shares, ProgramError> {
+    let shares = stake_pool_data
+      .total_shares
+      .checked_sub(stake_pool_data.pending_shares)
+      .and_then(|shares| shares.checked_add(stake_pool_data.departed_shares))
+      .ok_or(AppError::Overflow)?;
+    Ok(Shares(shares))
+  }
+
+  // Shares staked during the in-progress period are pending until it ends,
+  // unstakes take them first and then count the rest as departed.
+  // Legacy pools split every period among the total shares.
+  pub fn track_pending(
+    shares: Shares,
+    next_shares: Shares,
+    delay: u64,
+    debt_data: &mut Debt,
+    stake_pool_data: &mut StakePool,
+  ) -> ProgramResult {
+    if !stake_pool_data.is_accumulator()
+      || Self::current_timestamp()? < stake_pool_data.genesis_timestamp
+    {
+      return Ok(());
+    }
+    if delay > debt_data.last_delay {
+      debt_data.pending_shares = 0;
+    }
+    if next_shares.0 >= shares.0 {
+      let added = next_shares.0 - shares.0;
+      debt_data.pending_shares = debt_data
+        .pending_shares
+        .checked_add(added)
+        .ok_or(AppError::Overflow)?;
+      stake_pool_data.pending_shares = stake_pool_data
+        .pending_shares
+        .checked_add(added)
+        .ok_or(AppError::Overflow)?;
+      return Ok(());
+    }
+    let removed = shares.0 - next_shares.0;
+    let unpended = removed.min(debt_data.pending_shares);
+    // Pending shares leaving before the boundary give up their forfeit
+    let TokenAmount(forfeit) = Pattern::estimate_forfeit(
+      stake_pool_data.reward,
+      ScaledAmount(stake_pool_data.min_reward_per_share),
+      Self::period_shares(stake_pool_data)?,
+      Shares(unpended),
+      stake_pool_data.precision(),
+    )
+    .ok_or(AppError::Overflow)?;
+    debt_data.pending_shares -= unpended;
+    debt_data.forfeit = if debt_data.pending_shares == 0 {
+      0
+    } else {
+      debt_data.forfeit.saturating_sub(forfeit)
+    };
+    stake_pool_data.pending_shares = stake_pool_data
+      .pending_shares
+      .checked_sub(unpended)
+      .ok_or(AppError::Overflow)?;
+    stake_pool_data.departed_shares = stake_pool_data
+      .departed_shares
+      .checked_add(removed - unpended)
+      .ok_or(AppError::Overflow)?;
+    Ok(())
+  }
+
+  // Move the unlocked part of the schedule to the claimable amount
+  // and restart the schedule on what remains locked
+  pub fn release_vesting(debt_data: &mut Debt) -> ProgramResult {
+    let now = Self::current_timestamp()?;
+    let unlocked = Pattern::unlock_vesting(
+      debt_data.vesting_amount,
+      debt_data.vesting_start,
+      debt_data.vesting_duration,
+      now,
+    )
+    .ok_or(AppError::Overflow)?;
+    let end = debt_data
+      .vesting_start
+      .checked_add(debt_data.vesting_duration as i64)
+      .ok_or(AppError::Overflow)?;
+    debt_data.vested_amount = debt_data
+      .vested_amount
+      .checked_add(unlocked)
+      .ok_or(AppError::Overflow)?;
+    debt_data.vesting_amount = debt_data
+      .vesting_amount
+      .checked_sub(unlocked)
+      .ok_or(AppError::Overflow)?;
+    debt_data.vesting_duration = end.saturating_sub(now).max(0) as u64;
+    debt_data.vesting_start = now;
+    Ok(())
+  }
+
+  // Return the yield to pay now, the rest vests for the pool's duration
+  // merged into the running schedule
+  pub fn vest(
+    yeild: u64,
+    debt_data: &mut Debt,
+    stake_pool_data: &mut StakePool,
+  ) -> Result<u64, ProgramError> {
+    if stake_pool_data.vesting_duration == 0 || yeild == 0 {
+      return Ok(yeild);
+    }
+    Self::release_vesting(debt_data)?;
+    debt_data.vesting_duration = Pattern::merge_vesting(
+      debt_data.vesting_amount,
+      debt_data.vesting_duration,
+      yeild,
+      stake_pool_data.vesting_duration,
+    )
+    .ok_or(AppError::Overflow)?;
+    debt_data.vesting_amount = debt_data
+      .vesting_amount
+      .checked_add(yeild)
+      .ok_or(AppError::Overflow)?;
+    stake_pool_data.total_vesting = stake_pool_data
+      .total_vesting
+      .checked_add(yeild)
+      .ok_or(AppError::Overflow)?;
+    msg!("VESTED:{}", yeild);
+    Ok(0)
+  }
+
+  pub fn is_reward_account(
+    dst_sen_acc: &AccountInfo,
+    stake_pool_data: &StakePool,
+  ) -> ProgramResult {
+    let dst_sen_data = Account::unpack(&dst_sen_acc.data.borrow())?;
+    if dst_sen_data.mint != stake_pool_data.mint_sen {
+      return Err(AppError::UnmatchedTreasurySen.into());
+    }
+    Ok(())
+  }
+
+  pub fn is_rent_exempt(
+    space: usize,
+    target_acc: &AccountInfo,
+    sysvar_rent_acc: &AccountInfo,
+  ) -> ProgramResult {
+    if target_acc.data_len() < space {
+      return Err(AppError::AccountTooSmall.into());
+    }
+    let rent = &Rent::from_account_info(sysvar_rent_acc)?;
+    if !rent.is_exempt(target_acc.lamports(), target_acc.data_len()) {
+      return Err(AppError::NotRentExempt.into());
+    }
+    Ok(())
+  }
+
+  pub fn treasurer_seed(
+    stake_pool_acc: &AccountInfo,
+    treasurer: &AccountInfo,
+    stake_pool_data: &StakePool,
+  ) -> Result<[u8; 32], ProgramError> {
+    // The treasurer was derived by safe_seed at initialization
+    if stake_pool_data.treasurer != *treasurer.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    Ok(stake_pool_acc.key.to_bytes())
+  }
+
+  pub fn safe_seed(
+    seed_acc: &AccountInfo,
+    expected_acc: &AccountInfo,
+    program_id: &Pubkey,
   ) -> Result<[u8; 32], PubkeyError> {
     let seed: [u8; 32] = seed_acc.key.to_bytes();
     let key = Pubkey::create_program_address(&[&seed], program_id)?;
@@ -875,13 +3461,521 @@ impl Processor {
     Ok(clock.unix_timestamp)
   }
 
+  pub fn is_unlocked(last_timestamp: i64, interval: u64) -> ProgramResult {
+    let current_timestamp = Self::current_timestamp()?;
+    let unlocked_timestamp = last_timestamp as i128 + interval as i128;
+    if (current_timestamp as i128) < unlocked_timestamp {
+      return Err(AppError::Locked.into());
+    }
+    Ok(())
+  }
+
   pub fn estimate_delay(stake_pool_data: StakePool) -> Result<u64, ProgramError> {
     let current_timestamp = Self::current_timestamp()?;
+    // Nothing accrues before genesis
+    if current_timestamp < stake_pool_data.genesis_timestamp {
+      return Ok(0);
+    }
     let delay =
       (current_timestamp - stake_pool_data.genesis_timestamp) as u64 / stake_pool_data.period;
     Ok(delay)
   }
 
+  pub fn estimate_liability(stake_pool_data: StakePool) -> Result<u128, ProgramError> {
+    let liability = if stake_pool_data.is_accumulator() {
+      let mut stake_pool_data = stake_pool_data;
+      Self::update_pool(&mut stake_pool_data)?;
+      Pattern::estimate_accumulated_liability(
+        Shares(stake_pool_data.total_shares),
+        ScaledAmount(stake_pool_data.acc_reward_per_share),
+        ScaledDebt(stake_pool_data.total_debt),
+        stake_pool_data.precision(),
+      )
+      .ok_or(AppError::Overflow)?
+    } else {
+      let delay = Self::estimate_delay(stake_pool_data)?;
+      Pattern::estimate_liability(
+        stake_pool_data.reward,
+        ScaledAmount(stake_pool_data.min_reward_per_share),
+        Shares(stake_pool_data.total_shares),
+        ScaledComp(stake_pool_data.compensation),
+        delay,
+        ScaledDebt(stake_pool_data.total_debt),
+        stake_pool_data.precision(),
+      )
+      .ok_or(AppError::Overflow)?
+    };
+    // Harvested yield still vesting stays in the treasury
+    let liability = liability
+      .0
+      .checked_add(stake_pool_data.total_vesting as u128)
+      .ok_or(AppError::Overflow)?;
+    Ok(liability)
+  }
+
+  // Every withdrawal of seed counts against the rolling window,
+  // capped to a share of the treasury balance when the window opened
+  pub fn track_unseed(
+    amount: u64,
+    treasury_sen_acc: &AccountInfo,
+    stake_pool_data: &mut StakePool,
+  ) -> ProgramResult {
+    if stake_pool_data.max_unseed_bps_per_day == 0 {
+      return Ok(());
+    }
+    if Self::is_unlocked(stake_pool_data.unseed_window_start, UNSEED_WINDOW).is_ok() {
+      let treasury_sen_data = Account::unpack(&treasury_sen_acc.data.borrow())?;
+      stake_pool_data.unseed_window_start = Self::current_timestamp()?;
+      stake_pool_data.unseed_window_balance = treasury_sen_data.amount;
+      stake_pool_data.unseeded_in_window = 0;
+    }
+    let limit = (stake_pool_data.unseed_window_balance as u128)
+      .checked_mul(stake_pool_data.max_unseed_bps_per_day as u128)
+      .ok_or(AppError::Overflow)?
+      / 10000;
+    let unseeded = stake_pool_data
+      .unseeded_in_window
+      .checked_add(amount)
+      .ok_or(AppError::Overflow)?;
+    if unseeded as u128 > limit {
+      return Err(AppError::UnseedLimitExceeded.into());
+    }
+    stake_pool_data.unseeded_in_window = unseeded;
+    Ok(())
+  }
+
+  pub fn is_solvent_after(
+    amount: u64,
+    treasury_sen_acc: &AccountInfo,
+    stake_pool_data: StakePool,
+  ) -> ProgramResult {
+    let treasury_sen_data = Account::unpack(&treasury_sen_acc.data.borrow())?;
+    let liability = Self::estimate_liability(stake_pool_data)?;
+    let remaining = treasury_sen_data
+      .amount
+      .checked_sub(amount)
+      .ok_or(AppError::InsufficientFunds)?;
+    if (remaining as u128) < liability {
+      return Err(AppError::InsufficientFunds.into());
+    }
+    Ok(())
+  }
+
+  pub fn is_security_council(
+    council: &AccountInfo,
+    council_acc: &AccountInfo,
+    program_id: &Pubkey,
+  ) -> ProgramResult {
+    let (key, _) = Pubkey::find_program_address(&[b"security_council"], program_id);
+    if key != *council_acc.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    let council_data = SecurityCouncil::unpack(&council_acc.data.borrow())?;
+    if council_data.council != *council.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    Ok(())
+  }
+
+  pub fn is_allowed_mint(
+    mint_token_acc: &AccountInfo,
+    registry_acc: &AccountInfo,
+    mint_whitelist_acc: &AccountInfo,
+    program_id: &Pubkey,
+  ) -> ProgramResult {
+    Self::is_program(program_id, &[registry_acc])?;
+    // Validate mint whitelist address
+    let (key, _) = Pubkey::find_program_address(
+      &[&registry_acc.key.to_bytes(), b"mint_whitelist"],
+      program_id,
+    );
+    if key != *mint_whitelist_acc.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    Self::is_program(program_id, &[mint_whitelist_acc])?;
+    let mint_whitelist_data = MintWhitelist::unpack(&mint_whitelist_acc.data.borrow())?;
+    if mint_whitelist_data.registry != *registry_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    if !mint_whitelist_data.is_allowed(mint_token_acc.key) {
+      return Err(AppError::MintNotAllowed.into());
+    }
+    Ok(())
+  }
+
+  pub fn register_pool<'a>(
+    stake_pool_acc: &AccountInfo<'a>,
+    registry_acc: &AccountInfo<'a>,
+    registry_page_acc: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    sysvar_rent_acc: &AccountInfo<'a>,
+    program_id: &Pubkey,
+  ) -> ProgramResult {
+    Self::is_program(program_id, &[registry_acc])?;
+    let mut registry_data = Registry::unpack(&registry_acc.data.borrow())?;
+    let (page, slot) = registry_data.next_slot();
+
+    // Validate registry page address
+    let (key, bump_seed) = Pubkey::find_program_address(
+      &[
+        &registry_acc.key.to_bytes(),
+        &page.to_le_bytes(),
+        b"registry_page",
+      ],
+      program_id,
+    );
+    if key != *registry_page_acc.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    // Rent a new page when the previous one is full
+    if slot == 0 {
+      let seed: &[&[u8]] = &[
+        &registry_acc.key.to_bytes(),
+        &page.to_le_bytes(),
+        b"registry_page",
+        &[bump_seed],
+      ];
+      Self::alloc_account(
+        RegistryPage::LEN,
+        registry_page_acc,
+        payer,
+        program_id,
+        sysvar_rent_acc,
+        system_program,
+        &[seed],
+      )?;
+      let mut page_data = RegistryPage::unpack_unchecked(&registry_page_acc.data.borrow())?;
+      if page_data.is_initialized() {
+        return Err(AppError::ConstructorOnce.into());
+      }
+      page_data.registry = *registry_acc.key;
+      page_data.index = page;
+      page_data.is_initialized = true;
+      RegistryPage::pack(page_data, &mut registry_page_acc.data.borrow_mut())?;
+    }
+
+    // Append the pool
+    Self::is_program(program_id, &[registry_page_acc])?;
+    let mut page_data = RegistryPage::unpack(&registry_page_acc.data.borrow())?;
+    page_data.pools[slot] = *stake_pool_acc.key;
+    RegistryPage::pack(page_data, &mut registry_page_acc.data.borrow_mut())?;
+    registry_data.total_pools = registry_data
+      .total_pools
+      .checked_add(1)
+      .ok_or(AppError::Overflow)?;
+    Registry::pack(registry_data, &mut registry_acc.data.borrow_mut())?;
+
+    Ok(())
+  }
+
+  // Stake pool settings are taken from the template, the rest from the accounts
+  fn create_stake_pool(
+    template: &StakePool,
+    genesis_timestamp: i64,
+    pool_seed: Option<[u8; 32]>,
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let InitializeStakePoolContext {
+      payer,
+      owner,
+      stake_pool_acc,
+      mint_share_acc,
+      proof_acc,
+      mint_token_acc,
+      treasury_token_acc,
+      mint_sen_acc,
+      treasury_sen_acc,
+      treasurer,
+      system_program,
+      splt_program,
+      sysvar_rent_acc,
+      splata_program,
+    } = InitializeStakePoolContext::load(program_id, accounts)?;
+
+    // Half of the keys hash onto the curve and cannot derive a treasurer
+    if Pubkey::create_program_address(&[&stake_pool_acc.key.to_bytes()], program_id).is_err() {
+      return Err(AppError::InvalidPoolSeed.into());
+    }
+    // Rent stake pool account, unless it was pre-created by the client.
+    // A derived stake pool is signed for by the program, otherwise by its keypair
+    match pool_seed {
+      Some(pool_seed) => {
+        let (key, bump_seed) =
+          Pubkey::find_program_address(&[b"stake_pool", &pool_seed], program_id);
+        if key != *stake_pool_acc.key {
+          return Err(AppError::InvalidOwner.into());
+        }
+        if stake_pool_acc.owner != program_id {
+          let seed: &[&[u8]] = &[b"stake_pool", &pool_seed, &[bump_seed]];
+          Self::alloc_account(
+            StakePool::LEN,
+            stake_pool_acc,
+            payer,
+            program_id,
+            sysvar_rent_acc,
+            system_program,
+            &[seed],
+          )?;
+        }
+      }
+      None => {
+        Self::is_signer(&[stake_pool_acc])?;
+        if stake_pool_acc.owner != program_id {
+          Self::alloc_account(
+            StakePool::LEN,
+            stake_pool_acc,
+            payer,
+            program_id,
+            sysvar_rent_acc,
+            system_program,
+            &[],
+          )?;
+        }
+      }
+    }
+    Self::is_rent_exempt(StakePool::LEN, stake_pool_acc, sysvar_rent_acc)?;
+    // Rent mint share account
+    Self::alloc_account(
+      Mint::LEN,
+      mint_share_acc,
+      payer,
+      splt_program.key,
+      sysvar_rent_acc,
+      system_program,
+      &[],
+    )?;
+
+    Self::is_program(program_id, &[stake_pool_acc])?;
+    Self::is_program(splt_program.key, &[mint_share_acc])?;
+
+    let mut stake_pool_data = StakePool::unpack_unchecked(&stake_pool_acc.data.borrow())?;
+    let mint_share_data = Mint::unpack_unchecked(&mint_share_acc.data.borrow())?;
+    let seed: &[&[&[u8]]] = &[&[&Self::safe_seed(stake_pool_acc, treasurer, program_id)?[..]]];
+    if stake_pool_data.is_initialized() || mint_share_data.is_initialized() {
+      return Err(AppError::ConstructorOnce.into());
+    }
+    if *proof_acc.key != program_id.xor(&(stake_pool_acc.key.xor(treasurer.key))) {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    // Clients build the instruction with the same validation
+    PoolParams {
+      reward: template.reward,
+      period: template.period,
+      precision_exp: template.precision_exp,
+    }
+    .validate()?;
+    // A default owner would leave the pool unmanageable
+    if *owner.key == Pubkey::default() {
+      return Err(AppError::InvalidOwner.into());
+    }
+    // Genesis may be scheduled, but not too far ahead nor back
+    let current_timestamp = Self::current_timestamp()?;
+    let genesis_timestamp = if genesis_timestamp == 0 {
+      current_timestamp
+    } else {
+      genesis_timestamp
+    };
+    if genesis_timestamp as i128 > current_timestamp as i128 + MAX_GENESIS_DELAY as i128
+      || (genesis_timestamp as i128) < current_timestamp as i128 - template.period as i128
+    {
+      return Err(AppError::InvalidGenesis.into());
+    }
+    // A registry lists only the stake mints its whitelist allows
+    let len = InitializeStakePoolContext::SPECS.len();
+    if let Some(registry_acc) = accounts.get(len) {
+      // Without its whitelist the mint cannot be shown allowed
+      let mint_whitelist_acc = accounts.get(len + 2).ok_or(AppError::MintNotAllowed)?;
+      Self::is_allowed_mint(mint_token_acc, registry_acc, mint_whitelist_acc, program_id)?;
+    }
+
+    // Treasuries are the treasurer's associated accounts under the given token program
+    for (treasury_acc, mint_acc) in [
+      (treasury_token_acc, mint_token_acc),
+      (treasury_sen_acc, mint_sen_acc),
+    ]
+    .iter()
+    {
+      let key = associated_token_address(
+        treasurer.key,
+        mint_acc.key,
+        splt_program.key,
+        splata_program.key,
+      );
+      if key != *treasury_acc.key {
+        return Err(AppError::UnmatchedTreasury.into());
+      }
+    }
+
+    // Initialize treasury token
+    XSPLATA::initialize_account(
+      payer,
+      treasury_token_acc,
+      treasurer,
+      mint_token_acc,
+      system_program,
+      splt_program,
+      sysvar_rent_acc,
+      splata_program,
+      &[],
+    )?;
+
+    // Initialize treasury sen
+    XSPLATA::initialize_account(
+      payer,
+      treasury_sen_acc,
+      treasurer,
+      mint_sen_acc,
+      system_program,
+      splt_program,
+      sysvar_rent_acc,
+      splata_program,
+      &[],
+    )?;
+
+    // Initialize mint share
+    let mint_token_data = Mint::unpack_unchecked(&mint_token_acc.data.borrow())?;
+    XSPLT::initialize_mint(
+      mint_token_data.decimals,
+      mint_share_acc,
+      treasurer,
+      proof_acc,
+      sysvar_rent_acc,
+      splt_program,
+      seed,
+    )?;
+    // Only the treasurer may ever mint shares
+    let mint_share_data = Mint::unpack(&mint_share_acc.data.borrow())?;
+    if mint_share_data.mint_authority != COption::Some(*treasurer.key) {
+      return Err(AppError::InvalidMintAuthority.into());
+    }
+
+    // Update stake pool data
+    stake_pool_data.owner = *owner.key;
+    stake_pool_data.state = StakePoolState::Initialized;
+    stake_pool_data.genesis_timestamp = genesis_timestamp;
+    stake_pool_data.total_shares = 0;
+    stake_pool_data.mint_share = *mint_share_acc.key;
+    stake_pool_data.mint_token = *mint_token_acc.key;
+    stake_pool_data.treasury_token = *treasury_token_acc.key;
+    stake_pool_data.reward = template.reward;
+    stake_pool_data.period = template.period;
+    stake_pool_data.compensation = 0;
+    stake_pool_data.mint_sen = *mint_sen_acc.key;
+    stake_pool_data.treasury_sen = *treasury_sen_acc.key;
+    stake_pool_data.max_crank_amount = template.max_crank_amount;
+    stake_pool_data.crank_interval = template.crank_interval;
+    stake_pool_data.crank_tip = template.crank_tip;
+    stake_pool_data.vesting_duration = template.vesting_duration;
+    stake_pool_data.last_crank_timestamp = 0;
+    stake_pool_data.last_state_change = 0;
+    stake_pool_data.total_debt = 0;
+    stake_pool_data.min_reward_per_share = template.min_reward_per_share;
+    stake_pool_data.precision_exp = template.precision_exp;
+    stake_pool_data.max_unseed_bps_per_day = template.max_unseed_bps_per_day;
+    stake_pool_data.unseed_window_start = 0;
+    stake_pool_data.unseeded_in_window = 0;
+    stake_pool_data.unseed_window_balance = 0;
+    stake_pool_data.pending_shares = 0;
+    stake_pool_data.departed_shares = 0;
+    stake_pool_data.unseed_destination = Pubkey::default();
+    stake_pool_data.min_harvest_interval = template.min_harvest_interval;
+    stake_pool_data.allow_pre_stake = template.allow_pre_stake;
+    stake_pool_data.version = ACCUMULATOR_VERSION;
+    stake_pool_data.acc_reward_per_share = 0;
+    stake_pool_data.last_update_delay = 0;
+    stake_pool_data.treasurer = *treasurer.key;
+    stake_pool_data.cumulative_staked = 0;
+    stake_pool_data.cumulative_unstaked = 0;
+    stake_pool_data.activation_timestamp = 0;
+    stake_pool_data.max_accrual_periods = template.max_accrual_periods;
+    stake_pool_data.last_admin_action = current_timestamp;
+    Self::pack_stake_pool(stake_pool_data, stake_pool_acc)?;
+
+    // List the pool if the registry accounts are provided
+    if let (Some(registry_acc), Some(registry_page_acc)) =
+      (accounts.get(len), accounts.get(len + 1))
+    {
+      Self::register_pool(
+        stake_pool_acc,
+        registry_acc,
+        registry_page_acc,
+        payer,
+        system_program,
+        sysvar_rent_acc,
+        program_id,
+      )?;
+    }
+
+    events::emit_pool_created(
+      stake_pool_acc.key,
+      owner.key,
+      mint_token_acc.key,
+      mint_share_acc.key,
+      stake_pool_data.reward,
+      stake_pool_data.period,
+    );
+
+    Ok(())
+  }
+
+  pub fn close_account(target_acc: &AccountInfo, dst_acc: &AccountInfo) -> ProgramResult {
+    let target_starting_lamports = target_acc.lamports();
+    let dst_starting_lamports = dst_acc.lamports();
+    let expected_lamports = dst_starting_lamports
+      .checked_add(target_starting_lamports)
+      .ok_or(AppError::Overflow)?;
+    **dst_acc.lamports.borrow_mut() = expected_lamports;
+    **target_acc.lamports.borrow_mut() = 0;
+    // Every lamport of the closed account must land in the destination
+    if target_acc.lamports() != 0 || dst_acc.lamports() != expected_lamports {
+      return Err(AppError::UnbalancedLamports.into());
+    }
+    Ok(())
+  }
+
+  // Tips come from the treasury surplus, never from the stakers' yield
+  pub fn tip_cranker<'a>(
+    cranker: &AccountInfo<'a>,
+    dst_sen_acc: &AccountInfo<'a>,
+    treasury_sen_acc: &AccountInfo<'a>,
+    treasurer: &AccountInfo<'a>,
+    splt_program: &AccountInfo<'a>,
+    stake_pool_acc: &AccountInfo<'a>,
+    stake_pool_data: &StakePool,
+  ) -> ProgramResult {
+    let tip = stake_pool_data.crank_tip;
+    if tip == 0 {
+      return Ok(());
+    }
+    Self::is_signer(&[cranker])?;
+    let dst_sen_data = Account::unpack(&dst_sen_acc.data.borrow())?;
+    if dst_sen_data.owner != *cranker.key {
+      return Err(AppError::InvalidOwner.into());
+    }
+    Self::is_reward_account(dst_sen_acc, stake_pool_data)?;
+    if stake_pool_data.treasury_sen != *treasury_sen_acc.key {
+      return Err(AppError::UnmatchedPool.into());
+    }
+    let seed: &[&[&[u8]]] =
+      &[&[&Self::treasurer_seed(stake_pool_acc, treasurer, stake_pool_data)?[..]]];
+    if Self::is_solvent_after(tip, treasury_sen_acc, *stake_pool_data).is_err() {
+      msg!("CRANK_TIP_SKIPPED:INSUFFICIENT_FUNDS");
+      return Ok(());
+    }
+    XSPLT::transfer(
+      tip,
+      treasury_sen_acc,
+      dst_sen_acc,
+      treasurer,
+      splt_program,
+      seed,
+    )?;
+    msg!("CRANK_TIP:{}", tip);
+    Ok(())
+  }
+
   pub fn alloc_account<'a>(
     space: usize,
     target_acc: &AccountInfo<'a>,
@@ -919,3 +4013,6 @@ impl Processor {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests;
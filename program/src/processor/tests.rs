@@ -0,0 +1,32 @@
+//!
+//! Handler tests over the fake bank of test_utils
+//!
+
+mod accrual;
+mod activation;
+mod batch;
+mod boundary;
+mod chaos;
+mod clock;
+mod crank;
+mod debt_binding;
+mod distribution;
+mod exit;
+mod genesis;
+mod harvest;
+mod idempotency;
+mod initialize;
+mod layout;
+mod migrate;
+mod owner_validation;
+mod precision;
+mod reference;
+mod registry;
+mod rescale;
+mod reward;
+mod roles;
+mod share_mint;
+mod simulation;
+mod unseed;
+mod vesting;
+mod withdraw_only;
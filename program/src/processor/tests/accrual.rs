@@ -0,0 +1,192 @@
+use crate::error::AppError;
+use crate::processor::Processor;
+use crate::test_utils::{warp_to, PoolFixture, GENESIS};
+use crate::PRECISION;
+
+#[test]
+fn test_top_up_keeps_the_reward_accrued_on_the_first_stake() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let incumbent = fixture.add_staker(1000);
+  fixture.stake(&incumbent, 1000).unwrap();
+  let staker = fixture.add_staker(2000);
+  // The first stake accrues from 120, the top-up from 240
+  warp_to(GENESIS + 61);
+  fixture.stake(&staker, 1000).unwrap();
+  warp_to(GENESIS + 181);
+  fixture.stake(&staker, 1000).unwrap();
+  warp_to(GENESIS + 301);
+  fixture.harvest(&staker).unwrap();
+  // 1000 of 2000 shares for two periods, then 1000 and 1000 of 3000 for one
+  let first = 100.0 * 1000.0 / 2000.0 * 2.0 + 100.0 * 1000.0 / 3000.0;
+  let top_up = 100.0 * 1000.0 / 3000.0;
+  let harvested = fixture.bank.token_amount(&staker.sen_acc) as f64;
+  assert!(
+    (first + top_up - harvested).abs() <= 2.0,
+    "harvested {} for {} + {}",
+    harvested,
+    first,
+    top_up
+  );
+}
+
+// Alone in the pool from genesis, rerated from 100 to 300 per period at 181
+fn harvest_across_a_rate_change(version: u8) -> u64 {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.version = version);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  warp_to(GENESIS + 181);
+  fixture.update_stake_pool(|stake_pool_data| {
+    let floor = stake_pool_data.min_reward_per_share;
+    Processor::rerate(300, floor, stake_pool_data).unwrap()
+  });
+  warp_to(GENESIS + 301);
+  fixture.harvest(&staker).unwrap();
+  fixture.bank.token_amount(&staker.sen_acc)
+}
+
+#[test]
+fn test_reward_change_keeps_the_old_rate_for_past_periods() {
+  // Periods 1 and 2 at the old rate, 3 and 4 at the new one
+  assert_eq!(harvest_across_a_rate_change(1), 2 * 100 + 2 * 300);
+}
+
+#[test]
+fn test_reward_change_rebases_the_legacy_compensation() {
+  assert_eq!(harvest_across_a_rate_change(0), 2 * 100 + 2 * 300);
+}
+
+#[test]
+fn test_reward_is_claimable_from_the_exact_end_of_the_first_period() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.update_stake_pool(|stake_pool_data| {
+    stake_pool_data.genesis_timestamp = GENESIS + 60;
+    stake_pool_data.allow_pre_stake = true;
+  });
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  warp_to(GENESIS + 60 + 59);
+  fixture.harvest(&staker).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.sen_acc), 0);
+  warp_to(GENESIS + 60 + 60);
+  fixture.harvest(&staker).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.sen_acc), 100);
+  assert_eq!(
+    fixture.stake_pool_data().acc_reward_per_share,
+    100 * PRECISION / 1000
+  );
+}
+
+#[test]
+fn test_unstake_books_the_principal_it_returns() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  fixture.unstake(&staker, 300).unwrap();
+  fixture.unstake(&staker, 200).unwrap();
+  let stake_pool_data = fixture.stake_pool_data();
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 500);
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_token), 500);
+  assert_eq!(stake_pool_data.total_shares, 500);
+  assert_eq!(stake_pool_data.cumulative_unstaked, 500);
+  assert_eq!(
+    stake_pool_data.cumulative_staked - stake_pool_data.cumulative_unstaked,
+    stake_pool_data.total_shares as u128
+  );
+}
+
+#[test]
+fn test_unstake_of_more_than_the_total_shares_fails_cleanly() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.total_shares = 400);
+  assert_eq!(
+    fixture.unstake(&staker, 500).unwrap_err(),
+    AppError::Overflow.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 0);
+}
+
+#[test]
+fn test_harvests_stay_exact_across_compensation_sign_changes() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.version = 0);
+  fixture.seed(1_000_000).unwrap();
+  let holder = fixture.add_staker(1000);
+  fixture.stake(&holder, 1000).unwrap();
+  let whale = fixture.add_staker(1_000_000);
+  // Periods in which the whale stakes then unstakes everything
+  let steps = [(1, true), (3, false), (6, true), (10, false), (12, true)];
+  let mut signs = vec![];
+  for (period, stake) in steps.iter() {
+    warp_to(GENESIS + 60 * period + 30);
+    if *stake {
+      fixture.stake(&whale, 1_000_000).unwrap();
+    } else {
+      fixture.unstake(&whale, 1_000_000).unwrap();
+    }
+    signs.push(fixture.stake_pool_data().compensation.signum());
+    fixture.harvest(&holder).unwrap();
+    // Every period splits among the shares at its end, the first one is forfeited
+    let expected: f64 = (1..*period)
+      .map(|past| {
+        let whale_in = (1..3).contains(&past) || (6..10).contains(&past);
+        100.0 * 1000.0 / if whale_in { 1_001_000.0 } else { 1000.0 }
+      })
+      .sum();
+    let harvested = fixture.bank.token_amount(&holder.sen_acc) as f64;
+    assert!(
+      (expected - harvested).abs() <= 1.0,
+      "harvested {} for {} after period {}",
+      harvested,
+      expected,
+      period
+    );
+  }
+  assert_eq!(signs, vec![1, -1, 1, -1, 1]);
+}
+
+// Yield of a first stake at 270, after a whale churned through the pool
+fn first_stake_after_churn(version: u8) -> u64 {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.version = version);
+  fixture.seed(1_000_000).unwrap();
+  let holder = fixture.add_staker(1000);
+  fixture.stake(&holder, 1000).unwrap();
+  // A whale in and out mid-period leaves the compensation behind
+  let whale = fixture.add_staker(1_000_000);
+  warp_to(GENESIS + 90);
+  fixture.stake(&whale, 1_000_000).unwrap();
+  warp_to(GENESIS + 210);
+  fixture.unstake(&whale, 1_000_000).unwrap();
+  if version == 0 {
+    assert_ne!(fixture.stake_pool_data().compensation, 0);
+  }
+  let fresh = fixture.add_staker(1000);
+  warp_to(GENESIS + 270);
+  fixture.stake(&fresh, 1000).unwrap();
+  assert_eq!(fixture.claimable(&fresh), 0);
+  fixture.harvest(&fresh).unwrap();
+  assert_eq!(fixture.bank.token_amount(&fresh.sen_acc), 0);
+  // Accrues from 300, half of the periods ending at 360 and 420
+  warp_to(GENESIS + 421);
+  fixture.harvest(&fresh).unwrap();
+  fixture.bank.token_amount(&fresh.sen_acc)
+}
+
+#[test]
+fn test_first_stake_after_churn_claims_nothing_inherited() {
+  for version in [0, 1].iter() {
+    let harvested = first_stake_after_churn(*version);
+    assert!(
+      (harvested as i64 - 100).abs() <= 1,
+      "harvested {} at version {}",
+      harvested,
+      version
+    );
+  }
+}
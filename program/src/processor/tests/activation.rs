@@ -0,0 +1,41 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::test_utils::{warp_to, PoolFixture, GENESIS};
+
+fn activated_at(activation_timestamp: i64) -> PoolFixture {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture
+    .send_owner(&AppInstruction::SetActivation {
+      activation_timestamp,
+    })
+    .unwrap();
+  fixture
+}
+
+#[test]
+fn test_stake_waits_for_the_activation() {
+  let mut fixture = activated_at(GENESIS + 600);
+  // Operators seed and stakers set up their accounts beforehand
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  for timestamp in [GENESIS, GENESIS + 599].iter() {
+    warp_to(*timestamp);
+    assert_eq!(
+      fixture.stake(&staker, 1000).unwrap_err(),
+      AppError::PoolNotActive.into()
+    );
+  }
+  warp_to(GENESIS + 600);
+  fixture.stake(&staker, 1000).unwrap();
+  assert_eq!(fixture.stake_pool_data().total_shares, 1000);
+}
+
+#[test]
+fn test_zero_or_past_activation_is_live() {
+  for activation_timestamp in [0, GENESIS - 1, GENESIS].iter() {
+    let mut fixture = activated_at(*activation_timestamp);
+    fixture.seed(1_000_000).unwrap();
+    let staker = fixture.add_staker(1000);
+    fixture.stake(&staker, 1000).unwrap();
+  }
+}
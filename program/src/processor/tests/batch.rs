@@ -0,0 +1,59 @@
+use crate::context::TransferOwnershipBatchContext;
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::test_utils::{metas, PoolFixture};
+use solana_program::{entrypoint::ProgramResult, instruction::AccountMeta, pubkey::Pubkey};
+
+// Hand the fixture pool over once per pair, whatever the count says
+fn transfer_ownership_batch(fixture: &mut PoolFixture, count: u8, pairs: usize) -> ProgramResult {
+  let mut metas = metas(TransferOwnershipBatchContext::SPECS, &[fixture.owner]);
+  for _ in 0..pairs {
+    metas.push(AccountMeta::new(fixture.stake_pool, false));
+    metas.push(AccountMeta::new_readonly(Pubkey::new_unique(), false));
+  }
+  fixture.send_metas(&AppInstruction::TransferOwnershipBatch { count }, metas)
+}
+
+#[test]
+fn test_batch_with_the_exact_accounts_goes_through() {
+  let mut fixture = PoolFixture::new(100, 60);
+  transfer_ownership_batch(&mut fixture, 1, 1).unwrap();
+  assert_ne!(fixture.stake_pool_data().owner, fixture.owner);
+}
+
+#[test]
+fn test_batch_with_too_few_accounts_fails_before_any_work() {
+  let mut fixture = PoolFixture::new(100, 60);
+  assert_eq!(
+    transfer_ownership_batch(&mut fixture, 2, 1).unwrap_err(),
+    AppError::BatchAccountsMismatch.into()
+  );
+  assert_eq!(fixture.stake_pool_data().owner, fixture.owner);
+}
+
+#[test]
+fn test_batch_with_too_many_accounts_fails_before_any_work() {
+  let mut fixture = PoolFixture::new(100, 60);
+  assert_eq!(
+    transfer_ownership_batch(&mut fixture, 1, 2).unwrap_err(),
+    AppError::BatchAccountsMismatch.into()
+  );
+  assert_eq!(fixture.stake_pool_data().owner, fixture.owner);
+}
+
+#[test]
+fn test_tvl_batch_checks_the_pairs_against_the_count() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let pair = [fixture.stake_pool, fixture.treasury_token];
+  let ix = AppInstruction::GetTvlBatch { count: 2 };
+  assert_eq!(
+    fixture.send(&ix, &pair).unwrap_err(),
+    AppError::BatchAccountsMismatch.into()
+  );
+  let ix = AppInstruction::GetTvlBatch { count: 1 };
+  assert_eq!(
+    fixture.send(&ix, &[pair, pair].concat()).unwrap_err(),
+    AppError::BatchAccountsMismatch.into()
+  );
+  fixture.send(&ix, &pair).unwrap();
+}
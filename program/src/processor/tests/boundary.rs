@@ -0,0 +1,95 @@
+use crate::test_utils::{warp_to, PoolFixture, Staker, GENESIS};
+
+// A staker joining at genesis, active from the second period
+fn fixture_with_incumbent() -> (PoolFixture, Staker) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let incumbent = fixture.add_staker(2000);
+  fixture.stake(&incumbent, 1000).unwrap();
+  (fixture, incumbent)
+}
+
+fn harvested(fixture: &mut PoolFixture, staker: &Staker) -> u64 {
+  let before = fixture.bank.token_amount(&staker.sen_acc);
+  fixture.harvest(staker).unwrap();
+  fixture.bank.token_amount(&staker.sen_acc) - before
+}
+
+#[test]
+fn test_stake_one_second_before_the_boundary_does_not_dilute_the_period() {
+  let (mut fixture, incumbent) = fixture_with_incumbent();
+  let newcomer = fixture.add_staker(1000);
+  warp_to(GENESIS + 119);
+  fixture.stake(&newcomer, 1000).unwrap();
+  warp_to(GENESIS + 120);
+  assert_eq!(harvested(&mut fixture, &incumbent), 100);
+  assert_eq!(harvested(&mut fixture, &newcomer), 0);
+  warp_to(GENESIS + 180);
+  assert_eq!(harvested(&mut fixture, &incumbent), 50);
+  assert_eq!(harvested(&mut fixture, &newcomer), 50);
+}
+
+#[test]
+fn test_stake_at_the_exact_boundary_waits_for_the_next_one() {
+  let (mut fixture, incumbent) = fixture_with_incumbent();
+  let newcomer = fixture.add_staker(1000);
+  warp_to(GENESIS + 120);
+  fixture.stake(&newcomer, 1000).unwrap();
+  warp_to(GENESIS + 179);
+  assert_eq!(harvested(&mut fixture, &newcomer), 0);
+  warp_to(GENESIS + 180);
+  assert_eq!(harvested(&mut fixture, &incumbent), 200);
+  assert_eq!(harvested(&mut fixture, &newcomer), 0);
+  warp_to(GENESIS + 240);
+  assert_eq!(harvested(&mut fixture, &newcomer), 50);
+}
+
+#[test]
+fn test_boundary_clears_the_pending_shares() {
+  let (mut fixture, _) = fixture_with_incumbent();
+  let newcomer = fixture.add_staker(1000);
+  warp_to(GENESIS + 90);
+  fixture.stake(&newcomer, 1000).unwrap();
+  assert_eq!(fixture.stake_pool_data().pending_shares, 1000);
+  assert_eq!(fixture.debt_data(&newcomer).pending_shares, 1000);
+  warp_to(GENESIS + 120);
+  fixture.harvest(&newcomer).unwrap();
+  assert_eq!(fixture.stake_pool_data().pending_shares, 0);
+  assert_eq!(fixture.debt_data(&newcomer).pending_shares, 0);
+  assert_eq!(fixture.debt_data(&newcomer).forfeit, 0);
+}
+
+#[test]
+fn test_pending_shares_leaving_before_the_boundary_drop_their_forfeit() {
+  let (mut fixture, incumbent) = fixture_with_incumbent();
+  warp_to(GENESIS + 60);
+  let other = fixture.add_staker(2000);
+  fixture.stake(&other, 1000).unwrap();
+  warp_to(GENESIS + 150);
+  fixture.stake(&other, 1000).unwrap();
+  warp_to(GENESIS + 179);
+  fixture.unstake(&other, 1000).unwrap();
+  assert_eq!(fixture.debt_data(&other).forfeit, 0);
+  assert_eq!(fixture.stake_pool_data().departed_shares, 0);
+  warp_to(GENESIS + 180);
+  assert_eq!(harvested(&mut fixture, &incumbent), 150);
+  assert_eq!(harvested(&mut fixture, &other), 50);
+}
+
+#[test]
+fn test_incumbent_leaving_mid_period_keeps_its_part_in_the_treasury() {
+  let (mut fixture, incumbent) = fixture_with_incumbent();
+  let other = fixture.add_staker(1000);
+  fixture.stake(&other, 1000).unwrap();
+  warp_to(GENESIS + 150);
+  assert_eq!(harvested(&mut fixture, &incumbent), 50);
+  fixture.unstake(&other, 1000).unwrap();
+  assert_eq!(fixture.stake_pool_data().departed_shares, 1000);
+  let treasury = fixture.bank.token_amount(&fixture.treasury_sen);
+  warp_to(GENESIS + 180);
+  assert_eq!(harvested(&mut fixture, &incumbent), 50);
+  assert_eq!(
+    fixture.bank.token_amount(&fixture.treasury_sen),
+    treasury - 50
+  );
+}
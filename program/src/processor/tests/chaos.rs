@@ -0,0 +1,140 @@
+use crate::schema::{debt::Debt, stake_pool::StakePool};
+use crate::test_utils::{
+  cpi_count, fail_cpi_at, warp_to, PoolFixture, Staker, GENESIS, INJECTED_FAILURE,
+};
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, program_pack::Pack};
+
+type Operation = fn(&mut PoolFixture, &Staker) -> ProgramResult;
+
+// A seeded pool with a position staked two periods ago, and a wallet yet to join
+fn setup() -> (PoolFixture, Staker, Staker) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(2000);
+  fixture.stake(&staker, 1000).unwrap();
+  let newcomer = fixture.new_staker(1000);
+  warp_to(GENESIS + 150);
+  (fixture, staker, newcomer)
+}
+
+// Everything an operation may change, free of the keys that differ between fixtures
+fn snapshot(fixture: &PoolFixture, staker: &Staker, newcomer: &Staker) -> Vec<i128> {
+  let bank = &fixture.bank;
+  let stake_pool_data: StakePool = fixture.stake_pool_data();
+  let mut state = vec![
+    stake_pool_data.total_shares as i128,
+    stake_pool_data.acc_reward_per_share as i128,
+    stake_pool_data.total_debt as i128,
+    stake_pool_data.compensation,
+    stake_pool_data.cumulative_staked as i128,
+    stake_pool_data.cumulative_unstaked as i128,
+    stake_pool_data.pending_shares as i128,
+    stake_pool_data.departed_shares as i128,
+    stake_pool_data.unseeded_in_window as i128,
+    bank.token_amount(&fixture.treasury_token) as i128,
+    bank.token_amount(&fixture.treasury_sen) as i128,
+    bank.token_amount(&fixture.owner_sen_acc) as i128,
+  ];
+  for wallet in [staker, newcomer].iter() {
+    state.push(bank.token_amount(&wallet.token_acc) as i128);
+    state.push(bank.lamports(&wallet.wallet) as i128);
+    for acc in [wallet.share_acc, wallet.sen_acc, wallet.debt_acc].iter() {
+      state.push(bank.lamports(acc) as i128);
+      state.push(bank.data(acc).len() as i128);
+    }
+    if !bank.data(&wallet.share_acc).is_empty() {
+      state.push(bank.token_amount(&wallet.share_acc) as i128);
+      state.push(bank.token_amount(&wallet.sen_acc) as i128);
+    }
+    if bank.data(&wallet.debt_acc).len() == Debt::LEN {
+      let debt_data = Debt::unpack(bank.data(&wallet.debt_acc)).unwrap();
+      state.push(debt_data.debt as i128);
+      state.push(debt_data.forfeit as i128);
+      state.push(debt_data.pending_shares as i128);
+      state.push(debt_data.last_delay as i128);
+    }
+  }
+  state
+}
+
+// Fail each CPI of the operation in turn, then retry on a healthy token program
+fn assert_converges(name: &str, operation: Operation, on_newcomer: bool) {
+  let run = |fixture: &mut PoolFixture, staker: &Staker, newcomer: &Staker| {
+    operation(fixture, if on_newcomer { newcomer } else { staker })
+  };
+  let (mut fixture, staker, newcomer) = setup();
+  fail_cpi_at(None);
+  run(&mut fixture, &staker, &newcomer).unwrap();
+  let cpis = cpi_count();
+  let expected = snapshot(&fixture, &staker, &newcomer);
+  assert!(cpis > 0, "{} issues no CPI", name);
+  for nth in 1..=cpis {
+    let (mut fixture, staker, newcomer) = setup();
+    let before = snapshot(&fixture, &staker, &newcomer);
+    fail_cpi_at(Some(nth));
+    assert_eq!(
+      run(&mut fixture, &staker, &newcomer).unwrap_err(),
+      ProgramError::Custom(INJECTED_FAILURE),
+      "{} with CPI {} failing",
+      name,
+      nth
+    );
+    assert_eq!(
+      snapshot(&fixture, &staker, &newcomer),
+      before,
+      "{} left state after CPI {} failed",
+      name,
+      nth
+    );
+    fail_cpi_at(None);
+    run(&mut fixture, &staker, &newcomer).unwrap();
+    assert_eq!(
+      snapshot(&fixture, &staker, &newcomer),
+      expected,
+      "{} diverged on retry after CPI {} failed",
+      name,
+      nth
+    );
+  }
+}
+
+#[test]
+fn test_initialize_accounts_converges_after_any_failed_cpi() {
+  assert_converges(
+    "InitializeAccounts",
+    |fixture, staker| fixture.initialize_accounts(staker),
+    true,
+  );
+}
+
+#[test]
+fn test_stake_converges_after_any_failed_cpi() {
+  assert_converges("Stake", |fixture, staker| fixture.stake(staker, 500), false);
+}
+
+#[test]
+fn test_unstake_converges_after_any_failed_cpi() {
+  assert_converges(
+    "Unstake",
+    |fixture, staker| fixture.unstake(staker, 500),
+    false,
+  );
+}
+
+#[test]
+fn test_harvest_converges_after_any_failed_cpi() {
+  assert_converges("Harvest", |fixture, staker| fixture.harvest(staker), false);
+}
+
+#[test]
+fn test_seed_and_unseed_converge_after_any_failed_cpi() {
+  assert_converges("Seed", |fixture, _| fixture.seed(1000), false);
+  assert_converges(
+    "Unseed",
+    |fixture, _| {
+      let dst_sen_acc = fixture.owner_sen_acc;
+      fixture.unseed(1000, dst_sen_acc)
+    },
+    false,
+  );
+}
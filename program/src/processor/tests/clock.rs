@@ -0,0 +1,84 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::processor::Processor;
+use crate::test_utils::{now, take_logs, warp, warp_to, PoolFixture, GENESIS};
+use std::thread;
+
+#[test]
+fn test_handlers_read_the_mock_clock() {
+  warp_to(1234);
+  assert_eq!(Processor::current_timestamp().unwrap(), 1234);
+  warp(-34);
+  assert_eq!(Processor::current_timestamp().unwrap(), 1200);
+  assert_eq!(now(), 1200);
+}
+
+#[test]
+fn test_each_thread_keeps_its_own_clock() {
+  warp_to(10);
+  let other = thread::spawn(|| {
+    warp_to(99);
+    Processor::current_timestamp().unwrap()
+  })
+  .join()
+  .unwrap();
+  assert_eq!(other, 99);
+  assert_eq!(Processor::current_timestamp().unwrap(), 10);
+}
+
+#[test]
+fn test_is_unlocked_at_the_exact_interval() {
+  warp_to(1000);
+  assert_eq!(
+    Processor::is_unlocked(900, 101).unwrap_err(),
+    AppError::Locked.into()
+  );
+  assert!(Processor::is_unlocked(900, 100).is_ok());
+}
+
+#[test]
+fn test_delay_counts_whole_periods_of_the_warped_clock() {
+  let fixture = PoolFixture::new(100, 60);
+  let stake_pool_data = fixture.stake_pool_data();
+  assert_eq!(stake_pool_data.genesis_timestamp, GENESIS);
+  for (elapsed, delay) in [(-1, 0), (0, 0), (59, 0), (60, 1), (119, 1), (120, 2)].iter() {
+    warp_to(GENESIS + elapsed);
+    assert_eq!(Processor::estimate_delay(stake_pool_data).unwrap(), *delay);
+  }
+}
+
+#[test]
+fn test_fixture_stakes_and_harvests_on_the_warped_clock() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.share_acc), 1000);
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_token), 1000);
+  warp(600);
+  fixture.harvest(&staker).unwrap();
+  let harvested = fixture.bank.token_amount(&staker.sen_acc);
+  assert!(harvested > 0 && harvested <= 1000);
+}
+
+fn pool_timing(fixture: &mut PoolFixture) -> Vec<String> {
+  let keys = [fixture.stake_pool];
+  fixture.send(&AppInstruction::GetPoolTiming, &keys).unwrap();
+  take_logs()
+}
+
+#[test]
+fn test_pool_timing_logs_the_age_since_genesis() {
+  let mut fixture = PoolFixture::new(100, 60);
+  warp_to(GENESIS + 150);
+  let logs = pool_timing(&mut fixture);
+  assert!(logs.iter().any(|log| log.ends_with("AGE_SECONDS:150")));
+  assert!(logs.iter().any(|log| log.ends_with("ACCRUED_PERIODS:2")));
+  let next = format!("NEXT_ACCRUAL_TIMESTAMP:{}", GENESIS + 180);
+  assert!(logs.iter().any(|log| log.ends_with(&next)));
+  assert!(logs.iter().any(|log| log.ends_with("REMAINING_SECONDS:0")));
+  // A pool scheduled in the future is zero seconds old
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.genesis_timestamp = GENESIS + 1000);
+  let logs = pool_timing(&mut fixture);
+  assert!(logs.iter().any(|log| log.ends_with("AGE_SECONDS:0")));
+}
@@ -0,0 +1,90 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::account::{Account, AccountState};
+use crate::test_utils::{splt_program_id, warp, FakeAccount, PoolFixture};
+use solana_program::{
+  entrypoint::ProgramResult, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+};
+
+const MAX_CRANK_AMOUNT: u64 = 500;
+const CRANK_INTERVAL: u64 = 60;
+
+// A pool cranking from a source of the given balance and allowance to the treasurer
+fn crank_pool(amount: u64, delegated_amount: u64) -> (PoolFixture, Pubkey) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture
+    .send_owner(&AppInstruction::UpdateCrankSeed {
+      max_crank_amount: MAX_CRANK_AMOUNT,
+      crank_interval: CRANK_INTERVAL,
+    })
+    .unwrap();
+  let src_sen_acc = Pubkey::new_unique();
+  let src_sen_data = Account {
+    mint: fixture.mint_sen,
+    owner: Pubkey::new_unique(),
+    amount,
+    delegate: COption::Some(fixture.treasurer),
+    delegated_amount,
+    state: AccountState::Initialized,
+    ..Account::default()
+  };
+  let mut data = vec![0; Account::LEN];
+  Account::pack(src_sen_data, &mut data).unwrap();
+  fixture
+    .bank
+    .insert(FakeAccount::new(src_sen_acc, splt_program_id(), data));
+  (fixture, src_sen_acc)
+}
+
+fn crank(fixture: &mut PoolFixture, src_sen_acc: Pubkey) -> ProgramResult {
+  let keys = [
+    fixture.stake_pool,
+    src_sen_acc,
+    fixture.treasury_sen,
+    fixture.treasurer,
+    splt_program_id(),
+  ];
+  fixture.send(&AppInstruction::CrankSeed, &keys)
+}
+
+#[test]
+fn test_crank_pulls_at_most_the_max_amount() {
+  let (mut fixture, src_sen_acc) = crank_pool(10_000, 10_000);
+  crank(&mut fixture, src_sen_acc).unwrap();
+  assert_eq!(
+    fixture.bank.token_amount(&fixture.treasury_sen),
+    MAX_CRANK_AMOUNT
+  );
+  // The interval holds the next crank back
+  assert_eq!(
+    crank(&mut fixture, src_sen_acc).unwrap_err(),
+    AppError::Locked.into()
+  );
+}
+
+#[test]
+fn test_crank_is_clamped_to_the_source_balance() {
+  // The allowance outlives a drained source
+  let (mut fixture, src_sen_acc) = crank_pool(300, 10_000);
+  crank(&mut fixture, src_sen_acc).unwrap();
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 300);
+  assert_eq!(fixture.bank.token_amount(&src_sen_acc), 0);
+  warp(CRANK_INTERVAL as i64);
+  assert_eq!(
+    crank(&mut fixture, src_sen_acc).unwrap_err(),
+    AppError::ZeroValue.into()
+  );
+}
+
+#[test]
+fn test_crank_is_clamped_to_the_allowance() {
+  let (mut fixture, src_sen_acc) = crank_pool(10_000, 200);
+  crank(&mut fixture, src_sen_acc).unwrap();
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 200);
+  warp(CRANK_INTERVAL as i64);
+  // The spent allowance revokes the delegate
+  assert_eq!(
+    crank(&mut fixture, src_sen_acc).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+}
@@ -0,0 +1,43 @@
+use crate::error::AppError;
+use crate::test_utils::{warp, PoolFixture, Staker};
+use solana_program::pubkey::Pubkey;
+
+// A staked position whose debt is then bound to another pool
+fn fixture_with_foreign_debt() -> (PoolFixture, Staker) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(2000);
+  fixture.stake(&staker, 1000).unwrap();
+  warp(120);
+  let other_pool = Pubkey::new_unique();
+  fixture.update_debt(&staker, |debt_data| debt_data.stake_pool = other_pool);
+  (fixture, staker)
+}
+
+#[test]
+fn test_stake_rejects_a_debt_of_another_pool() {
+  let (mut fixture, staker) = fixture_with_foreign_debt();
+  assert_eq!(
+    fixture.stake(&staker, 1000).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+}
+
+#[test]
+fn test_unstake_rejects_a_debt_of_another_pool() {
+  let (mut fixture, staker) = fixture_with_foreign_debt();
+  assert_eq!(
+    fixture.unstake(&staker, 1000).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+}
+
+#[test]
+fn test_harvest_rejects_a_debt_of_another_pool() {
+  let (mut fixture, staker) = fixture_with_foreign_debt();
+  assert_eq!(
+    fixture.harvest(&staker).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&staker.sen_acc), 0);
+}
@@ -0,0 +1,197 @@
+use crate::context::DistributeContext;
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::{
+  account::Account,
+  distribution::{DistributionConfig, MAX_DISTRIBUTION_CHILDREN},
+  stake_pool::StakePool,
+};
+use crate::test_utils::{fake_stake_pool_account, metas, splt_program_id, PoolFixture};
+use solana_program::{
+  entrypoint::ProgramResult, instruction::AccountMeta, program_error::ProgramError,
+  program_option::COption, program_pack::Pack, pubkey::Pubkey, system_program, sysvar,
+};
+
+// Pools fed by one funding account delegated to the distributor of a config
+struct Distribution {
+  fixture: PoolFixture,
+  authority: Pubkey,
+  config: Pubkey,
+  distributor: Pubkey,
+  funding_acc: Pubkey,
+  children: Vec<(Pubkey, Pubkey)>,
+}
+
+impl Distribution {
+  // The fixture pool, then two more pools of the same reward mint
+  fn new(funds: u64) -> Self {
+    let mut fixture = PoolFixture::new(100, 60);
+    let mut children = vec![(fixture.stake_pool, fixture.treasury_sen)];
+    for _ in 0..2 {
+      let stake_pool = Pubkey::new_unique();
+      let treasury_sen = fixture.add_token_account(fixture.mint_sen, Pubkey::new_unique(), 0);
+      let stake_pool_data = StakePool {
+        treasury_sen,
+        ..fixture.stake_pool_data()
+      };
+      fixture.bank.insert(fake_stake_pool_account(
+        stake_pool,
+        fixture.program_id,
+        stake_pool_data,
+      ));
+      children.push((stake_pool, treasury_sen));
+    }
+    let authority = Pubkey::new_unique();
+    let (config, _) = Pubkey::find_program_address(
+      &[&authority.to_bytes(), b"distribution"],
+      &fixture.program_id,
+    );
+    let (distributor, _) =
+      Pubkey::find_program_address(&[&config.to_bytes(), b"distributor"], &fixture.program_id);
+    let funding_acc = fixture.add_token_account(fixture.mint_sen, Pubkey::new_unique(), funds);
+    let acc = fixture.bank.accounts.get_mut(&funding_acc).unwrap();
+    let mut funding_data = Account::unpack(&acc.data).unwrap();
+    funding_data.delegate = COption::Some(distributor);
+    funding_data.delegated_amount = funds;
+    Account::pack(funding_data, &mut acc.data).unwrap();
+    Distribution {
+      fixture,
+      authority,
+      config,
+      distributor,
+      funding_acc,
+      children,
+    }
+  }
+
+  fn set_weights(&mut self, weights: &[u16]) -> ProgramResult {
+    let mut weights_bps = [0; MAX_DISTRIBUTION_CHILDREN];
+    weights_bps[..weights.len()].copy_from_slice(weights);
+    let ix = AppInstruction::SetDistribution {
+      count: weights.len() as u8,
+      weights_bps,
+    };
+    let mut keys = vec![
+      self.authority,
+      self.fixture.payer,
+      self.config,
+      system_program::id(),
+      sysvar::rent::id(),
+    ];
+    keys.extend(
+      self
+        .children
+        .iter()
+        .take(weights.len())
+        .map(|child| child.0),
+    );
+    self.fixture.send(&ix, &keys)
+  }
+
+  fn distribute(&mut self, amount: u64) -> ProgramResult {
+    let keys = [
+      self.config,
+      self.funding_acc,
+      self.distributor,
+      splt_program_id(),
+    ];
+    let mut metas = metas(DistributeContext::SPECS, &keys);
+    let count = self.config_data().count as usize;
+    for (stake_pool, treasury_sen) in self.children.iter().take(count) {
+      metas.push(AccountMeta::new_readonly(*stake_pool, false));
+      metas.push(AccountMeta::new(*treasury_sen, false));
+    }
+    self
+      .fixture
+      .send_metas(&AppInstruction::Distribute { amount }, metas)
+  }
+
+  fn config_data(&self) -> DistributionConfig {
+    self.fixture.bank.unpack(&self.config)
+  }
+
+  fn treasuries(&self) -> Vec<u64> {
+    self
+      .children
+      .iter()
+      .map(|(_, treasury_sen)| self.fixture.bank.token_amount(treasury_sen))
+      .collect()
+  }
+}
+
+#[test]
+fn test_distribute_seeds_each_child_by_weight() {
+  let mut distribution = Distribution::new(10_000);
+  distribution.set_weights(&[5000, 3000, 2000]).unwrap();
+  let config_data = distribution.config_data();
+  assert_eq!(config_data.authority, distribution.authority);
+  assert_eq!(config_data.count, 3);
+  distribution.distribute(1000).unwrap();
+  assert_eq!(distribution.treasuries(), vec![500, 300, 200]);
+  // The last child takes the rounding remainder
+  distribution.distribute(1001).unwrap();
+  assert_eq!(distribution.treasuries(), vec![1000, 600, 401]);
+  let funding = &distribution.funding_acc;
+  assert_eq!(
+    distribution.fixture.bank.token_amount(funding),
+    10_000 - 2001
+  );
+  let funding_data: Account = distribution.fixture.bank.unpack(funding);
+  assert_eq!(funding_data.delegated_amount, 10_000 - 2001);
+}
+
+#[test]
+fn test_distribute_follows_a_reconfiguration() {
+  let mut distribution = Distribution::new(10_000);
+  distribution.set_weights(&[5000, 3000, 2000]).unwrap();
+  distribution.set_weights(&[2500, 7500]).unwrap();
+  distribution.distribute(1000).unwrap();
+  assert_eq!(distribution.treasuries(), vec![250, 750, 0]);
+}
+
+#[test]
+fn test_set_distribution_rejects_weights_off_10000() {
+  let mut distribution = Distribution::new(10_000);
+  for weights in [&[5000, 3000, 1000][..], &[5000, 3000, 3000], &[10000, 0]].iter() {
+    assert_eq!(
+      distribution.set_weights(weights).unwrap_err(),
+      AppError::InvalidWeights.into()
+    );
+  }
+}
+
+#[test]
+fn test_distribute_checks_every_treasury_before_any_transfer() {
+  let mut distribution = Distribution::new(10_000);
+  distribution.set_weights(&[5000, 3000, 2000]).unwrap();
+  // The last treasury is not the one of its pool
+  let stray =
+    distribution
+      .fixture
+      .add_token_account(distribution.fixture.mint_sen, Pubkey::new_unique(), 0);
+  distribution.children[2].1 = stray;
+  assert_eq!(
+    distribution.distribute(1000).unwrap_err(),
+    AppError::UnmatchedPool.into()
+  );
+  assert_eq!(
+    distribution
+      .fixture
+      .bank
+      .token_amount(&distribution.funding_acc),
+    10_000
+  );
+  assert_eq!(distribution.treasuries(), vec![0, 0, 0]);
+}
+
+#[test]
+fn test_distribute_spends_no_more_than_the_allowance() {
+  let mut distribution = Distribution::new(1000);
+  distribution.set_weights(&[5000, 3000, 2000]).unwrap();
+  assert_eq!(
+    distribution.distribute(1001).unwrap_err(),
+    ProgramError::InsufficientFunds
+  );
+  distribution.distribute(1000).unwrap();
+  assert_eq!(distribution.treasuries(), vec![500, 300, 200]);
+}
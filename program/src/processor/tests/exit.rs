@@ -0,0 +1,50 @@
+use crate::context::ExitPoolContext;
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::test_utils::{metas, splt_program_id, warp, PoolFixture, Staker};
+use solana_program::entrypoint::ProgramResult;
+
+fn exit_pool(fixture: &mut PoolFixture, staker: &Staker) -> ProgramResult {
+  let keys = [
+    staker.wallet,
+    fixture.stake_pool,
+    fixture.mint_share,
+    staker.token_acc,
+    fixture.treasury_token,
+    staker.share_acc,
+    staker.debt_acc,
+    staker.sen_acc,
+    fixture.treasury_sen,
+    fixture.treasurer,
+    splt_program_id(),
+  ];
+  fixture.send_metas(
+    &AppInstruction::ExitPool,
+    metas(ExitPoolContext::SPECS, &keys),
+  )
+}
+
+#[test]
+fn test_exit_pool_unstakes_and_closes_the_accounts() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  warp(600);
+  exit_pool(&mut fixture, &staker).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 1000);
+  assert!(fixture.bank.token_amount(&staker.sen_acc) > 0);
+  assert_eq!(fixture.bank.lamports(&staker.debt_acc), 0);
+  assert_eq!(fixture.stake_pool_data().total_shares, 0);
+}
+
+#[test]
+fn test_exit_pool_rejects_debt_left_after_the_unstake() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.add_staker(1000);
+  fixture.update_debt(&staker, |debt_data| debt_data.debt = 5);
+  assert_eq!(
+    exit_pool(&mut fixture, &staker).unwrap_err(),
+    AppError::InconsistentState.into()
+  );
+}
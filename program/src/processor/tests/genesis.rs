@@ -0,0 +1,63 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::stake_pool::STATE_CHANGE_INTERVAL;
+use crate::test_utils::{now, warp, warp_to, PoolFixture, GENESIS};
+
+// Harvest of a lone staker, the genesis shifted by delta_seconds while frozen
+fn harvest_across_a_shift(delta_seconds: Option<i64>) -> u64 {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  warp_to(GENESIS + STATE_CHANGE_INTERVAL as i64);
+  fixture
+    .send_owner(&AppInstruction::FreezeStakePool)
+    .unwrap();
+  if let Some(delta_seconds) = delta_seconds {
+    fixture
+      .send_owner(&AppInstruction::ShiftGenesis { delta_seconds })
+      .unwrap();
+    assert_eq!(
+      fixture.stake_pool_data().genesis_timestamp,
+      GENESIS + delta_seconds
+    );
+  }
+  warp(STATE_CHANGE_INTERVAL as i64);
+  fixture.send_owner(&AppInstruction::ThawStakePool).unwrap();
+  fixture.harvest(&staker).unwrap();
+  fixture.bank.token_amount(&staker.sen_acc)
+}
+
+#[test]
+fn test_shifting_genesis_backward_accrues_the_extra_periods() {
+  let unshifted = harvest_across_a_shift(None);
+  assert!(unshifted > 0);
+  assert_eq!(harvest_across_a_shift(Some(-120)), unshifted + 2 * 100);
+}
+
+#[test]
+fn test_shift_genesis_rejects_a_genesis_in_the_future() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let to_the_future = AppInstruction::ShiftGenesis {
+    delta_seconds: STATE_CHANGE_INTERVAL as i64 + 1,
+  };
+  warp_to(GENESIS + STATE_CHANGE_INTERVAL as i64);
+  assert_eq!(
+    fixture.send_owner(&to_the_future).unwrap_err(),
+    AppError::UnfrozenPool.into()
+  );
+  fixture
+    .send_owner(&AppInstruction::FreezeStakePool)
+    .unwrap();
+  assert_eq!(
+    fixture.send_owner(&to_the_future).unwrap_err(),
+    AppError::InvalidGenesis.into()
+  );
+  assert_eq!(fixture.stake_pool_data().genesis_timestamp, GENESIS);
+  // Right up to the current time is still a recovery
+  let delta_seconds = now() - GENESIS;
+  fixture
+    .send_owner(&AppInstruction::ShiftGenesis { delta_seconds })
+    .unwrap();
+  assert_eq!(fixture.stake_pool_data().genesis_timestamp, now());
+}
@@ -0,0 +1,408 @@
+use crate::context::{
+  HarvestAndSeedContext, HarvestContext, HarvestSolContext, HarvestWithCreateContext,
+  SetDefaultSenAccountContext,
+};
+use crate::error::AppError;
+use crate::helper::pubutil::NATIVE_MINT;
+use crate::instruction::AppInstruction;
+use crate::schema::account::Account;
+use crate::test_utils::{
+  memo_program_id, metas, now, splata_program_id, splt_program_id, take_logs, warp, PoolFixture,
+  Staker,
+};
+use solana_program::{
+  entrypoint::ProgramResult, instruction::AccountMeta, program_pack::Pack, pubkey::Pubkey,
+  rent::Rent, system_program, sysvar,
+};
+
+const KEY: [u8; 16] = [7; 16];
+
+fn staked_pool() -> (PoolFixture, Staker) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  (fixture, staker)
+}
+
+fn harvest_with_key(fixture: &mut PoolFixture, staker: &Staker, signed: bool) -> ProgramResult {
+  let ix = AppInstruction::Harvest {
+    memo: false,
+    idempotency_key: Some(KEY),
+  };
+  let keys = fixture.harvest_keys(staker);
+  let mut metas = metas(HarvestContext::SPECS, &keys);
+  metas[0].is_signer = signed;
+  fixture.send_metas(&ix, metas)
+}
+
+#[test]
+fn test_owner_harvest_spends_its_idempotency_key() {
+  let (mut fixture, staker) = staked_pool();
+  warp(60);
+  harvest_with_key(&mut fixture, &staker, true).unwrap();
+  warp(60);
+  assert_eq!(
+    harvest_with_key(&mut fixture, &staker, true).unwrap_err(),
+    AppError::DuplicateIdempotencyKey.into()
+  );
+}
+
+// Let keepers harvest the staker's yield to its SEN account
+fn set_default_sen_account(fixture: &mut PoolFixture, staker: &Staker) {
+  let keys = [
+    staker.wallet,
+    fixture.stake_pool,
+    staker.debt_acc,
+    staker.sen_acc,
+  ];
+  fixture
+    .send_metas(
+      &AppInstruction::SetDefaultSenAccount,
+      metas(SetDefaultSenAccountContext::SPECS, &keys),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_keeper_harvest_leaves_the_idempotency_keys_alone() {
+  let (mut fixture, staker) = staked_pool();
+  set_default_sen_account(&mut fixture, &staker);
+  let keys_before = fixture.debt_data(&staker).idempotency_keys;
+  // Stakes accrue from the next period boundary
+  warp(120);
+  // A keeper pays the default account without the owner's signature
+  harvest_with_key(&mut fixture, &staker, false).unwrap();
+  assert!(fixture.bank.token_amount(&staker.sen_acc) > 0);
+  assert_eq!(fixture.debt_data(&staker).idempotency_keys, keys_before);
+  warp(60);
+  harvest_with_key(&mut fixture, &staker, false).unwrap();
+  // The owner can still use the key the keeper passed
+  warp(60);
+  harvest_with_key(&mut fixture, &staker, true).unwrap();
+  assert!(fixture.debt_data(&staker).has_idempotency_key(&KEY));
+}
+
+#[test]
+fn test_keeper_harvest_does_not_start_the_owner_interval() {
+  let (mut fixture, staker) = staked_pool();
+  set_default_sen_account(&mut fixture, &staker);
+  fixture
+    .send_owner(&AppInstruction::SetHarvestInterval {
+      min_harvest_interval: 600,
+    })
+    .unwrap();
+  warp(120);
+  harvest_with_key(&mut fixture, &staker, false).unwrap();
+  assert_eq!(fixture.debt_data(&staker).last_harvest_timestamp, 0);
+  // The owner harvests right after the keeper
+  warp(60);
+  fixture.harvest(&staker).unwrap();
+  assert_eq!(fixture.debt_data(&staker).last_harvest_timestamp, now());
+  warp(60);
+  assert_eq!(
+    fixture.harvest(&staker).unwrap_err(),
+    AppError::Locked.into()
+  );
+}
+
+// Harvest signed by the staker, with the memo sent through the given program
+fn harvest_with_memo(
+  fixture: &mut PoolFixture,
+  staker: &Staker,
+  memo_program: Pubkey,
+) -> ProgramResult {
+  let ix = AppInstruction::Harvest {
+    memo: true,
+    idempotency_key: None,
+  };
+  let keys = fixture.harvest_keys(staker);
+  let mut metas = metas(HarvestContext::SPECS, &keys);
+  metas[0].is_signer = true;
+  metas.push(AccountMeta::new_readonly(memo_program, false));
+  fixture.send_metas(&ix, metas)
+}
+
+#[test]
+fn test_harvest_memo_goes_to_the_memo_program_only() {
+  let (mut fixture, staker) = staked_pool();
+  warp(180);
+  let rogue = Pubkey::new_unique();
+  assert_eq!(
+    harvest_with_memo(&mut fixture, &staker, rogue).unwrap_err(),
+    AppError::IncorrectProgramId.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&staker.sen_acc), 0);
+  take_logs();
+  harvest_with_memo(&mut fixture, &staker, memo_program_id()).unwrap();
+  let memo = format!("Memo: senfarm:harvest:{}:200", fixture.stake_pool);
+  assert!(take_logs().contains(&memo));
+}
+
+// Staked pool whose owner is the staker, as harvest and seed requires
+fn owner_staked_pool() -> (PoolFixture, Staker) {
+  let (mut fixture, staker) = staked_pool();
+  fixture.update_stake_pool(|data| data.owner = staker.wallet);
+  fixture.owner = staker.wallet;
+  (fixture, staker)
+}
+
+fn harvest_and_seed(fixture: &mut PoolFixture, staker: &Staker) -> ProgramResult {
+  let keys = [
+    staker.wallet,
+    fixture.stake_pool,
+    fixture.mint_share,
+    staker.share_acc,
+    staker.debt_acc,
+    fixture.treasury_sen,
+  ];
+  fixture.send_metas(
+    &AppInstruction::HarvestAndSeed,
+    metas(HarvestAndSeedContext::SPECS, &keys),
+  )
+}
+
+#[test]
+fn test_harvest_and_seed_is_for_the_pool_owner_only() {
+  let (mut fixture, staker) = staked_pool();
+  warp(600);
+  assert_eq!(
+    harvest_and_seed(&mut fixture, &staker).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+  assert_eq!(fixture.debt_data(&staker).last_harvest_timestamp, 0);
+}
+
+#[test]
+fn test_harvest_and_seed_keeps_the_yield_in_the_treasury() {
+  let (mut fixture, staker) = owner_staked_pool();
+  let count = fixture.stake_pool_data().admin_action_count;
+  warp(600);
+  take_logs();
+  harvest_and_seed(&mut fixture, &staker).unwrap();
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 1_000_000);
+  let expected = format!(
+    "ADMIN_ACTION:action=SEED,stake_pool={},signer={},old=0,new=900,count={}",
+    fixture.stake_pool,
+    staker.wallet,
+    count + 1
+  );
+  assert!(take_logs().iter().any(|log| log.starts_with(&expected)));
+  assert_eq!(fixture.stake_pool_data().admin_action_count, count + 1);
+  // The reseeded periods are settled, a harvest right after pays nothing
+  fixture.harvest(&staker).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.sen_acc), 0);
+}
+
+#[test]
+fn test_harvest_and_seed_rejects_a_vesting_pool() {
+  let (mut fixture, staker) = owner_staked_pool();
+  fixture
+    .send_owner(&AppInstruction::SetVestingDuration {
+      vesting_duration: 600,
+    })
+    .unwrap();
+  warp(600);
+  assert_eq!(
+    harvest_and_seed(&mut fixture, &staker).unwrap_err(),
+    AppError::VestingEnabled.into()
+  );
+}
+
+#[test]
+fn test_harvest_and_seed_checks_the_treasury_cover() {
+  let (mut fixture, staker) = owner_staked_pool();
+  warp(600);
+  // Drain the treasury below the next period of emission
+  let mut treasury_sen_data: Account = fixture.bank.unpack(&fixture.treasury_sen);
+  treasury_sen_data.amount = 1;
+  let acc = fixture
+    .bank
+    .accounts
+    .get_mut(&fixture.treasury_sen)
+    .unwrap();
+  Account::pack(treasury_sen_data, &mut acc.data).unwrap();
+  harvest_and_seed(&mut fixture, &staker).unwrap();
+  assert!(fixture.stake_pool_data().withdraw_only);
+}
+
+// Harvest signed by the staker to the given SEN account
+fn harvest_to(fixture: &mut PoolFixture, staker: &Staker, dst_sen_acc: Pubkey) -> ProgramResult {
+  let ix = AppInstruction::Harvest {
+    memo: false,
+    idempotency_key: None,
+  };
+  let mut keys = fixture.harvest_keys(staker);
+  keys[5] = dst_sen_acc;
+  let mut metas = metas(HarvestContext::SPECS, &keys);
+  metas[0].is_signer = true;
+  fixture.send_metas(&ix, metas)
+}
+
+#[test]
+fn test_harvest_pays_a_third_party_account_of_the_reward_mint() {
+  let (mut fixture, staker) = staked_pool();
+  let recipient = fixture.add_token_account(fixture.mint_sen, Pubkey::new_unique(), 0);
+  warp(180);
+  harvest_to(&mut fixture, &staker, recipient).unwrap();
+  assert_eq!(fixture.bank.token_amount(&recipient), 200);
+  assert_eq!(fixture.bank.token_amount(&staker.sen_acc), 0);
+  assert_eq!(
+    fixture.bank.token_amount(&fixture.treasury_sen),
+    1_000_000 - 200
+  );
+}
+
+#[test]
+fn test_harvest_rejects_a_recipient_of_another_mint() {
+  let (mut fixture, staker) = staked_pool();
+  let recipient = fixture.add_token_account(fixture.mint_token, staker.wallet, 0);
+  warp(180);
+  assert_eq!(
+    harvest_to(&mut fixture, &staker, recipient).unwrap_err(),
+    AppError::UnmatchedTreasurySen.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 1_000_000);
+}
+
+#[test]
+fn test_claimable_is_what_a_harvest_transfers() {
+  let (mut fixture, staker) = staked_pool();
+  let late = fixture.add_staker(3000);
+  warp(150);
+  fixture.stake(&late, 3000).unwrap();
+  for _ in 0..3 {
+    warp(95);
+    for staker in [&staker, &late].iter() {
+      let expected = fixture.claimable(staker);
+      let before = fixture.bank.token_amount(&staker.sen_acc);
+      fixture.harvest(staker).unwrap();
+      let paid = fixture.bank.token_amount(&staker.sen_acc) - before;
+      assert_eq!(paid, expected);
+    }
+  }
+  assert!(fixture.bank.token_amount(&late.sen_acc) > 0);
+  // Nothing is left to claim right after a harvest
+  assert_eq!(fixture.claimable(&staker), 0);
+}
+
+// Harvest signed by the staker, creating the given SEN account if it is missing
+fn harvest_with_create(
+  fixture: &mut PoolFixture,
+  staker: &Staker,
+  dst_sen_acc: Pubkey,
+) -> ProgramResult {
+  let mut keys = fixture.harvest_keys(staker);
+  keys[5] = dst_sen_acc;
+  keys.extend_from_slice(&[
+    fixture.payer,
+    fixture.mint_sen,
+    system_program::id(),
+    sysvar::rent::id(),
+    splata_program_id(),
+  ]);
+  let mut metas = metas(HarvestWithCreateContext::SPECS, &keys);
+  metas[0].is_signer = true;
+  fixture.send_metas(&AppInstruction::HarvestWithCreate, metas)
+}
+
+#[test]
+fn test_harvest_with_create_opens_the_missing_sen_account() {
+  let (mut fixture, staker) = staked_pool();
+  // A staker who never opened its SEN account
+  fixture.bank.accounts.remove(&staker.sen_acc);
+  warp(180);
+  harvest_with_create(&mut fixture, &staker, staker.sen_acc).unwrap();
+  let sen_acc = &fixture.bank.accounts[&staker.sen_acc];
+  assert_eq!(sen_acc.owner, splt_program_id());
+  let sen_data: Account = fixture.bank.unpack(&staker.sen_acc);
+  assert_eq!(sen_data.owner, staker.wallet);
+  assert_eq!(sen_data.mint, fixture.mint_sen);
+  assert_eq!(sen_data.amount, 200);
+  // The account now exists, a later call only harvests
+  warp(60);
+  harvest_with_create(&mut fixture, &staker, staker.sen_acc).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.sen_acc), 300);
+}
+
+#[test]
+fn test_harvest_with_create_rejects_a_non_canonical_account() {
+  let (mut fixture, staker) = staked_pool();
+  warp(180);
+  assert_eq!(
+    harvest_with_create(&mut fixture, &staker, Pubkey::new_unique()).unwrap_err(),
+    AppError::UnmatchedAssociatedAccount.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 1_000_000);
+}
+
+// Harvest to a temporary wrapped SOL account of the staker, closed to its wallet
+fn harvest_sol(fixture: &mut PoolFixture, staker: &Staker, tmp_sen_acc: Pubkey) -> ProgramResult {
+  let mut keys = fixture.harvest_keys(staker);
+  keys[5] = tmp_sen_acc;
+  fixture.send_metas(
+    &AppInstruction::HarvestSol,
+    metas(HarvestSolContext::SPECS, &keys),
+  )
+}
+
+#[test]
+fn test_harvest_sol_pays_the_yield_in_native_sol() {
+  let mut fixture = PoolFixture::uninitialized_with_reward_mint(NATIVE_MINT);
+  fixture.initialize(100, 60, &[]).unwrap();
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  warp(180);
+  let tmp_sen_acc = fixture.add_token_account(NATIVE_MINT, staker.wallet, 0);
+  let reserve = Rent::default().minimum_balance(Account::LEN);
+  let lamports = fixture.bank.lamports(&staker.wallet);
+  harvest_sol(&mut fixture, &staker, tmp_sen_acc).unwrap();
+  // The yield and the rent of the temporary account
+  assert_eq!(
+    fixture.bank.lamports(&staker.wallet),
+    lamports + 200 + reserve
+  );
+  assert_eq!(fixture.bank.lamports(&tmp_sen_acc), 0);
+  assert_eq!(
+    fixture.bank.token_amount(&fixture.treasury_sen),
+    1_000_000 - 200
+  );
+}
+
+#[test]
+fn test_harvest_sol_never_closes_a_funded_or_foreign_account() {
+  let mut fixture = PoolFixture::uninitialized_with_reward_mint(NATIVE_MINT);
+  fixture.initialize(100, 60, &[]).unwrap();
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  warp(180);
+  let lamports = fixture.bank.lamports(&staker.wallet);
+  // The main wrapped SOL account of the staker holds a balance
+  let funded = fixture.add_token_account(NATIVE_MINT, staker.wallet, 5000);
+  assert_eq!(
+    harvest_sol(&mut fixture, &staker, funded).unwrap_err(),
+    AppError::NonEmptyTemporaryAccount.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&funded), 5000);
+  let foreign = fixture.add_token_account(NATIVE_MINT, Pubkey::new_unique(), 0);
+  assert_eq!(
+    harvest_sol(&mut fixture, &staker, foreign).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+  assert_eq!(fixture.bank.lamports(&staker.wallet), lamports);
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 1_000_000);
+}
+
+#[test]
+fn test_harvest_sol_rejects_a_reward_other_than_wrapped_sol() {
+  let (mut fixture, staker) = staked_pool();
+  let tmp_sen_acc = fixture.add_token_account(fixture.mint_sen, staker.wallet, 0);
+  warp(180);
+  assert_eq!(
+    harvest_sol(&mut fixture, &staker, tmp_sen_acc).unwrap_err(),
+    AppError::UnmatchedTreasurySen.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 1_000_000);
+}
@@ -0,0 +1,113 @@
+use crate::context::{StakeContext, UnstakeContext};
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::debt::IDEMPOTENCY_KEYS;
+use crate::test_utils::{metas, PoolFixture, Staker};
+use solana_program::entrypoint::ProgramResult;
+
+fn stake_with_key(
+  fixture: &mut PoolFixture,
+  staker: &Staker,
+  amount: u64,
+  key: [u8; 16],
+) -> ProgramResult {
+  let ix = AppInstruction::Stake {
+    amount,
+    reference: None,
+    idempotency_key: Some(key),
+  };
+  let keys = fixture.stake_keys(staker);
+  fixture.send_metas(&ix, metas(StakeContext::SPECS, &keys))
+}
+
+fn unstake_with_key(
+  fixture: &mut PoolFixture,
+  staker: &Staker,
+  amount: u64,
+  key: [u8; 16],
+) -> ProgramResult {
+  let ix = AppInstruction::Unstake {
+    amount,
+    reference: None,
+    idempotency_key: Some(key),
+  };
+  let keys = fixture.unstake_keys(staker);
+  fixture.send_metas(&ix, metas(UnstakeContext::SPECS, &keys))
+}
+
+fn staked_pool() -> (PoolFixture, Staker) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  (fixture, staker)
+}
+
+#[test]
+fn test_retried_stake_and_unstake_execute_once() {
+  let (mut fixture, staker) = staked_pool();
+  stake_with_key(&mut fixture, &staker, 600, [1; 16]).unwrap();
+  assert_eq!(
+    stake_with_key(&mut fixture, &staker, 600, [1; 16]).unwrap_err(),
+    AppError::DuplicateIdempotencyKey.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 400);
+  unstake_with_key(&mut fixture, &staker, 200, [2; 16]).unwrap();
+  assert_eq!(
+    unstake_with_key(&mut fixture, &staker, 200, [2; 16]).unwrap_err(),
+    AppError::DuplicateIdempotencyKey.into()
+  );
+  // Keys are shared across the staker instructions
+  assert_eq!(
+    unstake_with_key(&mut fixture, &staker, 200, [1; 16]).unwrap_err(),
+    AppError::DuplicateIdempotencyKey.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 600);
+  assert_eq!(fixture.stake_pool_data().total_shares, 400);
+}
+
+#[test]
+fn test_ring_evicts_the_oldest_key() {
+  let (mut fixture, staker) = staked_pool();
+  for index in 1..=IDEMPOTENCY_KEYS as u8 + 1 {
+    stake_with_key(&mut fixture, &staker, 10, [index; 16]).unwrap();
+  }
+  let debt_data = fixture.debt_data(&staker);
+  assert!(!debt_data.has_idempotency_key(&[1; 16]));
+  for index in 2..=IDEMPOTENCY_KEYS as u8 + 1 {
+    assert!(debt_data.has_idempotency_key(&[index; 16]));
+  }
+  // The oldest key may be reused, the last ones may not
+  assert_eq!(
+    stake_with_key(&mut fixture, &staker, 10, [2; 16]).unwrap_err(),
+    AppError::DuplicateIdempotencyKey.into()
+  );
+  stake_with_key(&mut fixture, &staker, 10, [1; 16]).unwrap();
+  assert_eq!(
+    fixture.stake_pool_data().total_shares,
+    10 * (IDEMPOTENCY_KEYS as u64 + 2)
+  );
+}
+
+#[test]
+fn test_legacy_encoding_skips_the_idempotency_keys() {
+  let (mut fixture, staker) = staked_pool();
+  stake_with_key(&mut fixture, &staker, 100, [1; 16]).unwrap();
+  let before = fixture.debt_data(&staker);
+  // Without a key, every retry executes
+  fixture.stake(&staker, 100).unwrap();
+  fixture.stake(&staker, 100).unwrap();
+  fixture.unstake(&staker, 100).unwrap();
+  let after = fixture.debt_data(&staker);
+  assert_eq!(after.idempotency_keys, before.idempotency_keys);
+  assert_eq!(after.idempotency_cursor, before.idempotency_cursor);
+  assert_eq!(fixture.stake_pool_data().total_shares, 200);
+}
+
+#[test]
+fn test_zero_key_is_rejected() {
+  let (mut fixture, staker) = staked_pool();
+  assert_eq!(
+    stake_with_key(&mut fixture, &staker, 100, [0; 16]).unwrap_err(),
+    AppError::ZeroValue.into()
+  );
+}
@@ -0,0 +1,145 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::{debt::Debt, mint::Mint, stake_pool::StakePool};
+use crate::test_utils::{take_logs, FakeAccount, PoolFixture, Staker};
+use solana_program::{
+  program_option::COption, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_program,
+};
+
+fn stake_pool_acc(fixture: &mut PoolFixture) -> &mut FakeAccount {
+  fixture.bank.accounts.get_mut(&fixture.stake_pool).unwrap()
+}
+
+#[test]
+fn test_initialize_rejects_an_undersized_stake_pool() {
+  let mut fixture = PoolFixture::uninitialized();
+  stake_pool_acc(&mut fixture)
+    .data
+    .truncate(StakePool::LEN - 1);
+  assert_eq!(
+    fixture.initialize(100, 60, &[]).unwrap_err(),
+    AppError::AccountTooSmall.into()
+  );
+}
+
+#[test]
+fn test_initialize_rejects_a_stake_pool_below_rent_exemption() {
+  let mut fixture = PoolFixture::uninitialized();
+  stake_pool_acc(&mut fixture).lamports -= 1;
+  assert_eq!(
+    fixture.initialize(100, 60, &[]).unwrap_err(),
+    AppError::NotRentExempt.into()
+  );
+}
+
+#[test]
+fn test_initialize_allocates_a_signing_system_stake_pool() {
+  let mut fixture = PoolFixture::uninitialized();
+  let acc = stake_pool_acc(&mut fixture);
+  acc.owner = system_program::id();
+  acc.lamports = 0;
+  acc.data.clear();
+  fixture.initialize(100, 60, &[]).unwrap();
+  let stake_pool = fixture.bank.accounts[&fixture.stake_pool].clone();
+  assert_eq!(stake_pool.owner, fixture.program_id);
+  assert_eq!(stake_pool.data.len(), StakePool::LEN);
+  assert_eq!(
+    stake_pool.lamports,
+    Rent::default().minimum_balance(StakePool::LEN)
+  );
+  // Usable as a pre-created one
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  assert_eq!(fixture.stake_pool_data().total_shares, 1000);
+}
+
+#[test]
+fn test_initialize_rejects_the_default_owner() {
+  let mut fixture = PoolFixture::uninitialized();
+  fixture.owner = Pubkey::default();
+  assert_eq!(
+    fixture.initialize(100, 60, &[]).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+}
+
+#[test]
+fn test_initialize_emits_the_created_pool() {
+  let mut fixture = PoolFixture::uninitialized();
+  fixture.initialize(250, 90, &[]).unwrap();
+  let data = fixture.stake_pool_data();
+  let event = format!(
+    "POOL_CREATED:stake_pool={},owner={},mint_token={},mint_share={},reward={},period={}",
+    fixture.stake_pool, data.owner, data.mint_token, data.mint_share, data.reward, data.period
+  );
+  let events: Vec<String> = take_logs()
+    .into_iter()
+    .filter(|log| log.contains("POOL_CREATED:"))
+    .collect();
+  assert_eq!(events.len(), 1);
+  assert!(events[0].ends_with(&event), "{}", events[0]);
+  assert_eq!((data.reward, data.period), (250, 90));
+  assert_eq!(data.owner, fixture.owner);
+}
+
+#[test]
+fn test_initialize_rejects_a_pool_key_that_cannot_derive_a_treasurer() {
+  let mut fixture = PoolFixture::uninitialized();
+  let program_id = fixture.program_id;
+  let stake_pool = loop {
+    let key = Pubkey::new_unique();
+    if Pubkey::create_program_address(&[&key.to_bytes()], &program_id).is_err() {
+      break key;
+    }
+  };
+  let mut acc = stake_pool_acc(&mut fixture).clone();
+  acc.key = stake_pool;
+  fixture.bank.insert(acc);
+  fixture.stake_pool = stake_pool;
+  assert_eq!(
+    fixture.initialize(100, 60, &[]).unwrap_err(),
+    AppError::InvalidPoolSeed.into()
+  );
+}
+
+#[test]
+fn test_initialize_leaves_the_treasurer_as_the_only_share_minter() {
+  let mut fixture = PoolFixture::uninitialized();
+  fixture.initialize(100, 60, &[]).unwrap();
+  let mint: Mint = fixture.bank.unpack(&fixture.mint_share);
+  assert_eq!(mint.mint_authority, COption::Some(fixture.treasurer));
+  assert_eq!(mint.decimals, 6);
+  assert_eq!(mint.supply, 0);
+}
+
+fn is_account_initialized(fixture: &mut PoolFixture, staker: &Staker) -> String {
+  let keys = [staker.wallet, fixture.stake_pool, staker.debt_acc];
+  fixture
+    .send(&AppInstruction::IsAccountInitialized, &keys)
+    .unwrap();
+  take_logs()
+    .into_iter()
+    .find(|log| log.contains("INITIALIZED:"))
+    .unwrap()
+}
+
+#[test]
+fn test_is_account_initialized_reports_both_states() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.new_staker(1000);
+  let uninitialized = "INITIALIZED:false,MATCHED:false";
+  assert!(is_account_initialized(&mut fixture, &staker).ends_with(uninitialized));
+  // Allocated but all zero
+  let program_id = fixture.program_id;
+  fixture.bank.insert(FakeAccount::new(
+    staker.debt_acc,
+    program_id,
+    vec![0; Debt::LEN],
+  ));
+  assert!(is_account_initialized(&mut fixture, &staker).ends_with(uninitialized));
+  fixture.bank.accounts.remove(&staker.debt_acc);
+  fixture.initialize_accounts(&staker).unwrap();
+  let initialized = "INITIALIZED:true,MATCHED:true";
+  assert!(is_account_initialized(&mut fixture, &staker).ends_with(initialized));
+}
@@ -0,0 +1,107 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::{
+  debt::Debt,
+  layout::{self, DEBT_LAYOUT, STAKE_POOL_LAYOUT},
+  stake_pool::StakePool,
+};
+use crate::test_utils::{take_logs, PoolFixture};
+use solana_program::program_pack::Pack;
+
+// Lengths of the first deployed layouts
+const LEGACY_STAKE_POOL_LEN: usize = 241;
+const LEGACY_DEBT_LEN: usize = 113;
+
+#[test]
+fn test_legacy_stake_pool_is_rejected() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let acc = fixture.bank.accounts.get_mut(&fixture.stake_pool).unwrap();
+  acc.data.truncate(LEGACY_STAKE_POOL_LEN);
+  assert_eq!(
+    fixture
+      .send_owner(&AppInstruction::FreezeStakePool {})
+      .unwrap_err(),
+    AppError::LegacyLayout.into()
+  );
+}
+
+#[test]
+fn test_legacy_debt_is_rejected() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.add_staker(1000);
+  let acc = fixture.bank.accounts.get_mut(&staker.debt_acc).unwrap();
+  acc.data.truncate(LEGACY_DEBT_LEN);
+  assert_eq!(
+    fixture.stake(&staker, 1000).unwrap_err(),
+    AppError::LegacyLayout.into()
+  );
+}
+
+#[test]
+fn test_reserved_bytes_are_written_zero() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.add_staker(1000);
+  let reserved = StakePool::LEN - crate::schema::stake_pool::RESERVED_LEN;
+  fixture
+    .bank
+    .accounts
+    .get_mut(&fixture.stake_pool)
+    .unwrap()
+    .data[reserved..]
+    .fill(0xff);
+  let reserved_debt = Debt::LEN - crate::schema::debt::RESERVED_LEN;
+  fixture
+    .bank
+    .accounts
+    .get_mut(&staker.debt_acc)
+    .unwrap()
+    .data[reserved_debt..]
+    .fill(0xff);
+  fixture.stake(&staker, 1000).unwrap();
+  // Fields later carved from the reserve read zero on every pool
+  assert!(fixture.bank.data(&fixture.stake_pool)[reserved..]
+    .iter()
+    .all(|&byte| byte == 0));
+  assert!(fixture.bank.data(&staker.debt_acc)[reserved_debt..]
+    .iter()
+    .all(|&byte| byte == 0));
+}
+
+#[test]
+fn test_inconsistent_state_is_never_persisted() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.max_unseed_bps_per_day = 10001);
+  let stake_pool_data = fixture.stake_pool_data();
+  assert_eq!(
+    fixture.stake(&staker, 1000).unwrap_err(),
+    AppError::InconsistentState.into()
+  );
+  assert_eq!(fixture.stake_pool_data(), stake_pool_data);
+  // The debt is checked alike
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.max_unseed_bps_per_day = 0);
+  fixture.update_debt(&staker, |debt_data| debt_data.idempotency_cursor = 200);
+  assert_eq!(
+    fixture.stake(&staker, 1000).unwrap_err(),
+    AppError::InconsistentState.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 1000);
+}
+
+#[test]
+fn test_get_layout_logs_both_schemas() {
+  let mut fixture = PoolFixture::new(100, 60);
+  take_logs();
+  fixture.send(&AppInstruction::GetLayout, &[]).unwrap();
+  let logs = take_logs();
+  for (name, len, fields) in [
+    ("STAKE_POOL", StakePool::LEN, STAKE_POOL_LAYOUT),
+    ("DEBT", Debt::LEN, DEBT_LAYOUT),
+  ]
+  .iter()
+  {
+    let logged = format!("LAYOUT:{}", layout::schema(name, *len, fields));
+    assert!(logs.iter().any(|log| log.contains(&logged)), "{}", name);
+  }
+}
@@ -0,0 +1,45 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::stake_pool::ACCUMULATOR_VERSION;
+use crate::test_utils::{warp, PoolFixture};
+use crate::PRECISION;
+
+// A pool left on the compensation model at the given compensation
+fn legacy_pool(compensation: i128) -> PoolFixture {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.update_stake_pool(|stake_pool_data| {
+    stake_pool_data.version = 0;
+    stake_pool_data.acc_reward_per_share = 0;
+    stake_pool_data.last_update_delay = 0;
+    stake_pool_data.total_shares = 10;
+    stake_pool_data.compensation = compensation;
+  });
+  fixture
+}
+
+#[test]
+fn test_migrate_starts_the_accumulator_at_the_compensation() {
+  let mut fixture = legacy_pool(-5 * PRECISION as i128);
+  warp(600);
+  fixture
+    .send_owner(&AppInstruction::MigrateStakePool {})
+    .unwrap();
+  let stake_pool_data = fixture.stake_pool_data();
+  assert_eq!(stake_pool_data.version, ACCUMULATOR_VERSION);
+  assert_eq!(stake_pool_data.last_update_delay, 10);
+  // Ten per share and period over ten periods, plus the compensation
+  assert_eq!(stake_pool_data.acc_reward_per_share, 95 * PRECISION);
+}
+
+#[test]
+fn test_migrate_of_a_large_negative_compensation_overflows() {
+  let mut fixture = legacy_pool(-1000 * PRECISION as i128);
+  warp(600);
+  assert_eq!(
+    fixture
+      .send_owner(&AppInstruction::MigrateStakePool {})
+      .unwrap_err(),
+    AppError::Overflow.into()
+  );
+  assert_eq!(fixture.stake_pool_data().version, 0);
+}
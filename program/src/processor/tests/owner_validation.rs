@@ -0,0 +1,308 @@
+use crate::error::AppError;
+use crate::processor::Processor;
+use crate::schema::{
+  account::Account,
+  stake_pool::{StakePool, StakePoolState, STATE_CHANGE_INTERVAL},
+};
+use crate::test_utils::{
+  fake_signer, fake_stake_pool_account, fake_token_account, run_handler, splt_program_id, warp_to,
+  FakeAccount,
+};
+use solana_program::{
+  entrypoint::ProgramResult, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+};
+
+const NOW: i64 = 1600000000;
+
+struct Keys {
+  program_id: Pubkey,
+  owner: Pubkey,
+  stake_pool: Pubkey,
+  mint_sen: Pubkey,
+  treasury_sen: Pubkey,
+}
+
+fn keys() -> Keys {
+  warp_to(NOW);
+  Keys {
+    program_id: Pubkey::new_unique(),
+    owner: Pubkey::new_unique(),
+    stake_pool: Pubkey::new_unique(),
+    mint_sen: Pubkey::new_unique(),
+    treasury_sen: Pubkey::new_unique(),
+  }
+}
+
+fn stake_pool_data(keys: &Keys) -> StakePool {
+  StakePool {
+    owner: keys.owner,
+    state: StakePoolState::Initialized,
+    genesis_timestamp: NOW,
+    reward: 10,
+    period: 60,
+    mint_sen: keys.mint_sen,
+    treasury_sen: keys.treasury_sen,
+    ..StakePool::default()
+  }
+}
+
+fn stake_pool_acc(keys: &Keys, data: StakePool) -> FakeAccount {
+  fake_stake_pool_account(keys.stake_pool, keys.program_id, data)
+}
+
+fn unpack_stake_pool(acc: &FakeAccount) -> StakePool {
+  StakePool::unpack(&acc.data).unwrap()
+}
+
+fn freeze(keys: &Keys, accounts: &mut [FakeAccount]) -> ProgramResult {
+  run_handler(Processor::freeze_stake_pool, &keys.program_id, accounts)
+}
+
+fn thaw(keys: &Keys, accounts: &mut [FakeAccount]) -> ProgramResult {
+  run_handler(Processor::thaw_stake_pool, &keys.program_id, accounts)
+}
+
+#[test]
+fn test_freeze_records_the_state_change() {
+  let keys = keys();
+  let mut accounts = [
+    fake_signer(keys.owner),
+    stake_pool_acc(&keys, stake_pool_data(&keys)),
+  ];
+  freeze(&keys, &mut accounts).unwrap();
+  let data = unpack_stake_pool(&accounts[1]);
+  assert_eq!(data.state, StakePoolState::Frozen);
+  assert_eq!(data.last_state_change, NOW);
+  assert_eq!(data.admin_action_count, 1);
+  assert_eq!(data.last_admin_action, NOW);
+}
+
+#[test]
+fn test_freeze_rejects_a_non_owner() {
+  let keys = keys();
+  let mut accounts = [
+    fake_signer(Pubkey::new_unique()),
+    stake_pool_acc(&keys, stake_pool_data(&keys)),
+  ];
+  assert_eq!(
+    freeze(&keys, &mut accounts).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+}
+
+#[test]
+fn test_freeze_rejects_an_unsigned_owner() {
+  let keys = keys();
+  let mut owner = fake_signer(keys.owner);
+  owner.is_signer = false;
+  let mut accounts = [owner, stake_pool_acc(&keys, stake_pool_data(&keys))];
+  assert_eq!(
+    freeze(&keys, &mut accounts).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+}
+
+#[test]
+fn test_freeze_rejects_a_foreign_or_readonly_stake_pool() {
+  let keys = keys();
+  let mut foreign = stake_pool_acc(&keys, stake_pool_data(&keys));
+  foreign.owner = Pubkey::new_unique();
+  let mut accounts = [fake_signer(keys.owner), foreign];
+  assert_eq!(
+    freeze(&keys, &mut accounts).unwrap_err(),
+    AppError::IncorrectProgramId.into()
+  );
+  let mut accounts = [
+    fake_signer(keys.owner),
+    stake_pool_acc(&keys, stake_pool_data(&keys)).readonly(),
+  ];
+  assert_eq!(
+    freeze(&keys, &mut accounts).unwrap_err(),
+    AppError::ReadonlyAccount.into()
+  );
+  let mut accounts = [fake_signer(keys.owner)];
+  assert_eq!(
+    freeze(&keys, &mut accounts).unwrap_err(),
+    ProgramError::NotEnoughAccountKeys
+  );
+}
+
+#[test]
+fn test_freeze_and_thaw_are_rate_limited() {
+  let keys = keys();
+  let mut accounts = [
+    fake_signer(keys.owner),
+    stake_pool_acc(&keys, stake_pool_data(&keys)),
+  ];
+  freeze(&keys, &mut accounts).unwrap();
+  warp_to(NOW + STATE_CHANGE_INTERVAL as i64 - 1);
+  assert_eq!(
+    thaw(&keys, &mut accounts).unwrap_err(),
+    AppError::Locked.into()
+  );
+  warp_to(NOW + STATE_CHANGE_INTERVAL as i64);
+  thaw(&keys, &mut accounts).unwrap();
+  let data = unpack_stake_pool(&accounts[1]);
+  assert_eq!(data.state, StakePoolState::Initialized);
+  assert_eq!(data.admin_action_count, 2);
+}
+
+#[test]
+fn test_freeze_right_after_a_thaw_is_rate_limited() {
+  let keys = keys();
+  let mut data = stake_pool_data(&keys);
+  data.state = StakePoolState::Frozen;
+  let mut accounts = [fake_signer(keys.owner), stake_pool_acc(&keys, data)];
+  thaw(&keys, &mut accounts).unwrap();
+  assert_eq!(unpack_stake_pool(&accounts[1]).last_state_change, NOW);
+  assert_eq!(
+    freeze(&keys, &mut accounts).unwrap_err(),
+    AppError::Locked.into()
+  );
+  assert_eq!(
+    unpack_stake_pool(&accounts[1]).state,
+    StakePoolState::Initialized
+  );
+  warp_to(NOW + STATE_CHANGE_INTERVAL as i64);
+  freeze(&keys, &mut accounts).unwrap();
+  let data = unpack_stake_pool(&accounts[1]);
+  assert_eq!(data.state, StakePoolState::Frozen);
+  assert_eq!(data.last_state_change, NOW + STATE_CHANGE_INTERVAL as i64);
+}
+
+#[test]
+fn test_thaw_rejects_a_non_owner() {
+  let keys = keys();
+  let mut data = stake_pool_data(&keys);
+  data.state = StakePoolState::Frozen;
+  let mut accounts = [
+    fake_signer(Pubkey::new_unique()),
+    stake_pool_acc(&keys, data),
+  ];
+  assert_eq!(
+    thaw(&keys, &mut accounts).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+  assert_eq!(
+    unpack_stake_pool(&accounts[1]).state,
+    StakePoolState::Frozen
+  );
+}
+
+#[test]
+fn test_transfer_ownership_hands_over_the_pool() {
+  let keys = keys();
+  let new_owner = Pubkey::new_unique();
+  let mut accounts = [
+    fake_signer(keys.owner),
+    stake_pool_acc(&keys, stake_pool_data(&keys)),
+    FakeAccount::new(new_owner, Pubkey::default(), vec![]).readonly(),
+  ];
+  run_handler(
+    Processor::transfer_stake_pool_ownership,
+    &keys.program_id,
+    &mut accounts,
+  )
+  .unwrap();
+  let data = unpack_stake_pool(&accounts[1]);
+  assert_eq!(data.owner, new_owner);
+  assert_eq!(data.admin_action_count, 1);
+  // The former owner lost its rights
+  assert_eq!(
+    freeze(&keys, &mut accounts[..2]).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+}
+
+#[test]
+fn test_transfer_ownership_rejects_a_non_owner() {
+  let keys = keys();
+  let mut accounts = [
+    fake_signer(Pubkey::new_unique()),
+    stake_pool_acc(&keys, stake_pool_data(&keys)),
+    FakeAccount::new(Pubkey::new_unique(), Pubkey::default(), vec![]),
+  ];
+  assert_eq!(
+    run_handler(
+      Processor::transfer_stake_pool_ownership,
+      &keys.program_id,
+      &mut accounts,
+    )
+    .unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+  assert_eq!(unpack_stake_pool(&accounts[1]).owner, keys.owner);
+}
+
+fn seed_accounts(keys: &Keys, treasury_sen: Pubkey) -> [FakeAccount; 5] {
+  let src_sen = Pubkey::new_unique();
+  [
+    fake_signer(keys.owner),
+    stake_pool_acc(keys, stake_pool_data(keys)).readonly(),
+    fake_token_account(src_sen, splt_program_id(), keys.mint_sen, keys.owner, 100),
+    fake_token_account(
+      treasury_sen,
+      splt_program_id(),
+      keys.mint_sen,
+      Pubkey::new_unique(),
+      0,
+    ),
+    FakeAccount::new(splt_program_id(), Pubkey::default(), vec![]).readonly(),
+  ]
+}
+
+fn seed(keys: &Keys, amount: u64, accounts: &mut [FakeAccount]) -> ProgramResult {
+  run_handler(
+    |program_id, accounts| Processor::seed(amount, program_id, accounts),
+    &keys.program_id,
+    accounts,
+  )
+}
+
+fn token_amount(acc: &FakeAccount) -> u64 {
+  Account::unpack(&acc.data).unwrap().amount
+}
+
+#[test]
+fn test_seed_moves_the_amount_to_the_treasury() {
+  let keys = keys();
+  let mut accounts = seed_accounts(&keys, keys.treasury_sen);
+  seed(&keys, 60, &mut accounts).unwrap();
+  assert_eq!(token_amount(&accounts[2]), 40);
+  assert_eq!(token_amount(&accounts[3]), 60);
+}
+
+#[test]
+fn test_seed_rejects_a_foreign_treasury() {
+  let keys = keys();
+  let mut accounts = seed_accounts(&keys, Pubkey::new_unique());
+  assert_eq!(
+    seed(&keys, 60, &mut accounts).unwrap_err(),
+    AppError::UnmatchedPool.into()
+  );
+}
+
+#[test]
+fn test_seed_rejects_zero_and_more_than_held() {
+  let keys = keys();
+  let mut accounts = seed_accounts(&keys, keys.treasury_sen);
+  assert_eq!(
+    seed(&keys, 0, &mut accounts).unwrap_err(),
+    AppError::ZeroValue.into()
+  );
+  assert_eq!(
+    seed(&keys, 101, &mut accounts).unwrap_err(),
+    ProgramError::InsufficientFunds
+  );
+}
+
+#[test]
+fn test_seed_rejects_an_unsigned_owner() {
+  let keys = keys();
+  let mut accounts = seed_accounts(&keys, keys.treasury_sen);
+  accounts[0].is_signer = false;
+  assert_eq!(
+    seed(&keys, 60, &mut accounts).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+}
@@ -0,0 +1,81 @@
+use crate::test_utils::{warp, warp_to, PoolFixture, GENESIS};
+
+// Staggered stakes, unstakes and harvests of three stakers
+fn payouts_at(precision_exp: u8) -> (Vec<u64>, PoolFixture) {
+  let mut fixture = PoolFixture::new(1000, 60);
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.precision_exp = precision_exp);
+  fixture.seed(10_000_000).unwrap();
+  let stakers: Vec<_> = [7, 1000, 333_333]
+    .iter()
+    .map(|tokens| fixture.add_staker(*tokens))
+    .collect();
+  for (step, staker) in stakers.iter().enumerate() {
+    warp_to(GENESIS + 37 * step as i64);
+    let tokens = fixture.bank.token_amount(&staker.token_acc);
+    fixture.stake(staker, tokens).unwrap();
+  }
+  for round in 1..=6 {
+    warp(97);
+    let staker = &stakers[round % stakers.len()];
+    if round % 2 == 0 {
+      fixture.harvest(staker).unwrap();
+    } else {
+      let shares = fixture.bank.token_amount(&staker.share_acc);
+      fixture.unstake(staker, shares / 3).unwrap();
+    }
+  }
+  warp(600);
+  let payouts = stakers
+    .iter()
+    .map(|staker| {
+      fixture.harvest(staker).unwrap();
+      fixture.bank.token_amount(&staker.sen_acc)
+    })
+    .collect();
+  (payouts, fixture)
+}
+
+#[test]
+fn test_precision_18_settles_bit_for_bit_as_before() {
+  let (legacy, legacy_fixture) = payouts_at(0);
+  let (explicit, explicit_fixture) = payouts_at(18);
+  assert!(legacy.iter().all(|paid| *paid > 0));
+  assert_eq!(legacy, explicit);
+  let settled = |fixture: &PoolFixture| {
+    let data = fixture.stake_pool_data();
+    (
+      data.acc_reward_per_share,
+      data.last_update_delay,
+      data.compensation,
+      data.total_debt,
+      data.total_shares,
+    )
+  };
+  assert_eq!(settled(&explicit_fixture), settled(&legacy_fixture));
+}
+
+// Two tokens a period over four billion billion shares
+fn tiny_reward_at(precision_exp: u8) -> (u64, u64) {
+  let mut fixture = PoolFixture::new(2, 60);
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.precision_exp = precision_exp);
+  fixture.seed(1_000_000).unwrap();
+  let small = fixture.add_staker(1_000_000_000_000_000_000);
+  let large = fixture.add_staker(3_000_000_000_000_000_000);
+  fixture.stake(&small, 1_000_000_000_000_000_000).unwrap();
+  fixture.stake(&large, 3_000_000_000_000_000_000).unwrap();
+  // Ten periods accrue past the forfeited first one
+  warp(11 * 60);
+  fixture.harvest(&small).unwrap();
+  fixture.harvest(&large).unwrap();
+  (
+    fixture.bank.token_amount(&small.sen_acc),
+    fixture.bank.token_amount(&large.sen_acc),
+  )
+}
+
+#[test]
+fn test_precision_24_distributes_a_tiny_reward_over_a_huge_pool() {
+  // A share earns less than the smallest fraction at 1e18
+  assert_eq!(tiny_reward_at(18), (0, 0));
+  assert_eq!(tiny_reward_at(24), (5, 15));
+}
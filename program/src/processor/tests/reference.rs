@@ -0,0 +1,29 @@
+use crate::instruction::AppInstruction;
+use crate::test_utils::{take_logs, PoolFixture};
+
+#[test]
+fn test_stake_and_unstake_echo_the_client_reference() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.add_staker(1000);
+  let stake = AppInstruction::Stake {
+    amount: 1000,
+    reference: Some(42),
+    idempotency_key: None,
+  };
+  fixture.send(&stake, &fixture.stake_keys(&staker)).unwrap();
+  assert!(take_logs().iter().any(|log| log.ends_with("REFERENCE:42")));
+  let unstake = AppInstruction::Unstake {
+    amount: 400,
+    reference: Some(u64::MAX),
+    idempotency_key: None,
+  };
+  fixture
+    .send(&unstake, &fixture.unstake_keys(&staker))
+    .unwrap();
+  let reference = format!("REFERENCE:{}", u64::MAX);
+  assert!(take_logs().iter().any(|log| log.ends_with(&reference)));
+  // Nothing is logged without one
+  fixture.unstake(&staker, 600).unwrap();
+  assert!(!take_logs().iter().any(|log| log.contains("REFERENCE:")));
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 1000);
+}
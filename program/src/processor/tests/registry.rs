@@ -0,0 +1,88 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::registry::Registry;
+use crate::test_utils::PoolFixture;
+use solana_program::{pubkey::Pubkey, system_program, sysvar};
+
+// Registry, its first page and mint whitelist, the given mint allowed
+fn curated_registry(fixture: &mut PoolFixture, allowed: Pubkey) -> [Pubkey; 3] {
+  let program_id = fixture.program_id;
+  let (registry, _) = Pubkey::find_program_address(&[b"registry"], &program_id);
+  let (registry_page, _) = Pubkey::find_program_address(
+    &[&registry.to_bytes(), &0u64.to_le_bytes(), b"registry_page"],
+    &program_id,
+  );
+  let (mint_whitelist, _) =
+    Pubkey::find_program_address(&[&registry.to_bytes(), b"mint_whitelist"], &program_id);
+  let authority = Pubkey::new_unique();
+  let system = [system_program::id(), sysvar::rent::id()];
+  let payer = fixture.payer;
+  let keys = [&[payer, authority, registry][..], &system].concat();
+  fixture
+    .send(&AppInstruction::InitializeRegistry, &keys)
+    .unwrap();
+  let keys = [
+    &[authority, payer, registry, mint_whitelist, allowed][..],
+    &system,
+  ]
+  .concat();
+  fixture.send(&AppInstruction::AllowMint, &keys).unwrap();
+  [registry, registry_page, mint_whitelist]
+}
+
+#[test]
+fn test_initialize_lists_a_pool_of_an_allowed_mint() {
+  let mut fixture = PoolFixture::uninitialized();
+  let mint_token = fixture.mint_token;
+  let registry = curated_registry(&mut fixture, mint_token);
+  fixture.initialize(100, 60, &registry).unwrap();
+  let registry_data: Registry = fixture.bank.unpack(&registry[0]);
+  assert_eq!(registry_data.total_pools, 1);
+}
+
+#[test]
+fn test_initialize_rejects_a_mint_the_registry_does_not_allow() {
+  let mut fixture = PoolFixture::uninitialized();
+  let mint_sen = fixture.mint_sen;
+  let registry = curated_registry(&mut fixture, mint_sen);
+  assert_eq!(
+    fixture.initialize(100, 60, &registry).unwrap_err(),
+    AppError::MintNotAllowed.into()
+  );
+  // Without the registry the mint is not checked
+  fixture.initialize(100, 60, &[]).unwrap();
+}
+
+#[test]
+fn test_initialize_checks_the_mint_even_without_the_whitelist_passed() {
+  let mut fixture = PoolFixture::uninitialized();
+  let mint_sen = fixture.mint_sen;
+  let [registry, registry_page, mint_whitelist] = curated_registry(&mut fixture, mint_sen);
+  // Listing without the whitelist would skip the curation
+  assert_eq!(
+    fixture
+      .initialize(100, 60, &[registry, registry_page])
+      .unwrap_err(),
+    AppError::MintNotAllowed.into()
+  );
+  // Nor can another whitelist stand in for the registry's
+  let mut other = PoolFixture::uninitialized();
+  let mint_token = other.mint_token;
+  let [_, _, other_whitelist] = curated_registry(&mut other, mint_token);
+  let whitelist = other.bank.accounts.remove(&other_whitelist).unwrap();
+  fixture.bank.insert(whitelist);
+  assert_eq!(
+    fixture
+      .initialize(100, 60, &[registry, registry_page, other_whitelist])
+      .unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+  let registry_data: Registry = fixture.bank.unpack(&registry);
+  assert_eq!(registry_data.total_pools, 0);
+  assert_eq!(
+    fixture
+      .initialize(100, 60, &[registry, registry_page, mint_whitelist])
+      .unwrap_err(),
+    AppError::MintNotAllowed.into()
+  );
+}
@@ -0,0 +1,92 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::stake_pool::STATE_CHANGE_INTERVAL;
+use crate::test_utils::{take_logs, warp, warp_to, PoolFixture, GENESIS};
+
+const OLD_PRECISION: u64 = 1_000_000_000_000_000_000;
+// Coarser precisions are covered by the pattern, pools never go below 1e18
+const NEW_PRECISION: u64 = 10_000_000_000_000_000_000;
+
+// Harvests of two stakers on a legacy pool, the precision optionally moved to 1e19 while frozen
+fn harvests_across_an_upgrade(rescale: bool) -> (u64, u64) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.update_stake_pool(|stake_pool_data| stake_pool_data.version = 0);
+  fixture.seed(10_000_000).unwrap();
+  let first = fixture.add_staker(1000);
+  fixture.stake(&first, 1000).unwrap();
+  // Joining mid-pool leaves a nonzero compensation
+  warp_to(GENESIS + 150);
+  let second = fixture.add_staker(3000);
+  fixture.stake(&second, 3000).unwrap();
+  let compensation = fixture.stake_pool_data().compensation;
+  assert_ne!(compensation, 0);
+  warp_to(GENESIS + STATE_CHANGE_INTERVAL as i64);
+  fixture
+    .send_owner(&AppInstruction::FreezeStakePool)
+    .unwrap();
+  if rescale {
+    fixture
+      .send_owner(&AppInstruction::RescaleCompensation {
+        old_precision: OLD_PRECISION,
+        new_precision: NEW_PRECISION,
+      })
+      .unwrap();
+    let rescaled = compensation * (NEW_PRECISION / OLD_PRECISION) as i128;
+    assert_eq!(fixture.stake_pool_data().compensation, rescaled);
+    let logged = format!("Compensation: {} -> {}", compensation, rescaled);
+    assert!(take_logs().iter().any(|log| log.contains(&logged)));
+    // The upgraded program reads the stake pool at the new precision
+    fixture.update_stake_pool(|stake_pool_data| stake_pool_data.precision_exp = 19);
+  }
+  warp(STATE_CHANGE_INTERVAL as i64);
+  fixture.send_owner(&AppInstruction::ThawStakePool).unwrap();
+  warp(600);
+  fixture.harvest(&first).unwrap();
+  fixture.harvest(&second).unwrap();
+  (
+    fixture.bank.token_amount(&first.sen_acc),
+    fixture.bank.token_amount(&second.sen_acc),
+  )
+}
+
+#[test]
+fn test_harvests_after_a_rescale_match_the_old_precision() {
+  let (first, second) = harvests_across_an_upgrade(false);
+  let (rescaled_first, rescaled_second) = harvests_across_an_upgrade(true);
+  assert!(first > 0 && second > 0);
+  // Either precision floors once per settlement
+  for (paid, expected) in [(rescaled_first, first), (rescaled_second, second)].iter() {
+    assert!(
+      (*paid as i64 - *expected as i64).abs() <= 1,
+      "{} for {}",
+      paid,
+      expected
+    );
+  }
+}
+
+#[test]
+fn test_rescale_requires_a_frozen_pool() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let rescale = AppInstruction::RescaleCompensation {
+    old_precision: OLD_PRECISION,
+    new_precision: NEW_PRECISION,
+  };
+  assert_eq!(
+    fixture.send_owner(&rescale).unwrap_err(),
+    AppError::UnfrozenPool.into()
+  );
+  warp(STATE_CHANGE_INTERVAL as i64);
+  fixture
+    .send_owner(&AppInstruction::FreezeStakePool)
+    .unwrap();
+  let zero = AppInstruction::RescaleCompensation {
+    old_precision: 0,
+    new_precision: NEW_PRECISION,
+  };
+  assert_eq!(
+    fixture.send_owner(&zero).unwrap_err(),
+    AppError::ZeroValue.into()
+  );
+  fixture.send_owner(&rescale).unwrap();
+}
@@ -0,0 +1,130 @@
+use crate::instruction::AppInstruction;
+use crate::test_utils::{take_logs, warp, PoolFixture, Staker};
+use crate::PRECISION;
+use solana_program::program_error::ProgramError;
+
+fn effective_emission(fixture: &mut PoolFixture) -> String {
+  let keys = [fixture.stake_pool];
+  fixture
+    .send(&AppInstruction::GetEffectiveReward, &keys)
+    .unwrap();
+  take_logs()
+    .into_iter()
+    .find(|log| log.starts_with("EFFECTIVE_EMISSION:"))
+    .unwrap()
+}
+
+#[test]
+fn test_emission_of_an_empty_pool_is_zero() {
+  let mut fixture = PoolFixture::new(100, 60);
+  assert_eq!(effective_emission(&mut fixture), "EFFECTIVE_EMISSION:0");
+}
+
+#[test]
+fn test_emission_truncates_per_share() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(3);
+  fixture.stake(&staker, 3).unwrap();
+  // A third of the reward per share, floored
+  assert_eq!(effective_emission(&mut fixture), "EFFECTIVE_EMISSION:99");
+}
+
+#[test]
+fn test_emission_applies_the_floor() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  fixture
+    .send_owner(&AppInstruction::SetRewardFloor {
+      min_reward_per_share: 50 * PRECISION,
+    })
+    .unwrap();
+  let staker = fixture.add_staker(3);
+  fixture.stake(&staker, 3).unwrap();
+  assert_eq!(effective_emission(&mut fixture), "EFFECTIVE_EMISSION:150");
+}
+
+// Lone staker of a million shares, three periods in
+fn staked_at_high_tvl(min_reward_per_share: u128, seeded: u64) -> (PoolFixture, Staker) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(seeded).unwrap();
+  fixture
+    .send_owner(&AppInstruction::SetRewardFloor {
+      min_reward_per_share,
+    })
+    .unwrap();
+  let staker = fixture.add_staker(1_000_000);
+  fixture.stake(&staker, 1_000_000).unwrap();
+  warp(180);
+  (fixture, staker)
+}
+
+#[test]
+fn test_floor_is_honored_under_high_tvl() {
+  let (mut fixture, staker) = staked_at_high_tvl(0, 1_000_000);
+  fixture.harvest(&staker).unwrap();
+  let diluted = fixture.bank.token_amount(&staker.sen_acc);
+  // A thousandth of a token per share and period, ten times the diluted rate
+  let (mut fixture, staker) = staked_at_high_tvl(PRECISION / 1000, 1_000_000);
+  fixture.harvest(&staker).unwrap();
+  let floored = fixture.bank.token_amount(&staker.sen_acc);
+  assert!(diluted > 0);
+  assert_eq!(floored, diluted * 10);
+  assert_eq!(
+    fixture.bank.token_amount(&fixture.treasury_sen),
+    1_000_000 - floored
+  );
+}
+
+#[test]
+fn test_floor_is_paid_only_from_the_treasury() {
+  // The floor owes 1000 per period, the treasury holds less
+  let (mut fixture, staker) = staked_at_high_tvl(PRECISION / 1000, 999);
+  assert!(fixture.stake_pool_data().withdraw_only);
+  assert_eq!(
+    fixture.harvest(&staker).unwrap_err(),
+    ProgramError::InsufficientFunds
+  );
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 999);
+  // Topped up, the floor is paid in full for the two elapsed periods
+  fixture.seed(1_000_000).unwrap();
+  fixture.harvest(&staker).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.sen_acc), 2 * 1000);
+}
+
+fn runway_seed(fixture: &mut PoolFixture, days: u64) -> u64 {
+  let keys = [fixture.stake_pool, fixture.treasury_sen];
+  fixture
+    .send(&AppInstruction::GetRunwaySeed { days }, &keys)
+    .unwrap();
+  let log = take_logs()
+    .into_iter()
+    .find(|log| log.starts_with("RUNWAY_SEED:"))
+    .unwrap();
+  log["RUNWAY_SEED:".len()..].parse().unwrap()
+}
+
+#[test]
+fn test_runway_seed_covers_a_simulated_drain() {
+  const DAY: i64 = 86400;
+  let mut fixture = PoolFixture::new(100, 3600);
+  fixture.seed(500).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  // Two days of 24 hourly periods, less what the treasury holds
+  let top_up = runway_seed(&mut fixture, 2);
+  assert_eq!(top_up, 2 * 24 * 100 - 500);
+  fixture.seed(top_up).unwrap();
+  assert_eq!(runway_seed(&mut fixture, 2), 0);
+  for _ in 0..8 {
+    warp(DAY / 4);
+    fixture.harvest(&staker).unwrap();
+  }
+  // The first period is forfeited and stays in the treasury
+  let paid = fixture.bank.token_amount(&staker.sen_acc);
+  assert_eq!(paid, (2 * 24 - 1) * 100);
+  assert_eq!(
+    fixture.bank.token_amount(&fixture.treasury_sen),
+    2 * 24 * 100 - paid
+  );
+}
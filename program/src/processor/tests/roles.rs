@@ -0,0 +1,389 @@
+use crate::context::*;
+use crate::error::AppError;
+use crate::instruction::{tests::samples, AppInstruction};
+use crate::processor::Processor;
+use crate::schema::stake_pool::{StakePool, StakePoolState};
+use crate::test_utils::{metas, run_handler, take_logs, FakeAccount, PoolFixture};
+use solana_program::{
+  account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+  program_pack::Pack, pubkey::Pubkey, system_program, sysvar,
+};
+
+// Accounts that fit every role of the specs
+fn fitting_accounts(program_id: &Pubkey, specs: &[AccountSpec]) -> Vec<FakeAccount> {
+  specs
+    .iter()
+    .map(|spec| {
+      let owner = if spec.is_program() {
+        *program_id
+      } else {
+        Pubkey::new_unique()
+      };
+      let mut acc = FakeAccount::new(Pubkey::new_unique(), owner, vec![0; spec.len]);
+      acc.is_signer = spec.is_signer();
+      acc.is_writable = spec.is_writable();
+      acc
+    })
+    .collect()
+}
+
+// Break one property of one role at a time and expect the load to name it
+fn assert_every_role_is_checked<F>(context: &str, specs: &[AccountSpec], load: F)
+where
+  F: for<'a, 'b, 'c> Fn(&'a Pubkey, &'b [AccountInfo<'c>]) -> ProgramResult,
+{
+  let program_id = Pubkey::new_unique();
+  let accounts = fitting_accounts(&program_id, specs);
+  run_handler(&load, &program_id, &mut accounts.clone()).unwrap();
+  if let Some((_, fewer)) = accounts.split_last() {
+    assert_eq!(
+      run_handler(&load, &program_id, &mut fewer.to_vec()).unwrap_err(),
+      ProgramError::NotEnoughAccountKeys,
+      "{}",
+      context
+    );
+  }
+  for (index, spec) in specs.iter().enumerate() {
+    let broken = |breaks: &dyn Fn(&mut FakeAccount), expected: ProgramError| {
+      let mut accounts = accounts.clone();
+      breaks(&mut accounts[index]);
+      let _ = take_logs();
+      let error = run_handler(&load, &program_id, &mut accounts).unwrap_err();
+      assert_eq!(error, expected, "{}.{}", context, spec.name);
+      let role = format!("Expected role={} ", spec.name);
+      assert!(
+        take_logs().iter().any(|log| log.contains(&role)),
+        "{}.{} is not named",
+        context,
+        spec.name
+      );
+    };
+    if spec.is_program() {
+      broken(
+        &|acc| acc.owner = Pubkey::new_unique(),
+        AppError::IncorrectProgramId.into(),
+      );
+    }
+    if spec.len != 0 {
+      let short = if spec.is_program() {
+        AppError::LegacyLayout.into()
+      } else {
+        ProgramError::InvalidAccountData
+      };
+      broken(&|acc| acc.data.truncate(spec.len - 1), short);
+      broken(&|acc| acc.data.push(0), ProgramError::InvalidAccountData);
+    }
+    if spec.is_writable() {
+      broken(
+        &|acc| acc.is_writable = false,
+        AppError::ReadonlyAccount.into(),
+      );
+    }
+    if spec.is_signer() {
+      broken(&|acc| acc.is_signer = false, AppError::InvalidOwner.into());
+    }
+  }
+}
+
+macro_rules! assert_roles_checked {
+  ($($context:ident),* $(,)?) => {
+    $(assert_every_role_is_checked(stringify!($context), $context::SPECS, |program_id, accounts| {
+      $context::load(program_id, accounts).map(|_| ())
+    });)*
+  };
+}
+
+#[test]
+fn test_every_role_of_every_context_is_checked_at_load() {
+  assert_roles_checked!(
+    InitializeStakePoolContext,
+    InitializeAccountsContext,
+    StakeContext,
+    UnstakeContext,
+    HarvestContext,
+    StakePoolOwnerContext,
+    SeedContext,
+    UnseedContext,
+    TransferStakePoolOwnershipContext,
+    CloseDebtContext,
+    CloseStakePoolContext,
+    GetStoredDebtContext,
+    CrankSeedContext,
+    InitializeContributionContext,
+    WithdrawSeedContext,
+    RepairShareMintContext,
+    InitializeRegistryContext,
+    RegisterExistingPoolContext,
+    DelistPoolContext,
+    GetRewardMintInfoContext,
+    SetUnseedDestinationContext,
+    GetEffectiveRewardContext,
+    HarvestAndSeedContext,
+    UpdatePoolContext,
+    CloneStakePoolContext,
+    TransferOwnershipBatchContext,
+    GetClaimableContext,
+    AllowMintContext,
+    DisallowMintContext,
+    GetPoolTimingContext,
+    ExitPoolContext,
+    GetRunwaySeedContext,
+    SetDefaultSenAccountContext,
+    ClaimVestedContext,
+    IsAccountInitializedContext,
+    ClearWithdrawOnlyContext,
+    GarbageCollectPoolContext,
+    HarvestWithCreateContext,
+    SetDistributionContext,
+    DistributeContext,
+    HarvestSolContext,
+    InitializeSecurityCouncilContext,
+  );
+}
+
+// Every instruction the pool owner signs over the stake pool alone
+fn owner_instructions() -> Vec<AppInstruction> {
+  vec![
+    AppInstruction::FreezeStakePool,
+    AppInstruction::ThawStakePool,
+    AppInstruction::RescaleCompensation {
+      old_precision: 18,
+      new_precision: 12,
+    },
+    AppInstruction::UpdateCrankSeed {
+      max_crank_amount: 7,
+      crank_interval: 8,
+    },
+    AppInstruction::SetRewardFloor {
+      min_reward_per_share: 1,
+    },
+    AppInstruction::SetUnseedLimit {
+      max_unseed_bps_per_day: 500,
+    },
+    AppInstruction::SetHarvestInterval {
+      min_harvest_interval: 60,
+    },
+    AppInstruction::MigrateStakePool,
+    AppInstruction::SetActivation {
+      activation_timestamp: -1,
+    },
+    AppInstruction::SetMaxAccrualPeriods {
+      max_accrual_periods: 12,
+    },
+    AppInstruction::SetCrankTip { crank_tip: 1 },
+    AppInstruction::SetMinClientVersion {
+      min_client_version: 2,
+    },
+    AppInstruction::SetVestingDuration {
+      vesting_duration: 86400,
+    },
+    AppInstruction::ShiftGenesis {
+      delta_seconds: -3600,
+    },
+  ]
+}
+
+#[test]
+fn test_owner_instructions_reject_a_non_owner() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let stake_pool_data = fixture.stake_pool_data();
+  let keys = [Pubkey::new_unique(), fixture.stake_pool];
+  for ix in owner_instructions() {
+    let metas = metas(StakePoolOwnerContext::SPECS, &keys);
+    assert_eq!(
+      fixture.send_metas(&ix, metas).unwrap_err(),
+      AppError::InvalidOwner.into(),
+      "{:?}",
+      ix
+    );
+  }
+  assert_eq!(fixture.stake_pool_data(), stake_pool_data);
+}
+
+#[test]
+fn test_owner_instructions_reject_an_unsigned_owner() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let stake_pool_data = fixture.stake_pool_data();
+  let keys = [fixture.owner, fixture.stake_pool];
+  for ix in owner_instructions() {
+    let mut metas = metas(StakePoolOwnerContext::SPECS, &keys);
+    metas[0].is_signer = false;
+    assert_eq!(
+      fixture.send_metas(&ix, metas).unwrap_err(),
+      AppError::InvalidOwner.into(),
+      "{:?}",
+      ix
+    );
+  }
+  assert_eq!(fixture.stake_pool_data(), stake_pool_data);
+}
+
+#[test]
+fn test_staker_instructions_reject_an_unsigned_wallet() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 500).unwrap();
+  let stake = AppInstruction::Stake {
+    amount: 500,
+    reference: None,
+    idempotency_key: None,
+  };
+  let unstake = AppInstruction::Unstake {
+    amount: 500,
+    reference: None,
+    idempotency_key: None,
+  };
+  for (ix, keys) in [
+    (stake, fixture.stake_keys(&staker)),
+    (unstake, fixture.unstake_keys(&staker)),
+  ]
+  .iter()
+  {
+    let mut metas = metas(account_specs(ix), keys);
+    metas[0].is_signer = false;
+    assert_eq!(
+      fixture.send_metas(ix, metas).unwrap_err(),
+      AppError::InvalidOwner.into(),
+      "{:?}",
+      ix
+    );
+  }
+  // Harvesting elsewhere than the default destination takes the signature
+  let mut keys = fixture.harvest_keys(&staker);
+  keys[5] = fixture.add_token_account(fixture.mint_sen, staker.wallet, 0);
+  let ix = AppInstruction::Harvest {
+    memo: false,
+    idempotency_key: None,
+  };
+  assert_eq!(
+    fixture.send(&ix, &keys).unwrap_err(),
+    AppError::InvalidOwner.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 500);
+}
+
+// Registry set up by its authority with the fixture pool listed and its stake mint allowed
+fn registry_of(fixture: &mut PoolFixture, authority: Pubkey) -> (Pubkey, Pubkey, Pubkey) {
+  let program_id = fixture.program_id;
+  let (registry, _) = Pubkey::find_program_address(&[b"registry"], &program_id);
+  let (registry_page, _) = Pubkey::find_program_address(
+    &[&registry.to_bytes(), &0u64.to_le_bytes(), b"registry_page"],
+    &program_id,
+  );
+  let (mint_whitelist, _) =
+    Pubkey::find_program_address(&[&registry.to_bytes(), b"mint_whitelist"], &program_id);
+  let system = [system_program::id(), sysvar::rent::id()];
+  let payer = fixture.payer;
+  let keys = [&[payer, authority, registry][..], &system].concat();
+  fixture
+    .send(&AppInstruction::InitializeRegistry, &keys)
+    .unwrap();
+  let stake_pool = fixture.stake_pool;
+  let keys = [
+    &[authority, payer, stake_pool, registry, registry_page][..],
+    &system,
+  ]
+  .concat();
+  fixture
+    .send(&AppInstruction::RegisterExistingPool, &keys)
+    .unwrap();
+  let mint_token = fixture.mint_token;
+  let keys = [
+    &[authority, payer, registry, mint_whitelist, mint_token][..],
+    &system,
+  ]
+  .concat();
+  fixture.send(&AppInstruction::AllowMint, &keys).unwrap();
+  (registry, registry_page, mint_whitelist)
+}
+
+#[test]
+fn test_registry_instructions_reject_a_non_authority() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let authority = Pubkey::new_unique();
+  let (registry, registry_page, mint_whitelist) = registry_of(&mut fixture, authority);
+  let stranger = Pubkey::new_unique();
+  let payer = fixture.payer;
+  let system = [system_program::id(), sysvar::rent::id()];
+  let cases = [
+    (
+      AppInstruction::RegisterExistingPool,
+      [
+        &[stranger, payer, fixture.stake_pool, registry, registry_page][..],
+        &system,
+      ]
+      .concat(),
+    ),
+    (
+      AppInstruction::AllowMint,
+      [
+        &[stranger, payer, registry, mint_whitelist, fixture.mint_sen][..],
+        &system,
+      ]
+      .concat(),
+    ),
+    (
+      AppInstruction::DisallowMint,
+      vec![stranger, registry, mint_whitelist, fixture.mint_token],
+    ),
+    // Neither the registry authority nor the pool owner
+    (
+      AppInstruction::DelistPool { index: 0 },
+      vec![stranger, fixture.stake_pool, registry, registry_page],
+    ),
+  ];
+  for (ix, keys) in cases.iter() {
+    assert_eq!(
+      fixture.send(ix, keys).unwrap_err(),
+      AppError::InvalidOwner.into(),
+      "{:?}",
+      ix
+    );
+  }
+  // The pool owner may delist its own pool, unlike the stranger
+  let owner = fixture.owner;
+  fixture
+    .send(
+      &AppInstruction::DelistPool { index: 0 },
+      &[owner, fixture.stake_pool, registry, registry_page],
+    )
+    .unwrap();
+}
+
+// Each instruction loads the context its specs describe, the flags are checked role by role above
+#[test]
+fn test_every_instruction_loads_as_many_accounts_as_its_specs() {
+  for ix in samples() {
+    let data = ix.pack();
+    let process = |program_id: &Pubkey, accounts: &[AccountInfo]| {
+      Processor::process(program_id, accounts, &data)
+    };
+    let program_id = Pubkey::new_unique();
+    let specs = account_specs(&ix);
+    let mut accounts = fitting_accounts(&program_id, specs);
+    // Staker instructions read the client version off the pool before the load
+    let stake_pool =
+      |spec: &AccountSpec| spec.name == "stake_pool_acc" && spec.len == StakePool::LEN;
+    if let Some(index) = specs.iter().position(stake_pool) {
+      let stake_pool_data = StakePool {
+        state: StakePoolState::Initialized,
+        reward: 100,
+        period: 60,
+        ..StakePool::default()
+      };
+      StakePool::pack(stake_pool_data, &mut accounts[index].data).unwrap();
+    }
+    // Failures past the load are expected, the accounts hold no other state
+    let error = run_handler(process, &program_id, &mut accounts.clone()).err();
+    assert_ne!(error, Some(ProgramError::NotEnoughAccountKeys), "{:?}", ix);
+    if let Some((_, fewer)) = accounts.split_last() {
+      assert_eq!(
+        run_handler(process, &program_id, &mut fewer.to_vec()).unwrap_err(),
+        ProgramError::NotEnoughAccountKeys,
+        "{:?}",
+        ix
+      );
+    }
+  }
+}
@@ -0,0 +1,120 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::mint::Mint;
+use crate::test_utils::{splt_program_id, PoolFixture};
+use solana_program::{program_option::COption, pubkey::Pubkey, system_program, sysvar};
+
+#[test]
+fn test_stake_rejects_a_share_mint_of_other_decimals() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.add_staker(1000);
+  let mint_share = fixture.mint_share;
+  fixture.update_mint(&mint_share, |mint| mint.decimals = 9);
+  assert_eq!(
+    fixture.stake(&staker, 1000).unwrap_err(),
+    AppError::DecimalMismatch.into()
+  );
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 1000);
+  // Restored decimals mint 1:1 again
+  fixture.update_mint(&mint_share, |mint| mint.decimals = 6);
+  fixture.stake(&staker, 1000).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.share_acc), 1000);
+}
+
+#[test]
+fn test_rotated_share_mint_authority_is_detected_then_repaired() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.add_staker(1000);
+  let mint_share = fixture.mint_share;
+  let rogue = Pubkey::new_unique();
+  fixture.update_mint(&mint_share, |mint| {
+    mint.mint_authority = COption::Some(rogue)
+  });
+  assert_eq!(
+    fixture.stake(&staker, 1000).unwrap_err(),
+    AppError::CorruptedShareMint.into()
+  );
+  let (stake_pool, treasurer) = (fixture.stake_pool, fixture.treasurer);
+  let council = Pubkey::new_unique();
+  let (council_acc, _) = Pubkey::find_program_address(&[b"security_council"], &fixture.program_id);
+  let keys = [
+    fixture.payer,
+    council,
+    council_acc,
+    system_program::id(),
+    sysvar::rent::id(),
+  ];
+  fixture
+    .send(&AppInstruction::InitializeSecurityCouncil, &keys)
+    .unwrap();
+  assert_eq!(
+    fixture
+      .send(&AppInstruction::InitializeSecurityCouncil, &keys)
+      .unwrap_err(),
+    AppError::ConstructorOnce.into()
+  );
+  let repair = |owner: Pubkey, authority: Pubkey, council: Pubkey| {
+    [
+      owner,
+      stake_pool,
+      mint_share,
+      authority,
+      council,
+      council_acc,
+      treasurer,
+      splt_program_id(),
+    ]
+  };
+  // The pool owner, the current authority and the security council co-sign
+  let cases = [
+    repair(Pubkey::new_unique(), rogue, council),
+    repair(fixture.owner, Pubkey::new_unique(), council),
+    repair(fixture.owner, rogue, Pubkey::new_unique()),
+  ];
+  for keys in cases.iter() {
+    assert_eq!(
+      fixture
+        .send(&AppInstruction::RepairShareMint, keys)
+        .unwrap_err(),
+      AppError::InvalidOwner.into()
+    );
+  }
+  let keys = repair(fixture.owner, rogue, council);
+  fixture
+    .send(&AppInstruction::RepairShareMint, &keys)
+    .unwrap();
+  let mint: Mint = fixture.bank.unpack(&mint_share);
+  assert_eq!(mint.mint_authority, COption::Some(treasurer));
+  fixture.stake(&staker, 1000).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.share_acc), 1000);
+}
+
+#[test]
+fn test_stake_mints_as_many_shares_as_tokens_received() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  let staker = fixture.add_staker(1000);
+  for amount in [1, 299, 700].iter() {
+    let received = fixture.bank.token_amount(&fixture.treasury_token);
+    fixture.stake(&staker, *amount).unwrap();
+    let received = fixture.bank.token_amount(&fixture.treasury_token) - received;
+    assert_eq!(received, *amount);
+  }
+  let mint: Mint = fixture.bank.unpack(&fixture.mint_share);
+  assert_eq!(mint.supply, 1000);
+  assert_eq!(fixture.bank.token_amount(&staker.share_acc), 1000);
+}
+
+#[test]
+fn test_stake_rejects_a_stake_mint_of_other_decimals() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let staker = fixture.add_staker(1000);
+  let mint_token = fixture.mint_token;
+  fixture.update_mint(&mint_token, |mint| mint.decimals = 0);
+  assert_eq!(
+    fixture.stake(&staker, 1000).unwrap_err(),
+    AppError::DecimalMismatch.into()
+  );
+  let mint: Mint = fixture.bank.unpack(&fixture.mint_share);
+  assert_eq!(mint.supply, 0);
+}
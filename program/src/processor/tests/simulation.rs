@@ -0,0 +1,146 @@
+use crate::test_utils::{warp_to, PoolFixture, Staker, GENESIS};
+
+const DAY: u64 = 86400;
+const REWARD: u64 = 1_000_000;
+
+// Deterministic generator so that a failure replays
+struct Lcg(u64);
+
+impl Lcg {
+  fn next(&mut self, bound: u64) -> u64 {
+    self.0 = self
+      .0
+      .wrapping_mul(6364136223846793005)
+      .wrapping_add(1442695040888963407);
+    (self.0 >> 33) % bound
+  }
+}
+
+struct Wallet {
+  staker: Staker,
+  shares: u64,
+  settlements: u64,
+  expected: f64,
+}
+
+#[test]
+fn test_a_year_of_daily_operations_tracks_the_reference_accrual() {
+  let mut fixture = PoolFixture::new(REWARD, DAY);
+  fixture.seed(REWARD * 400).unwrap();
+  let mut wallets: Vec<Wallet> = (0..4)
+    .map(|_| Wallet {
+      staker: fixture.add_staker(1_000_000_000),
+      shares: 0,
+      settlements: 0,
+      expected: 0.0,
+    })
+    .collect();
+  let mut rng = Lcg(922);
+  for day in 0..365 {
+    warp_to(GENESIS + (day * DAY + 1 + rng.next(DAY - 1)) as i64);
+    let start: Vec<u64> = wallets.iter().map(|wallet| wallet.shares).collect();
+    for wallet in wallets.iter_mut() {
+      match rng.next(4) {
+        0 => {
+          let amount = 1 + rng.next(10_000);
+          fixture.stake(&wallet.staker, amount).unwrap();
+          wallet.shares += amount;
+        }
+        1 if wallet.shares > 0 => {
+          let amount = 1 + rng.next(wallet.shares);
+          fixture.unstake(&wallet.staker, amount).unwrap();
+          wallet.shares -= amount;
+        }
+        2 if wallet.shares > 0 => fixture.harvest(&wallet.staker).unwrap(),
+        _ => continue,
+      }
+      wallet.settlements += 1;
+    }
+    // The period pays the shares it began with that are still staked at its end
+    let divisor: u64 = start.iter().sum();
+    if divisor > 0 {
+      for (wallet, start) in wallets.iter_mut().zip(start) {
+        wallet.expected += REWARD as f64 * start.min(wallet.shares) as f64 / divisor as f64;
+      }
+    }
+  }
+  warp_to(GENESIS + (365 * DAY + 1) as i64);
+  let mut max_drift = 0.0f64;
+  for wallet in wallets.iter_mut() {
+    if wallet.shares > 0 {
+      fixture.harvest(&wallet.staker).unwrap();
+      wallet.settlements += 1;
+    }
+    let paid = fixture.bank.token_amount(&wallet.staker.sen_acc) as f64;
+    let drift = wallet.expected - paid;
+    // Every settlement floors the accrued amounts once
+    assert!(
+      drift.abs() <= wallet.settlements as f64 + 1.0,
+      "paid {} for an expected {} over {} settlements",
+      paid,
+      wallet.expected,
+      wallet.settlements
+    );
+    max_drift = max_drift.max(drift.abs());
+  }
+  println!("max drift over a year: {:.6} SEN", max_drift);
+}
+
+#[test]
+fn test_harvests_never_pay_more_than_was_seeded_over_a_full_cycle() {
+  const PERIODS: u64 = 200;
+  let seeded = REWARD * PERIODS;
+  let mut fixture = PoolFixture::new(REWARD, DAY);
+  fixture.seed(seeded).unwrap();
+  let mut wallets: Vec<(Staker, u64)> = (0..6)
+    .map(|_| (fixture.add_staker(1_000_000_000), 0))
+    .collect();
+  let mut rng = Lcg(944);
+  let mut timestamp = GENESIS;
+  while timestamp < GENESIS + (PERIODS * DAY) as i64 {
+    timestamp += 1 + rng.next(DAY / 2) as i64;
+    warp_to(timestamp);
+    let (staker, shares) = &mut wallets[rng.next(6) as usize];
+    match rng.next(3) {
+      0 => {
+        let amount = 1 + rng.next(1_000_000);
+        fixture.stake(staker, amount).unwrap();
+        *shares += amount;
+      }
+      1 if *shares > 0 => {
+        let amount = 1 + rng.next(*shares);
+        fixture.unstake(staker, amount).unwrap();
+        *shares -= amount;
+      }
+      _ if *shares > 0 => fixture.harvest(staker).unwrap(),
+      _ => {}
+    }
+    let tally: u64 = wallets
+      .iter()
+      .map(|(staker, _)| fixture.bank.token_amount(&staker.sen_acc))
+      .sum();
+    // Every paid unit left the treasury and was emitted by an elapsed period
+    let delay = (timestamp - GENESIS) as u64 / DAY;
+    assert_eq!(
+      fixture.bank.token_amount(&fixture.treasury_sen) + tally,
+      seeded
+    );
+    assert!(
+      tally <= REWARD * delay,
+      "tally {} over {} emitted",
+      tally,
+      REWARD * delay
+    );
+  }
+  warp_to(GENESIS + (PERIODS * DAY) as i64);
+  for (staker, shares) in wallets.iter() {
+    if *shares > 0 {
+      fixture.harvest(staker).unwrap();
+    }
+  }
+  let tally: u64 = wallets
+    .iter()
+    .map(|(staker, _)| fixture.bank.token_amount(&staker.sen_acc))
+    .sum();
+  assert!(tally <= seeded, "tally {} over {} seeded", tally, seeded);
+}
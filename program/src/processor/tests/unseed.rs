@@ -0,0 +1,200 @@
+use crate::context::SetUnseedDestinationContext;
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::schema::stake_pool::UNSEED_WINDOW;
+use crate::test_utils::{metas, now, take_logs, warp, PoolFixture};
+use solana_program::pubkey::Pubkey;
+
+const TREASURY: u64 = 1_000_000;
+
+// A pool of a seeded treasury whose withdrawals are capped to a tenth per window
+fn capped_pool() -> PoolFixture {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(TREASURY).unwrap();
+  fixture
+    .send_owner(&AppInstruction::SetUnseedLimit {
+      max_unseed_bps_per_day: 1000,
+    })
+    .unwrap();
+  fixture
+}
+
+fn exceeded() -> solana_program::program_error::ProgramError {
+  AppError::UnseedLimitExceeded.into()
+}
+
+#[test]
+fn test_unseed_exactly_at_the_limit() {
+  let mut fixture = capped_pool();
+  let dst = fixture.owner_sen_acc;
+  fixture.unseed(60_000, dst).unwrap();
+  fixture.unseed(40_000, dst).unwrap();
+  assert_eq!(fixture.unseed(1, dst).unwrap_err(), exceeded());
+  let stake_pool_data = fixture.stake_pool_data();
+  assert_eq!(stake_pool_data.unseed_window_balance, TREASURY);
+  assert_eq!(stake_pool_data.unseeded_in_window, 100_000);
+}
+
+#[test]
+fn test_seeding_mid_window_keeps_the_limit_of_the_window_start() {
+  let mut fixture = capped_pool();
+  let dst = fixture.owner_sen_acc;
+  fixture.unseed(50_000, dst).unwrap();
+  fixture.seed(9 * TREASURY).unwrap();
+  assert_eq!(fixture.unseed(50_001, dst).unwrap_err(), exceeded());
+  fixture.unseed(50_000, dst).unwrap();
+}
+
+#[test]
+fn test_window_rolls_over_on_the_remaining_treasury() {
+  let mut fixture = capped_pool();
+  let dst = fixture.owner_sen_acc;
+  fixture.unseed(100_000, dst).unwrap();
+  warp(UNSEED_WINDOW as i64 - 1);
+  assert_eq!(fixture.unseed(1, dst).unwrap_err(), exceeded());
+  warp(1);
+  fixture.unseed(90_000, dst).unwrap();
+  assert_eq!(fixture.unseed(1, dst).unwrap_err(), exceeded());
+  let stake_pool_data = fixture.stake_pool_data();
+  assert_eq!(stake_pool_data.unseed_window_balance, TREASURY - 100_000);
+  assert_eq!(stake_pool_data.unseeded_in_window, 90_000);
+}
+
+#[test]
+fn test_withdraw_seed_shares_the_window() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let contributor = fixture.add_contributor(TREASURY);
+  fixture
+    .send_owner(&AppInstruction::SetUnseedLimit {
+      max_unseed_bps_per_day: 1000,
+    })
+    .unwrap();
+  let dst = fixture.owner_sen_acc;
+  fixture.unseed(50_000, dst).unwrap();
+  fixture
+    .withdraw_seed(&contributor, 50_000, contributor.sen_acc)
+    .unwrap();
+  assert_eq!(
+    fixture
+      .withdraw_seed(&contributor, 1, contributor.sen_acc)
+      .unwrap_err(),
+    exceeded()
+  );
+  assert_eq!(fixture.unseed(1, dst).unwrap_err(), exceeded());
+  warp(UNSEED_WINDOW as i64);
+  fixture
+    .withdraw_seed(&contributor, 90_000, contributor.sen_acc)
+    .unwrap();
+  assert_eq!(fixture.bank.token_amount(&contributor.sen_acc), 140_000);
+}
+
+#[test]
+fn test_zero_limit_is_unlimited() {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(TREASURY).unwrap();
+  let dst = fixture.owner_sen_acc;
+  fixture.unseed(TREASURY, dst).unwrap();
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 0);
+}
+
+fn pin_destination(fixture: &mut PoolFixture, destination: Pubkey) {
+  let keys = [fixture.owner, fixture.stake_pool, destination];
+  fixture
+    .send_metas(
+      &AppInstruction::SetUnseedDestination,
+      metas(SetUnseedDestinationContext::SPECS, &keys),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_withdraw_seed_rejects_a_destination_of_another_mint() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let contributor = fixture.add_contributor(TREASURY);
+  let wrong_mint = fixture.add_token_account(fixture.mint_token, contributor.wallet, 0);
+  assert_eq!(
+    fixture
+      .withdraw_seed(&contributor, 1, wrong_mint)
+      .unwrap_err(),
+    AppError::UnmatchedTreasurySen.into()
+  );
+}
+
+#[test]
+fn test_withdraw_seed_follows_the_pinned_destination() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let contributor = fixture.add_contributor(TREASURY);
+  let pinned = fixture.add_token_account(fixture.mint_sen, contributor.wallet, 0);
+  pin_destination(&mut fixture, pinned);
+  assert_eq!(
+    fixture
+      .withdraw_seed(&contributor, 1, contributor.sen_acc)
+      .unwrap_err(),
+    AppError::UnauthorizedDestination.into()
+  );
+  fixture.withdraw_seed(&contributor, 1, pinned).unwrap();
+  assert_eq!(fixture.bank.token_amount(&pinned), 1);
+}
+
+#[test]
+fn test_withdraw_seed_is_unrestricted_by_default() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let contributor = fixture.add_contributor(TREASURY);
+  let other = fixture.add_token_account(fixture.mint_sen, Pubkey::new_unique(), 0);
+  fixture
+    .withdraw_seed(&contributor, TREASURY, other)
+    .unwrap();
+  assert_eq!(fixture.bank.token_amount(&other), TREASURY);
+}
+
+#[test]
+fn test_two_seeders_withdraw_their_own_share() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let first = fixture.add_contributor(300_000);
+  let second = fixture.add_contributor(700_000);
+  assert_eq!(
+    fixture
+      .withdraw_seed(&first, 300_001, first.sen_acc)
+      .unwrap_err(),
+    AppError::ExceedLimit.into()
+  );
+  fixture
+    .withdraw_seed(&first, 300_000, first.sen_acc)
+    .unwrap();
+  fixture
+    .withdraw_seed(&second, 200_000, second.sen_acc)
+    .unwrap();
+  assert_eq!(fixture.bank.token_amount(&first.sen_acc), 300_000);
+  assert_eq!(fixture.bank.token_amount(&second.sen_acc), 200_000);
+  assert_eq!(fixture.bank.token_amount(&fixture.treasury_sen), 500_000);
+  // The first seeder is done, the second keeps its remainder
+  assert_eq!(
+    fixture.withdraw_seed(&first, 1, first.sen_acc).unwrap_err(),
+    AppError::ExceedLimit.into()
+  );
+  fixture
+    .withdraw_seed(&second, 500_000, second.sen_acc)
+    .unwrap();
+}
+
+#[test]
+fn test_withdraw_seed_is_logged_as_an_admin_action() {
+  let mut fixture = PoolFixture::new(100, 60);
+  let contributor = fixture.add_contributor(TREASURY);
+  let count = fixture.stake_pool_data().admin_action_count;
+  fixture
+    .withdraw_seed(&contributor, 1000, contributor.sen_acc)
+    .unwrap();
+  let expected = format!(
+    "ADMIN_ACTION:action=WITHDRAW_SEED,stake_pool={},signer={},old={},new={},count={}",
+    fixture.stake_pool,
+    contributor.wallet,
+    TREASURY,
+    TREASURY - 1000,
+    count + 1
+  );
+  assert!(take_logs().iter().any(|log| log.starts_with(&expected)));
+  let stake_pool_data = fixture.stake_pool_data();
+  assert_eq!(stake_pool_data.admin_action_count, count + 1);
+  assert_eq!(stake_pool_data.last_admin_action, now());
+}
@@ -0,0 +1,137 @@
+use crate::context::ClaimVestedContext;
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::test_utils::{metas, now, splt_program_id, warp, warp_to, PoolFixture, Staker};
+
+const DURATION: u64 = 600;
+
+fn vesting_pool() -> (PoolFixture, Staker) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(1_000_000).unwrap();
+  fixture
+    .send_owner(&AppInstruction::SetVestingDuration {
+      vesting_duration: DURATION,
+    })
+    .unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  (fixture, staker)
+}
+
+fn claimed(fixture: &PoolFixture, staker: &Staker) -> u64 {
+  fixture.bank.token_amount(&staker.sen_acc)
+}
+
+fn vesting_end(fixture: &PoolFixture, staker: &Staker) -> i64 {
+  let debt_data = fixture.debt_data(staker);
+  debt_data.vesting_start + debt_data.vesting_duration as i64
+}
+
+#[test]
+fn test_harvest_vests_then_claims_unlock_linearly() {
+  let (mut fixture, staker) = vesting_pool();
+  warp(600);
+  fixture.harvest(&staker).unwrap();
+  let harvested_at = now();
+  let vesting = fixture.debt_data(&staker).vesting_amount;
+  assert!(vesting > 0);
+  assert_eq!(claimed(&fixture, &staker), 0);
+  assert_eq!(fixture.stake_pool_data().total_vesting, vesting);
+  // Nothing is unlocked at the start
+  assert_eq!(
+    fixture.claim_vested(&staker).unwrap_err(),
+    AppError::ZeroValue.into()
+  );
+  // A quarter, then half, then all of it
+  warp_to(harvested_at + 150);
+  fixture.claim_vested(&staker).unwrap();
+  assert_eq!(claimed(&fixture, &staker), vesting / 4);
+  warp_to(harvested_at + 300);
+  fixture.claim_vested(&staker).unwrap();
+  let half = claimed(&fixture, &staker);
+  assert!(half + 1 >= vesting / 2 && half <= vesting / 2);
+  warp_to(harvested_at + DURATION as i64);
+  fixture.claim_vested(&staker).unwrap();
+  assert_eq!(claimed(&fixture, &staker), vesting);
+  assert_eq!(fixture.stake_pool_data().total_vesting, 0);
+  assert_eq!(fixture.debt_data(&staker).vesting_amount, 0);
+}
+
+#[test]
+fn test_new_yield_does_not_relock_the_running_schedule() {
+  let (mut fixture, staker) = vesting_pool();
+  warp(600);
+  fixture.harvest(&staker).unwrap();
+  let first = fixture.debt_data(&staker).vesting_amount;
+  let first_end = vesting_end(&fixture, &staker);
+  // Halfway through, a second harvest merges into the schedule
+  warp(DURATION as i64 / 2);
+  fixture.harvest(&staker).unwrap();
+  let debt_data = fixture.debt_data(&staker);
+  let second = debt_data.vesting_amount + debt_data.vested_amount - first;
+  assert!(second > 0);
+  let end = vesting_end(&fixture, &staker);
+  assert!(end > first_end);
+  assert!(end < now() + DURATION as i64);
+  // The first yield keeps unlocking, it is not re-locked for the full duration
+  warp(1);
+  fixture.claim_vested(&staker).unwrap();
+  assert!(claimed(&fixture, &staker) >= first / 2);
+  // Everything is claimable at the merged end
+  warp_to(end);
+  fixture.claim_vested(&staker).unwrap();
+  assert_eq!(claimed(&fixture, &staker), first + second);
+}
+
+#[test]
+fn test_shorter_duration_keeps_the_running_end() {
+  let (mut fixture, staker) = vesting_pool();
+  warp(600);
+  fixture.harvest(&staker).unwrap();
+  let first_end = vesting_end(&fixture, &staker);
+  fixture
+    .send_owner(&AppInstruction::SetVestingDuration {
+      vesting_duration: 60,
+    })
+    .unwrap();
+  warp(100);
+  fixture.harvest(&staker).unwrap();
+  let end = vesting_end(&fixture, &staker);
+  assert!(end <= first_end.max(now() + 60));
+  assert!(end >= now() + 60);
+  warp_to(first_end);
+  fixture.claim_vested(&staker).unwrap();
+  assert_eq!(fixture.debt_data(&staker).vesting_amount, 0);
+}
+
+#[test]
+fn test_claim_vested_requires_a_current_client() {
+  let (mut fixture, staker) = vesting_pool();
+  warp(600);
+  fixture.harvest(&staker).unwrap();
+  fixture
+    .send_owner(&AppInstruction::SetMinClientVersion {
+      min_client_version: 2,
+    })
+    .unwrap();
+  warp(DURATION as i64);
+  assert_eq!(
+    fixture.claim_vested(&staker).unwrap_err(),
+    AppError::ClientTooOld.into()
+  );
+  assert_eq!(claimed(&fixture, &staker), 0);
+  let keys = [
+    staker.wallet,
+    fixture.stake_pool,
+    staker.debt_acc,
+    staker.sen_acc,
+    fixture.treasury_sen,
+    fixture.treasurer,
+    splt_program_id(),
+  ];
+  let data = AppInstruction::ClaimVested.pack_with_client_version(2);
+  fixture
+    .send_data(data, metas(ClaimVestedContext::SPECS, &keys))
+    .unwrap();
+  assert!(claimed(&fixture, &staker) > 0);
+}
@@ -0,0 +1,59 @@
+use crate::error::AppError;
+use crate::instruction::AppInstruction;
+use crate::test_utils::{warp, PoolFixture, Staker};
+use solana_program::entrypoint::ProgramResult;
+
+// A lone staker, its stake checks the treasury against a next period of 100
+fn staked_over(seeded: u64) -> (PoolFixture, Staker) {
+  let mut fixture = PoolFixture::new(100, 60);
+  fixture.seed(seeded).unwrap();
+  let staker = fixture.add_staker(1000);
+  fixture.stake(&staker, 1000).unwrap();
+  (fixture, staker)
+}
+
+fn clear_withdraw_only(fixture: &mut PoolFixture) -> ProgramResult {
+  let keys = [fixture.owner, fixture.stake_pool, fixture.treasury_sen];
+  fixture.send(&AppInstruction::ClearWithdrawOnly, &keys)
+}
+
+#[test]
+fn test_withdraw_only_engages_below_the_next_period() {
+  let (fixture, _) = staked_over(100);
+  assert!(!fixture.stake_pool_data().withdraw_only);
+  let (fixture, _) = staked_over(99);
+  assert!(fixture.stake_pool_data().withdraw_only);
+}
+
+#[test]
+fn test_withdraw_only_blocks_stakes_but_not_withdrawals() {
+  let (mut fixture, staker) = staked_over(99);
+  let newcomer = fixture.add_staker(1000);
+  assert_eq!(
+    fixture.stake(&newcomer, 1000).unwrap_err(),
+    AppError::WithdrawOnly.into()
+  );
+  assert_eq!(
+    fixture.stake(&staker, 1).unwrap_err(),
+    AppError::WithdrawOnly.into()
+  );
+  warp(30);
+  fixture.harvest(&staker).unwrap();
+  fixture.unstake(&staker, 400).unwrap();
+  assert_eq!(fixture.bank.token_amount(&staker.token_acc), 400);
+}
+
+#[test]
+fn test_withdraw_only_clears_once_the_treasury_covers_the_next_period() {
+  let (mut fixture, _) = staked_over(99);
+  assert_eq!(
+    clear_withdraw_only(&mut fixture).unwrap_err(),
+    AppError::InsufficientFunds.into()
+  );
+  assert!(fixture.stake_pool_data().withdraw_only);
+  fixture.seed(1).unwrap();
+  clear_withdraw_only(&mut fixture).unwrap();
+  assert!(!fixture.stake_pool_data().withdraw_only);
+  let newcomer = fixture.add_staker(1000);
+  fixture.stake(&newcomer, 1000).unwrap();
+}
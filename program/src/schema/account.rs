@@ -45,6 +45,16 @@ impl Account {
   pub fn is_native(&self) -> bool {
     self.is_native.is_some()
   }
+  /// Returns the delegate and the amount it can actually move, if any
+  pub fn effective_delegate(&self) -> Option<(Pubkey, u64)> {
+    if self.is_frozen() {
+      return None;
+    }
+    match self.delegate {
+      COption::Some(delegate) => Some((delegate, self.delegated_amount.min(self.amount))),
+      COption::None => None,
+    }
+  }
 }
 
 ///
@@ -177,3 +187,71 @@ fn unpack_coption_u64(src: &[u8; 12]) -> Result<COption<u64>, ProgramError> {
     _ => Err(ProgramError::InvalidAccountData),
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::from_hex;
+
+  // Packed by spl_token::state::Account 3.5.0
+  const SPL_ACCOUNT: &str = "\
+    0202020202020202020202020202020202020202020202020202020202020202\
+    0303030303030303030303030303030303030303030303030303030303030303\
+    0700000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000100000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000";
+  const SPL_ACCOUNT_DELEGATED: &str = "\
+    0202020202020202020202020202020202020202020202020202020202020202\
+    0303030303030303030303030303030303030303030303030303030303030303\
+    e803000000000000010000000404040404040404040404040404040404040404\
+    0404040404040404040404040201000000f01d1f0000000000f4010000000000\
+    0001000000050505050505050505050505050505050505050505050505050505\
+    0505050505";
+
+  fn round_trip(hex: &str, account: Account) {
+    let bytes = from_hex(hex);
+    assert_eq!(Account::unpack(&bytes).unwrap(), account);
+    let mut packed = vec![0; Account::LEN];
+    Account::pack(account, &mut packed).unwrap();
+    assert_eq!(packed, bytes);
+  }
+
+  #[test]
+  fn test_account_matches_the_spl_token_layout() {
+    let account = Account {
+      mint: Pubkey::new_from_array([2; 32]),
+      owner: Pubkey::new_from_array([3; 32]),
+      amount: 7,
+      delegate: COption::None,
+      state: AccountState::Initialized,
+      is_native: COption::None,
+      delegated_amount: 0,
+      close_authority: COption::None,
+    };
+    round_trip(SPL_ACCOUNT, account);
+    let account = Account {
+      amount: 1000,
+      delegate: COption::Some(Pubkey::new_from_array([4; 32])),
+      state: AccountState::Frozen,
+      is_native: COption::Some(2039280),
+      delegated_amount: 500,
+      close_authority: COption::Some(Pubkey::new_from_array([5; 32])),
+      ..account
+    };
+    round_trip(SPL_ACCOUNT_DELEGATED, account);
+    assert!(account.is_frozen());
+    assert!(account.is_native());
+    assert_eq!(account.effective_delegate(), None);
+  }
+
+  #[test]
+  fn test_account_rejects_an_unknown_state() {
+    let mut bytes = from_hex(SPL_ACCOUNT);
+    bytes[108] = 3;
+    assert_eq!(
+      Account::unpack_unchecked(&bytes).unwrap_err(),
+      ProgramError::InvalidAccountData
+    );
+  }
+}
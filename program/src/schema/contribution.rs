@@ -0,0 +1,73 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+  msg,
+  program_error::ProgramError,
+  program_pack::{IsInitialized, Pack, Sealed},
+  pubkey::Pubkey,
+};
+
+//
+// Define the data struct
+//
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Contribution {
+  pub stake_pool: Pubkey,
+  pub owner: Pubkey,
+  pub amount: u64, // units: SEN
+  pub is_initialized: bool,
+}
+
+//
+// Implement Sealed trait
+//
+impl Sealed for Contribution {}
+
+//
+// Implement IsInitialized trait
+//
+impl IsInitialized for Contribution {
+  fn is_initialized(&self) -> bool {
+    self.is_initialized
+  }
+}
+
+//
+// Implement Pack trait
+//
+impl Pack for Contribution {
+  // Fixed length
+  const LEN: usize = 73;
+  // Unpack data from [u8] to the data struct
+  fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+    msg!("Read contribution data");
+    let src = array_ref![src, 0, 73];
+    let (stake_pool, owner, amount, is_initialized) = array_refs![src, 32, 32, 8, 1];
+    Ok(Contribution {
+      stake_pool: Pubkey::new_from_array(*stake_pool),
+      owner: Pubkey::new_from_array(*owner),
+      amount: u64::from_le_bytes(*amount),
+      is_initialized: match is_initialized {
+        [0] => false,
+        [1] => true,
+        _ => return Err(ProgramError::InvalidAccountData),
+      },
+    })
+  }
+  // Pack data from the data struct to [u8]
+  fn pack_into_slice(&self, dst: &mut [u8]) {
+    msg!("Write contribution data");
+    let dst = array_mut_ref![dst, 0, 73];
+    let (dst_stake_pool, dst_owner, dst_amount, dst_is_initialized) =
+      mut_array_refs![dst, 32, 32, 8, 1];
+    let &Contribution {
+      ref stake_pool,
+      ref owner,
+      amount,
+      is_initialized,
+    } = self;
+    dst_stake_pool.copy_from_slice(stake_pool.as_ref());
+    dst_owner.copy_from_slice(owner.as_ref());
+    *dst_amount = amount.to_le_bytes();
+    *dst_is_initialized = [is_initialized as u8];
+  }
+}
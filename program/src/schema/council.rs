@@ -0,0 +1,65 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+  msg,
+  program_error::ProgramError,
+  program_pack::{IsInitialized, Pack, Sealed},
+  pubkey::Pubkey,
+};
+
+//
+// Define the data struct
+// The program-wide co-signer of the recovery instructions
+//
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SecurityCouncil {
+  pub council: Pubkey,
+  pub is_initialized: bool,
+}
+
+//
+// Implement Sealed trait
+//
+impl Sealed for SecurityCouncil {}
+
+//
+// Implement IsInitialized trait
+//
+impl IsInitialized for SecurityCouncil {
+  fn is_initialized(&self) -> bool {
+    self.is_initialized
+  }
+}
+
+//
+// Implement Pack trait
+//
+impl Pack for SecurityCouncil {
+  // Fixed length
+  const LEN: usize = 33;
+  // Unpack data from [u8] to the data struct
+  fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+    msg!("Read security council data");
+    let src = array_ref![src, 0, 33];
+    let (council, is_initialized) = array_refs![src, 32, 1];
+    Ok(SecurityCouncil {
+      council: Pubkey::new_from_array(*council),
+      is_initialized: match is_initialized {
+        [0] => false,
+        [1] => true,
+        _ => return Err(ProgramError::InvalidAccountData),
+      },
+    })
+  }
+  // Pack data from the data struct to [u8]
+  fn pack_into_slice(&self, dst: &mut [u8]) {
+    msg!("Write security council data");
+    let dst = array_mut_ref![dst, 0, 33];
+    let (dst_council, dst_is_initialized) = mut_array_refs![dst, 32, 1];
+    let &SecurityCouncil {
+      ref council,
+      is_initialized,
+    } = self;
+    dst_council.copy_from_slice(council.as_ref());
+    *dst_is_initialized = [is_initialized as u8];
+  }
+}
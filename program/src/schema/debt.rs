@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use solana_program::{
   msg,
@@ -6,6 +7,16 @@ use solana_program::{
   pubkey::Pubkey,
 };
 
+///
+/// Idempotency keys remembered per position
+///
+pub const IDEMPOTENCY_KEYS: usize = 4;
+
+///
+/// Trailing bytes kept zero for future fields, so that adding one does not grow LEN
+///
+pub const RESERVED_LEN: usize = 56;
+
 //
 // Define the data struct
 //
@@ -14,8 +25,47 @@ pub struct Debt {
   pub stake_pool: Pubkey,
   pub owner: Pubkey,
   pub account: Pubkey,
-  pub debt: u128, // units: SEN
+  pub debt: u128, // units: SEN, PRECISION already divided out
   pub is_initialized: bool,
+  pub last_delay: u64,                                // periods
+  pub forfeit: u64,                                   // units: SEN
+  pub last_harvest_timestamp: i64,                    // seconds
+  pub last_settled_delay: u64,                        // periods
+  pub default_sen_account: Pubkey, // Harvest destination for keepers, default means none
+  pub vesting_amount: u64,         // units: SEN, locked at vesting_start
+  pub vesting_start: i64,          // seconds
+  pub vesting_duration: u64,       // seconds
+  pub vested_amount: u64,          // units: SEN, unlocked but not claimed
+  pub idempotency_keys: [[u8; 16]; IDEMPOTENCY_KEYS], // Ring of the last idempotency keys
+  pub idempotency_cursor: u8,      // Slot of the next key in the ring
+  pub pending_shares: u64,         // Staked during the period of last_delay, accruing from its end
+}
+
+impl Debt {
+  // Internal consistency of a debt about to be persisted
+  pub fn validate(&self) -> Result<(), AppError> {
+    if !self.is_initialized
+      || self.stake_pool == Pubkey::default()
+      || self.owner == Pubkey::default()
+      || self.account == Pubkey::default()
+    {
+      return Err(AppError::InconsistentState);
+    }
+    if self.idempotency_cursor as usize >= IDEMPOTENCY_KEYS {
+      return Err(AppError::InconsistentState);
+    }
+    Ok(())
+  }
+  // Whether the key was recorded by one of the last instructions
+  pub fn has_idempotency_key(&self, key: &[u8; 16]) -> bool {
+    self.idempotency_keys.contains(key)
+  }
+  // Remember the key in place of the oldest one
+  pub fn record_idempotency_key(&mut self, key: [u8; 16]) {
+    let cursor = self.idempotency_cursor as usize % IDEMPOTENCY_KEYS;
+    self.idempotency_keys[cursor] = key;
+    self.idempotency_cursor = ((cursor + 1) % IDEMPOTENCY_KEYS) as u8;
+  }
 }
 
 //
@@ -37,12 +87,35 @@ impl IsInitialized for Debt {
 //
 impl Pack for Debt {
   // Fixed length
-  const LEN: usize = 113;
+  const LEN: usize = 338;
   // Unpack data from [u8] to the data struct
   fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
     msg!("Read debt data");
-    let src = array_ref![src, 0, 113];
-    let (stake_pool, owner, account, debt, is_initialized) = array_refs![src, 32, 32, 32, 16, 1];
+    // Accounts of a smaller legacy layout must not panic on indexing
+    if src.len() < Self::LEN {
+      return Err(ProgramError::AccountDataTooSmall);
+    }
+    let src = array_ref![src, 0, 338];
+    let (
+      stake_pool,
+      owner,
+      account,
+      debt,
+      is_initialized,
+      last_delay,
+      forfeit,
+      last_harvest_timestamp,
+      last_settled_delay,
+      default_sen_account,
+      vesting_amount,
+      vesting_start,
+      vesting_duration,
+      vested_amount,
+      idempotency_keys,
+      idempotency_cursor,
+      pending_shares,
+      _reserved,
+    ) = array_refs![src, 32, 32, 32, 16, 1, 8, 8, 8, 8, 32, 8, 8, 8, 8, 64, 1, 8, 56];
     Ok(Debt {
       stake_pool: Pubkey::new_from_array(*stake_pool),
       owner: Pubkey::new_from_array(*owner),
@@ -53,25 +126,142 @@ impl Pack for Debt {
         [1] => true,
         _ => return Err(ProgramError::InvalidAccountData),
       },
+      last_delay: u64::from_le_bytes(*last_delay),
+      forfeit: u64::from_le_bytes(*forfeit),
+      last_harvest_timestamp: i64::from_le_bytes(*last_harvest_timestamp),
+      last_settled_delay: u64::from_le_bytes(*last_settled_delay),
+      default_sen_account: Pubkey::new_from_array(*default_sen_account),
+      vesting_amount: u64::from_le_bytes(*vesting_amount),
+      vesting_start: i64::from_le_bytes(*vesting_start),
+      vesting_duration: u64::from_le_bytes(*vesting_duration),
+      vested_amount: u64::from_le_bytes(*vested_amount),
+      idempotency_keys: {
+        let (k0, k1, k2, k3) = array_refs![idempotency_keys, 16, 16, 16, 16];
+        [*k0, *k1, *k2, *k3]
+      },
+      idempotency_cursor: idempotency_cursor[0],
+      pending_shares: u64::from_le_bytes(*pending_shares),
     })
   }
   // Pack data from the data struct to [u8]
   fn pack_into_slice(&self, dst: &mut [u8]) {
     msg!("Write debt data");
-    let dst = array_mut_ref![dst, 0, 113];
-    let (dst_stake_pool, dst_owner, dst_account, dst_debt, dst_is_initialized) =
-      mut_array_refs![dst, 32, 32, 32, 16, 1];
+    let dst = array_mut_ref![dst, 0, 338];
+    let (
+      dst_stake_pool,
+      dst_owner,
+      dst_account,
+      dst_debt,
+      dst_is_initialized,
+      dst_last_delay,
+      dst_forfeit,
+      dst_last_harvest_timestamp,
+      dst_last_settled_delay,
+      dst_default_sen_account,
+      dst_vesting_amount,
+      dst_vesting_start,
+      dst_vesting_duration,
+      dst_vested_amount,
+      dst_idempotency_keys,
+      dst_idempotency_cursor,
+      dst_pending_shares,
+      dst_reserved,
+    ) = mut_array_refs![dst, 32, 32, 32, 16, 1, 8, 8, 8, 8, 32, 8, 8, 8, 8, 64, 1, 8, 56];
     let &Debt {
       ref stake_pool,
       ref owner,
       ref account,
       debt,
       is_initialized,
+      last_delay,
+      forfeit,
+      last_harvest_timestamp,
+      last_settled_delay,
+      ref default_sen_account,
+      vesting_amount,
+      vesting_start,
+      vesting_duration,
+      vested_amount,
+      idempotency_keys,
+      idempotency_cursor,
+      pending_shares,
     } = self;
     dst_stake_pool.copy_from_slice(stake_pool.as_ref());
     dst_owner.copy_from_slice(owner.as_ref());
     dst_account.copy_from_slice(account.as_ref());
     *dst_debt = debt.to_le_bytes();
     *dst_is_initialized = [is_initialized as u8];
+    *dst_last_delay = last_delay.to_le_bytes();
+    *dst_forfeit = forfeit.to_le_bytes();
+    *dst_last_harvest_timestamp = last_harvest_timestamp.to_le_bytes();
+    *dst_last_settled_delay = last_settled_delay.to_le_bytes();
+    dst_default_sen_account.copy_from_slice(default_sen_account.as_ref());
+    *dst_vesting_amount = vesting_amount.to_le_bytes();
+    *dst_vesting_start = vesting_start.to_le_bytes();
+    *dst_vesting_duration = vesting_duration.to_le_bytes();
+    *dst_vested_amount = vested_amount.to_le_bytes();
+    for (dst, key) in dst_idempotency_keys
+      .chunks_mut(16)
+      .zip(idempotency_keys.iter())
+    {
+      dst.copy_from_slice(key);
+    }
+    *dst_idempotency_cursor = [idempotency_cursor];
+    *dst_pending_shares = pending_shares.to_le_bytes();
+    *dst_reserved = [0; RESERVED_LEN];
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Named invariant and how to break it
+  type Violation = (&'static str, fn(&mut Debt));
+
+  fn valid() -> Debt {
+    Debt {
+      stake_pool: Pubkey::new_unique(),
+      owner: Pubkey::new_unique(),
+      account: Pubkey::new_unique(),
+      is_initialized: true,
+      idempotency_cursor: (IDEMPOTENCY_KEYS - 1) as u8,
+      ..Debt::default()
+    }
+  }
+
+  #[test]
+  fn test_validate_rejects_each_violated_invariant() {
+    assert_eq!(valid().validate(), Ok(()));
+    let violations: Vec<Violation> = vec![
+      ("uninitialized", |data| data.is_initialized = false),
+      ("no stake pool", |data| data.stake_pool = Pubkey::default()),
+      ("no owner", |data| data.owner = Pubkey::default()),
+      ("no account", |data| data.account = Pubkey::default()),
+      ("cursor out of the ring", |data| {
+        data.idempotency_cursor = IDEMPOTENCY_KEYS as u8
+      }),
+    ];
+    for (invariant, violate) in violations {
+      let mut data = valid();
+      violate(&mut data);
+      assert_eq!(
+        data.validate(),
+        Err(AppError::InconsistentState),
+        "{}",
+        invariant
+      );
+    }
+  }
+
+  #[test]
+  fn test_unpack_of_an_undersized_buffer_is_a_clean_error() {
+    // Empty, of the first deployed layout and one byte short
+    for len in [0, 113, Debt::LEN - 1].iter() {
+      assert_eq!(
+        Debt::unpack_from_slice(&vec![0; *len]).unwrap_err(),
+        ProgramError::AccountDataTooSmall
+      );
+    }
   }
 }
@@ -0,0 +1,121 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+  msg,
+  program_error::ProgramError,
+  program_pack::{IsInitialized, Pack, Sealed},
+  pubkey::Pubkey,
+};
+
+///
+/// Number of child pools a distribution can feed
+///
+pub const MAX_DISTRIBUTION_CHILDREN: usize = 8;
+
+//
+// Define the data struct
+// Entries past count are the default pubkey with a zero weight
+//
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DistributionConfig {
+  pub authority: Pubkey,
+  pub children: [Pubkey; MAX_DISTRIBUTION_CHILDREN], // Child stake pools
+  pub weights_bps: [u16; MAX_DISTRIBUTION_CHILDREN], // Summing to 10000 over count
+  pub count: u8,
+  pub is_initialized: bool,
+}
+
+impl DistributionConfig {
+  // Every listed child takes a part and the parts make the whole
+  pub fn is_valid_weights(weights_bps: &[u16]) -> bool {
+    !weights_bps.is_empty()
+      && weights_bps.iter().all(|&weight| weight != 0)
+      && weights_bps.iter().map(|&weight| weight as u64).sum::<u64>() == 10000
+  }
+  // Part of each child, the last takes the rounding remainder
+  pub fn split(&self, amount: u64) -> Option<Vec<u64>> {
+    let count = self.count as usize;
+    let mut parts = Vec::with_capacity(count);
+    let mut remaining = amount;
+    for (i, &weight) in self.weights_bps.iter().take(count).enumerate() {
+      let part = if i + 1 == count {
+        remaining
+      } else {
+        (amount as u128 * weight as u128 / 10000) as u64
+      };
+      remaining = remaining.checked_sub(part)?;
+      parts.push(part);
+    }
+    Some(parts)
+  }
+}
+
+//
+// Implement Sealed trait
+//
+impl Sealed for DistributionConfig {}
+
+//
+// Implement IsInitialized trait
+//
+impl IsInitialized for DistributionConfig {
+  fn is_initialized(&self) -> bool {
+    self.is_initialized
+  }
+}
+
+//
+// Implement Pack trait
+//
+impl Pack for DistributionConfig {
+  // Fixed length
+  const LEN: usize = 306;
+  // Unpack data from [u8] to the data struct
+  fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+    msg!("Read distribution config data");
+    let src = array_ref![src, 0, 306];
+    let (authority, src_children, src_weights_bps, count, is_initialized) =
+      array_refs![src, 32, 256, 16, 1, 1];
+    let mut children = [Pubkey::default(); MAX_DISTRIBUTION_CHILDREN];
+    for (child, key) in children.iter_mut().zip(src_children.chunks(32)) {
+      *child = Pubkey::new(key);
+    }
+    let mut weights_bps = [0; MAX_DISTRIBUTION_CHILDREN];
+    for (weight, bytes) in weights_bps.iter_mut().zip(src_weights_bps.chunks(2)) {
+      *weight = u16::from_le_bytes([bytes[0], bytes[1]]);
+    }
+    Ok(DistributionConfig {
+      authority: Pubkey::new_from_array(*authority),
+      children,
+      weights_bps,
+      count: count[0],
+      is_initialized: match is_initialized {
+        [0] => false,
+        [1] => true,
+        _ => return Err(ProgramError::InvalidAccountData),
+      },
+    })
+  }
+  // Pack data from the data struct to [u8]
+  fn pack_into_slice(&self, dst: &mut [u8]) {
+    msg!("Write distribution config data");
+    let dst = array_mut_ref![dst, 0, 306];
+    let (dst_authority, dst_children, dst_weights_bps, dst_count, dst_is_initialized) =
+      mut_array_refs![dst, 32, 256, 16, 1, 1];
+    let &DistributionConfig {
+      ref authority,
+      ref children,
+      ref weights_bps,
+      count,
+      is_initialized,
+    } = self;
+    dst_authority.copy_from_slice(authority.as_ref());
+    for (child, key) in children.iter().zip(dst_children.chunks_mut(32)) {
+      key.copy_from_slice(child.as_ref());
+    }
+    for (weight, bytes) in weights_bps.iter().zip(dst_weights_bps.chunks_mut(2)) {
+      bytes.copy_from_slice(&weight.to_le_bytes());
+    }
+    *dst_count = [count];
+    *dst_is_initialized = [is_initialized as u8];
+  }
+}
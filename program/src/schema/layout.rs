@@ -0,0 +1,514 @@
+///
+/// Offset, size and type of a packed field, for client decoders
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldLayout {
+  pub name: &'static str,
+  pub offset: usize,
+  pub size: usize,
+  pub ty: &'static str,
+}
+
+///
+/// Fields of StakePool in packed order
+///
+pub const STAKE_POOL_LAYOUT: &[FieldLayout] = &[
+  FieldLayout {
+    name: "owner",
+    offset: 0,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "state",
+    offset: 32,
+    size: 1,
+    ty: "u8",
+  },
+  FieldLayout {
+    name: "genesis_timestamp",
+    offset: 33,
+    size: 8,
+    ty: "i64",
+  },
+  FieldLayout {
+    name: "total_shares",
+    offset: 41,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "mint_share",
+    offset: 49,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "mint_token",
+    offset: 81,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "treasury_token",
+    offset: 113,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "reward",
+    offset: 145,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "period",
+    offset: 153,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "compensation",
+    offset: 161,
+    size: 16,
+    ty: "i128",
+  },
+  FieldLayout {
+    name: "mint_sen",
+    offset: 177,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "treasury_sen",
+    offset: 209,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "max_crank_amount",
+    offset: 241,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "crank_interval",
+    offset: 249,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "last_crank_timestamp",
+    offset: 257,
+    size: 8,
+    ty: "i64",
+  },
+  FieldLayout {
+    name: "last_state_change",
+    offset: 265,
+    size: 8,
+    ty: "i64",
+  },
+  FieldLayout {
+    name: "total_debt",
+    offset: 273,
+    size: 16,
+    ty: "u128",
+  },
+  FieldLayout {
+    name: "min_reward_per_share",
+    offset: 289,
+    size: 16,
+    ty: "u128",
+  },
+  FieldLayout {
+    name: "max_unseed_bps_per_day",
+    offset: 305,
+    size: 2,
+    ty: "u16",
+  },
+  FieldLayout {
+    name: "unseed_window_start",
+    offset: 307,
+    size: 8,
+    ty: "i64",
+  },
+  FieldLayout {
+    name: "unseeded_in_window",
+    offset: 315,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "unseed_destination",
+    offset: 323,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "min_harvest_interval",
+    offset: 355,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "allow_pre_stake",
+    offset: 363,
+    size: 1,
+    ty: "bool",
+  },
+  FieldLayout {
+    name: "version",
+    offset: 364,
+    size: 1,
+    ty: "u8",
+  },
+  FieldLayout {
+    name: "acc_reward_per_share",
+    offset: 365,
+    size: 16,
+    ty: "u128",
+  },
+  FieldLayout {
+    name: "last_update_delay",
+    offset: 381,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "treasurer",
+    offset: 389,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "cumulative_staked",
+    offset: 421,
+    size: 16,
+    ty: "u128",
+  },
+  FieldLayout {
+    name: "cumulative_unstaked",
+    offset: 437,
+    size: 16,
+    ty: "u128",
+  },
+  FieldLayout {
+    name: "activation_timestamp",
+    offset: 453,
+    size: 8,
+    ty: "i64",
+  },
+  FieldLayout {
+    name: "max_accrual_periods",
+    offset: 461,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "crank_tip",
+    offset: 469,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "min_client_version",
+    offset: 477,
+    size: 1,
+    ty: "u8",
+  },
+  FieldLayout {
+    name: "vesting_duration",
+    offset: 478,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "total_vesting",
+    offset: 486,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "admin_action_count",
+    offset: 494,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "precision_exp",
+    offset: 502,
+    size: 1,
+    ty: "u8",
+  },
+  FieldLayout {
+    name: "withdraw_only",
+    offset: 503,
+    size: 1,
+    ty: "bool",
+  },
+  FieldLayout {
+    name: "last_admin_action",
+    offset: 504,
+    size: 8,
+    ty: "i64",
+  },
+  FieldLayout {
+    name: "unseed_window_balance",
+    offset: 512,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "pending_shares",
+    offset: 520,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "departed_shares",
+    offset: 528,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "reserved",
+    offset: 536,
+    size: 104,
+    ty: "bytes",
+  },
+];
+
+///
+/// Fields of Debt in packed order
+///
+pub const DEBT_LAYOUT: &[FieldLayout] = &[
+  FieldLayout {
+    name: "stake_pool",
+    offset: 0,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "owner",
+    offset: 32,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "account",
+    offset: 64,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "debt",
+    offset: 96,
+    size: 16,
+    ty: "u128",
+  },
+  FieldLayout {
+    name: "is_initialized",
+    offset: 112,
+    size: 1,
+    ty: "bool",
+  },
+  FieldLayout {
+    name: "last_delay",
+    offset: 113,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "forfeit",
+    offset: 121,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "last_harvest_timestamp",
+    offset: 129,
+    size: 8,
+    ty: "i64",
+  },
+  FieldLayout {
+    name: "last_settled_delay",
+    offset: 137,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "default_sen_account",
+    offset: 145,
+    size: 32,
+    ty: "pubkey",
+  },
+  FieldLayout {
+    name: "vesting_amount",
+    offset: 177,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "vesting_start",
+    offset: 185,
+    size: 8,
+    ty: "i64",
+  },
+  FieldLayout {
+    name: "vesting_duration",
+    offset: 193,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "vested_amount",
+    offset: 201,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "idempotency_keys",
+    offset: 209,
+    size: 64,
+    ty: "bytes",
+  },
+  FieldLayout {
+    name: "idempotency_cursor",
+    offset: 273,
+    size: 1,
+    ty: "u8",
+  },
+  FieldLayout {
+    name: "pending_shares",
+    offset: 274,
+    size: 8,
+    ty: "u64",
+  },
+  FieldLayout {
+    name: "reserved",
+    offset: 282,
+    size: 56,
+    ty: "bytes",
+  },
+];
+
+///
+/// Bytes covered by a layout, its fields must be contiguous
+///
+pub fn packed_len(layout: &[FieldLayout]) -> Option<usize> {
+  layout.iter().try_fold(0, |offset, field| {
+    if field.offset == offset {
+      Some(offset + field.size)
+    } else {
+      None
+    }
+  })
+}
+
+///
+/// Compact schema, NAME:LEN then name@offset+size:type per field
+///
+pub fn schema(name: &str, len: usize, layout: &[FieldLayout]) -> String {
+  let fields: Vec<String> = layout
+    .iter()
+    .map(|field| {
+      format!(
+        "{}@{}+{}:{}",
+        field.name, field.offset, field.size, field.ty
+      )
+    })
+    .collect();
+  format!("{}:{}:{}", name, len, fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::schema::{debt::Debt, stake_pool::StakePool};
+  use solana_program::{program_pack::Pack, pubkey::Pubkey};
+
+  // Partitions of the array_refs in StakePool and Debt unpacking
+  const STAKE_POOL_PARTITIONS: &[usize] = &[
+    32, 1, 8, 8, 32, 32, 32, 8, 8, 16, 32, 32, 8, 8, 8, 8, 16, 16, 2, 8, 8, 32, 8, 1, 1, 16, 8, 32,
+    16, 16, 8, 8, 8, 1, 8, 8, 8, 1, 1, 8, 8, 8, 8, 104,
+  ];
+  const DEBT_PARTITIONS: &[usize] = &[32, 32, 32, 16, 1, 8, 8, 8, 8, 32, 8, 8, 8, 8, 64, 1, 8, 56];
+
+  fn field<'a>(layout: &[FieldLayout], name: &str, data: &'a [u8]) -> &'a [u8] {
+    let field = layout.iter().find(|field| field.name == name).unwrap();
+    &data[field.offset..field.offset + field.size]
+  }
+
+  #[test]
+  fn test_layouts_cover_the_packed_lengths() {
+    assert_eq!(packed_len(STAKE_POOL_LAYOUT), Some(StakePool::LEN));
+    assert_eq!(packed_len(DEBT_LAYOUT), Some(Debt::LEN));
+    let mut gapped = STAKE_POOL_LAYOUT.to_vec();
+    gapped[1].offset += 1;
+    assert_eq!(packed_len(&gapped), None);
+  }
+
+  #[test]
+  fn test_layouts_match_the_array_refs_partitions() {
+    for (layout, partitions) in [
+      (STAKE_POOL_LAYOUT, STAKE_POOL_PARTITIONS),
+      (DEBT_LAYOUT, DEBT_PARTITIONS),
+    ]
+    .iter()
+    {
+      let sizes: Vec<usize> = layout.iter().map(|field| field.size).collect();
+      assert_eq!(sizes, partitions.to_vec());
+    }
+  }
+
+  #[test]
+  fn test_offsets_locate_the_packed_fields() {
+    let stake_pool_data = StakePool {
+      owner: Pubkey::new_unique(),
+      genesis_timestamp: -5,
+      reward: 7,
+      compensation: -9,
+      treasurer: Pubkey::new_unique(),
+      withdraw_only: true,
+      departed_shares: 11,
+      ..StakePool::default()
+    };
+    let mut data = vec![0; StakePool::LEN];
+    StakePool::pack(stake_pool_data, &mut data).unwrap();
+    let read = |name| field(STAKE_POOL_LAYOUT, name, &data);
+    assert_eq!(read("owner"), stake_pool_data.owner.as_ref());
+    assert_eq!(read("genesis_timestamp"), (-5i64).to_le_bytes());
+    assert_eq!(read("reward"), 7u64.to_le_bytes());
+    assert_eq!(read("compensation"), (-9i128).to_le_bytes());
+    assert_eq!(read("treasurer"), stake_pool_data.treasurer.as_ref());
+    assert_eq!(read("withdraw_only"), [1]);
+    assert_eq!(read("departed_shares"), 11u64.to_le_bytes());
+
+    let debt_data = Debt {
+      owner: Pubkey::new_unique(),
+      debt: 13,
+      is_initialized: true,
+      idempotency_cursor: 2,
+      pending_shares: 17,
+      ..Debt::default()
+    };
+    let mut data = vec![0; Debt::LEN];
+    Debt::pack(debt_data, &mut data).unwrap();
+    let read = |name| field(DEBT_LAYOUT, name, &data);
+    assert_eq!(read("owner"), debt_data.owner.as_ref());
+    assert_eq!(read("debt"), 13u128.to_le_bytes());
+    assert_eq!(read("is_initialized"), [1]);
+    assert_eq!(read("idempotency_cursor"), [2]);
+    assert_eq!(read("pending_shares"), 17u64.to_le_bytes());
+  }
+
+  #[test]
+  fn test_schema_lists_each_field() {
+    let layout = &DEBT_LAYOUT[..2];
+    assert_eq!(
+      schema("DEBT", Debt::LEN, layout),
+      "DEBT:338:stake_pool@0+32:pubkey,owner@32+32:pubkey"
+    );
+  }
+}
@@ -107,3 +107,61 @@ fn unpack_coption_key(src: &[u8; 36]) -> Result<COption<Pubkey>, ProgramError> {
     _ => Err(ProgramError::InvalidAccountData),
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::from_hex;
+
+  // Packed by spl_token::state::Mint 3.5.0
+  const SPL_MINT: &str = "\
+    0100000001010101010101010101010101010101010101010101010101010101\
+    0101010108070605040302010901000000000000000000000000000000000000\
+    000000000000000000000000000000000000";
+  const SPL_MINT_FREEZABLE: &str = "\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    000000002a000000000000000001010000000606060606060606060606060606\
+    060606060606060606060606060606060606";
+
+  fn round_trip(hex: &str, mint: Mint) {
+    let bytes = from_hex(hex);
+    assert_eq!(Mint::unpack(&bytes).unwrap(), mint);
+    let mut packed = vec![0; Mint::LEN];
+    Mint::pack(mint, &mut packed).unwrap();
+    assert_eq!(packed, bytes);
+  }
+
+  #[test]
+  fn test_mint_matches_the_spl_token_layout() {
+    round_trip(
+      SPL_MINT,
+      Mint {
+        mint_authority: COption::Some(Pubkey::new_from_array([1; 32])),
+        supply: 0x0102030405060708,
+        decimals: 9,
+        is_initialized: true,
+        freeze_authority: COption::None,
+      },
+    );
+    round_trip(
+      SPL_MINT_FREEZABLE,
+      Mint {
+        mint_authority: COption::None,
+        supply: 42,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::Some(Pubkey::new_from_array([6; 32])),
+      },
+    );
+  }
+
+  #[test]
+  fn test_mint_rejects_an_invalid_option_tag() {
+    let mut bytes = from_hex(SPL_MINT);
+    bytes[0] = 2;
+    assert_eq!(
+      Mint::unpack(&bytes).unwrap_err(),
+      ProgramError::InvalidAccountData
+    );
+  }
+}
@@ -1,4 +1,9 @@
 pub mod account;
+pub mod contribution;
+pub mod council;
 pub mod debt;
+pub mod distribution;
+pub mod layout;
 pub mod mint;
+pub mod registry;
 pub mod stake_pool;
@@ -0,0 +1,235 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+  msg,
+  program_error::ProgramError,
+  program_pack::{IsInitialized, Pack, Sealed},
+  pubkey::Pubkey,
+};
+
+///
+/// Number of stake pools listed per registry page
+///
+pub const REGISTRY_PAGE_SIZE: usize = 100;
+
+///
+/// Number of stake mints a registry can allow
+///
+pub const MAX_ALLOWED_MINTS: usize = 100;
+
+//
+// Define the data struct
+//
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Registry {
+  pub authority: Pubkey,
+  pub total_pools: u64, // Including delisted pools
+  pub is_initialized: bool,
+}
+
+impl Registry {
+  // Page and slot of the next listed pool
+  pub fn next_slot(&self) -> (u64, usize) {
+    let page = self.total_pools / REGISTRY_PAGE_SIZE as u64;
+    let slot = (self.total_pools % REGISTRY_PAGE_SIZE as u64) as usize;
+    (page, slot)
+  }
+}
+
+//
+// Implement Sealed trait
+//
+impl Sealed for Registry {}
+
+//
+// Implement IsInitialized trait
+//
+impl IsInitialized for Registry {
+  fn is_initialized(&self) -> bool {
+    self.is_initialized
+  }
+}
+
+//
+// Implement Pack trait
+//
+impl Pack for Registry {
+  // Fixed length
+  const LEN: usize = 41;
+  // Unpack data from [u8] to the data struct
+  fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+    msg!("Read registry data");
+    let src = array_ref![src, 0, 41];
+    let (authority, total_pools, is_initialized) = array_refs![src, 32, 8, 1];
+    Ok(Registry {
+      authority: Pubkey::new_from_array(*authority),
+      total_pools: u64::from_le_bytes(*total_pools),
+      is_initialized: match is_initialized {
+        [0] => false,
+        [1] => true,
+        _ => return Err(ProgramError::InvalidAccountData),
+      },
+    })
+  }
+  // Pack data from the data struct to [u8]
+  fn pack_into_slice(&self, dst: &mut [u8]) {
+    msg!("Write registry data");
+    let dst = array_mut_ref![dst, 0, 41];
+    let (dst_authority, dst_total_pools, dst_is_initialized) = mut_array_refs![dst, 32, 8, 1];
+    let &Registry {
+      ref authority,
+      total_pools,
+      is_initialized,
+    } = self;
+    dst_authority.copy_from_slice(authority.as_ref());
+    *dst_total_pools = total_pools.to_le_bytes();
+    *dst_is_initialized = [is_initialized as u8];
+  }
+}
+
+//
+// Define the data struct
+// Delisted pools are tombstoned with the default pubkey
+//
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RegistryPage {
+  pub registry: Pubkey,
+  pub index: u64,
+  pub pools: [Pubkey; REGISTRY_PAGE_SIZE],
+  pub is_initialized: bool,
+}
+
+//
+// Implement Sealed trait
+//
+impl Sealed for RegistryPage {}
+
+//
+// Implement IsInitialized trait
+//
+impl IsInitialized for RegistryPage {
+  fn is_initialized(&self) -> bool {
+    self.is_initialized
+  }
+}
+
+//
+// Implement Pack trait
+//
+impl Pack for RegistryPage {
+  // Fixed length
+  const LEN: usize = 3241;
+  // Unpack data from [u8] to the data struct
+  fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+    msg!("Read registry page data");
+    let src = array_ref![src, 0, 3241];
+    let (registry, index, src_pools, is_initialized) = array_refs![src, 32, 8, 3200, 1];
+    let mut pools = [Pubkey::default(); REGISTRY_PAGE_SIZE];
+    for (pool, key) in pools.iter_mut().zip(src_pools.chunks(32)) {
+      *pool = Pubkey::new(key);
+    }
+    Ok(RegistryPage {
+      registry: Pubkey::new_from_array(*registry),
+      index: u64::from_le_bytes(*index),
+      pools,
+      is_initialized: match is_initialized {
+        [0] => false,
+        [1] => true,
+        _ => return Err(ProgramError::InvalidAccountData),
+      },
+    })
+  }
+  // Pack data from the data struct to [u8]
+  fn pack_into_slice(&self, dst: &mut [u8]) {
+    msg!("Write registry page data");
+    let dst = array_mut_ref![dst, 0, 3241];
+    let (dst_registry, dst_index, dst_pools, dst_is_initialized) =
+      mut_array_refs![dst, 32, 8, 3200, 1];
+    let &RegistryPage {
+      ref registry,
+      index,
+      ref pools,
+      is_initialized,
+    } = self;
+    dst_registry.copy_from_slice(registry.as_ref());
+    *dst_index = index.to_le_bytes();
+    for (pool, key) in pools.iter().zip(dst_pools.chunks_mut(32)) {
+      key.copy_from_slice(pool.as_ref());
+    }
+    *dst_is_initialized = [is_initialized as u8];
+  }
+}
+
+//
+// Define the data struct
+// Disallowed mints are tombstoned with the default pubkey
+//
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MintWhitelist {
+  pub registry: Pubkey,
+  pub mints: [Pubkey; MAX_ALLOWED_MINTS],
+  pub is_initialized: bool,
+}
+
+impl MintWhitelist {
+  // A default mint would match every tombstone
+  pub fn is_allowed(&self, mint: &Pubkey) -> bool {
+    *mint != Pubkey::default() && self.mints.contains(mint)
+  }
+}
+
+//
+// Implement Sealed trait
+//
+impl Sealed for MintWhitelist {}
+
+//
+// Implement IsInitialized trait
+//
+impl IsInitialized for MintWhitelist {
+  fn is_initialized(&self) -> bool {
+    self.is_initialized
+  }
+}
+
+//
+// Implement Pack trait
+//
+impl Pack for MintWhitelist {
+  // Fixed length
+  const LEN: usize = 3233;
+  // Unpack data from [u8] to the data struct
+  fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+    msg!("Read mint whitelist data");
+    let src = array_ref![src, 0, 3233];
+    let (registry, src_mints, is_initialized) = array_refs![src, 32, 3200, 1];
+    let mut mints = [Pubkey::default(); MAX_ALLOWED_MINTS];
+    for (mint, key) in mints.iter_mut().zip(src_mints.chunks(32)) {
+      *mint = Pubkey::new(key);
+    }
+    Ok(MintWhitelist {
+      registry: Pubkey::new_from_array(*registry),
+      mints,
+      is_initialized: match is_initialized {
+        [0] => false,
+        [1] => true,
+        _ => return Err(ProgramError::InvalidAccountData),
+      },
+    })
+  }
+  // Pack data from the data struct to [u8]
+  fn pack_into_slice(&self, dst: &mut [u8]) {
+    msg!("Write mint whitelist data");
+    let dst = array_mut_ref![dst, 0, 3233];
+    let (dst_registry, dst_mints, dst_is_initialized) = mut_array_refs![dst, 32, 3200, 1];
+    let &MintWhitelist {
+      ref registry,
+      ref mints,
+      is_initialized,
+    } = self;
+    dst_registry.copy_from_slice(registry.as_ref());
+    for (mint, key) in mints.iter().zip(dst_mints.chunks_mut(32)) {
+      key.copy_from_slice(mint.as_ref());
+    }
+    *dst_is_initialized = [is_initialized as u8];
+  }
+}
@@ -1,3 +1,5 @@
+use crate::error::AppError;
+use crate::{MAX_PRECISION_EXP, PRECISION, PRECISION_EXP};
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use num_enum::TryFromPrimitive;
 use solana_program::{
@@ -7,6 +9,41 @@ use solana_program::{
   pubkey::Pubkey,
 };
 
+///
+/// Minimum seconds between two state changes (freeze/thaw)
+///
+pub const STATE_CHANGE_INTERVAL: u64 = 3600;
+
+///
+/// Length of the rolling window capping unseed
+///
+pub const UNSEED_WINDOW: u64 = 86400;
+
+///
+/// Maximum seconds between pool creation and a scheduled genesis
+///
+pub const MAX_GENESIS_DELAY: u64 = 2592000;
+
+///
+/// Idle seconds after the last owner action from which an emptied pool may be collected
+///
+pub const GC_IDLE_INTERVAL: u64 = 7776000;
+
+///
+/// Share of the reclaimed rent paid to whoever collects a pool
+///
+pub const GC_INCENTIVE_BPS: u64 = 1000;
+
+///
+/// Pools from this version settle with the reward accumulator
+///
+pub const ACCUMULATOR_VERSION: u8 = 1;
+
+///
+/// Trailing bytes kept zero for future fields, so that adding one does not grow LEN
+///
+pub const RESERVED_LEN: usize = 104;
+
 ///
 /// StakePool state
 ///
@@ -40,9 +77,41 @@ pub struct StakePool {
 
   pub reward: u64,          // units: SEN / (share * seconds)
   pub period: u64,          // seconds
-  pub compensation: i128,   // units: SEN / share, with 1e18 precision
+  pub compensation: i128,   // units: SEN / share, scaled by the precision
   pub mint_sen: Pubkey,     // Mint SEN
   pub treasury_sen: Pubkey, // Treasury SEN
+
+  pub max_crank_amount: u64,       // units: SEN
+  pub crank_interval: u64,         // seconds
+  pub last_crank_timestamp: i64,   // seconds
+  pub last_state_change: i64,      // seconds
+  pub total_debt: u128,            // units: SEN
+  pub min_reward_per_share: u128,  // Floor of the fractional reward, scaled by the precision
+  pub max_unseed_bps_per_day: u16, // 0 means unlimited
+  pub unseed_window_start: i64,    // seconds
+  pub unseeded_in_window: u64,     // units: SEN
+  pub unseed_destination: Pubkey,  // Default means unrestricted
+  pub min_harvest_interval: u64,   // seconds
+  pub allow_pre_stake: bool,       // Stake before genesis
+  pub version: u8,                 // Settlement model
+  pub acc_reward_per_share: u128,  // units: SEN / share, scaled by the precision
+  pub last_update_delay: u64,      // periods
+  pub treasurer: Pubkey,           // Signs for the treasuries
+  pub cumulative_staked: u128,     // Staked tokens ever
+  pub cumulative_unstaked: u128,   // Unstaked tokens ever
+  pub activation_timestamp: i64,   // Stakes open from, zero means live
+  pub max_accrual_periods: u64,    // Claimable periods per position, zero means unbounded
+  pub crank_tip: u64,              // SEN paid to a cranker that advanced the state
+  pub min_client_version: u8,      // Oldest client accepted on user instructions, zero accepts all
+  pub vesting_duration: u64,       // seconds, zero pays harvests at once
+  pub total_vesting: u64,          // units: SEN, held for the vesting schedules
+  pub admin_action_count: u64,     // Owner actions recorded, one event each
+  pub precision_exp: u8,           // Exponent of the precision, zero reads as 18
+  pub withdraw_only: bool,         // Stakes blocked until the treasury covers a period again
+  pub last_admin_action: i64,      // seconds, owner actions keep the pool from collection
+  pub unseed_window_balance: u64,  // units: SEN, treasury balance when the unseed window opened
+  pub pending_shares: u64,         // Staked during the in-progress period, accruing from its end
+  pub departed_shares: u64,        // Staked before the in-progress period, unstaked during it
 }
 
 ///
@@ -53,6 +122,51 @@ impl StakePool {
   pub fn is_frozen(&self) -> bool {
     self.state == StakePoolState::Frozen
   }
+  // Timestamp at which the period after the given delay is credited
+  pub fn next_accrual_timestamp(&self, delay: u64) -> Option<i64> {
+    let elapsed = delay.checked_add(1)?.checked_mul(self.period)?;
+    self.genesis_timestamp.checked_add(elapsed as i64)
+  }
+  // Settles with the reward accumulator rather than the compensation
+  pub fn is_accumulator(&self) -> bool {
+    self.version >= ACCUMULATOR_VERSION
+  }
+  // Scaling factor of the fractional rewards of this pool
+  pub fn precision(&self) -> u128 {
+    match self.precision_exp {
+      0 => PRECISION,
+      exp => 10u128.pow(exp as u32),
+    }
+  }
+  // Internal consistency of a stake pool about to be persisted
+  pub fn validate(&self) -> Result<(), AppError> {
+    if !self.is_initialized() {
+      return Err(AppError::InconsistentState);
+    }
+    if self.reward == 0 || self.period == 0 {
+      return Err(AppError::InconsistentState);
+    }
+    if self.total_shares != 0 && self.mint_share == Pubkey::default() {
+      return Err(AppError::InconsistentState);
+    }
+    if self.max_unseed_bps_per_day > 10000 {
+      return Err(AppError::InconsistentState);
+    }
+    if self.precision_exp != 0
+      && (self.precision_exp < PRECISION_EXP || self.precision_exp > MAX_PRECISION_EXP)
+    {
+      return Err(AppError::InconsistentState);
+    }
+    // The floor cannot pay a single share more than the whole reward
+    if self.min_reward_per_share / self.precision() > self.reward as u128 {
+      return Err(AppError::InconsistentState);
+    }
+    // Legacy pools never move the accumulator
+    if !self.is_accumulator() && (self.acc_reward_per_share != 0 || self.last_update_delay != 0) {
+      return Err(AppError::InconsistentState);
+    }
+    Ok(())
+  }
 }
 
 //
@@ -74,11 +188,15 @@ impl IsInitialized for StakePool {
 //
 impl Pack for StakePool {
   // Fixed length
-  const LEN: usize = 241;
+  const LEN: usize = 640;
   // Unpack data from [u8] to the data struct
   fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
     msg!("Read stake pool data");
-    let src = array_ref![src, 0, 241];
+    // Accounts of a smaller legacy layout must not panic on indexing
+    if src.len() < Self::LEN {
+      return Err(ProgramError::AccountDataTooSmall);
+    }
+    let src = array_ref![src, 0, 640];
     let (
       owner,
       state,
@@ -92,7 +210,42 @@ impl Pack for StakePool {
       compensation,
       mint_sen,
       treasury_sen,
-    ) = array_refs![src, 32, 1, 8, 8, 32, 32, 32, 8, 8, 16, 32, 32];
+      max_crank_amount,
+      crank_interval,
+      last_crank_timestamp,
+      last_state_change,
+      total_debt,
+      min_reward_per_share,
+      max_unseed_bps_per_day,
+      unseed_window_start,
+      unseeded_in_window,
+      unseed_destination,
+      min_harvest_interval,
+      allow_pre_stake,
+      version,
+      acc_reward_per_share,
+      last_update_delay,
+      treasurer,
+      cumulative_staked,
+      cumulative_unstaked,
+      activation_timestamp,
+      max_accrual_periods,
+      crank_tip,
+      min_client_version,
+      vesting_duration,
+      total_vesting,
+      admin_action_count,
+      precision_exp,
+      withdraw_only,
+      last_admin_action,
+      unseed_window_balance,
+      pending_shares,
+      departed_shares,
+      _reserved,
+    ) = array_refs![
+      src, 32, 1, 8, 8, 32, 32, 32, 8, 8, 16, 32, 32, 8, 8, 8, 8, 16, 16, 2, 8, 8, 32, 8, 1, 1, 16,
+      8, 32, 16, 16, 8, 8, 8, 1, 8, 8, 8, 1, 1, 8, 8, 8, 8, 104
+    ];
     Ok(StakePool {
       owner: Pubkey::new_from_array(*owner),
       state: StakePoolState::try_from_primitive(state[0])
@@ -110,12 +263,52 @@ impl Pack for StakePool {
       compensation: i128::from_le_bytes(*compensation),
       mint_sen: Pubkey::new_from_array(*mint_sen),
       treasury_sen: Pubkey::new_from_array(*treasury_sen),
+
+      max_crank_amount: u64::from_le_bytes(*max_crank_amount),
+      crank_interval: u64::from_le_bytes(*crank_interval),
+      last_crank_timestamp: i64::from_le_bytes(*last_crank_timestamp),
+      last_state_change: i64::from_le_bytes(*last_state_change),
+      total_debt: u128::from_le_bytes(*total_debt),
+      min_reward_per_share: u128::from_le_bytes(*min_reward_per_share),
+      max_unseed_bps_per_day: u16::from_le_bytes(*max_unseed_bps_per_day),
+      unseed_window_start: i64::from_le_bytes(*unseed_window_start),
+      unseeded_in_window: u64::from_le_bytes(*unseeded_in_window),
+      unseed_destination: Pubkey::new_from_array(*unseed_destination),
+      min_harvest_interval: u64::from_le_bytes(*min_harvest_interval),
+      allow_pre_stake: match allow_pre_stake {
+        [0] => false,
+        [1] => true,
+        _ => return Err(ProgramError::InvalidAccountData),
+      },
+      version: version[0],
+      acc_reward_per_share: u128::from_le_bytes(*acc_reward_per_share),
+      last_update_delay: u64::from_le_bytes(*last_update_delay),
+      treasurer: Pubkey::new_from_array(*treasurer),
+      cumulative_staked: u128::from_le_bytes(*cumulative_staked),
+      cumulative_unstaked: u128::from_le_bytes(*cumulative_unstaked),
+      activation_timestamp: i64::from_le_bytes(*activation_timestamp),
+      max_accrual_periods: u64::from_le_bytes(*max_accrual_periods),
+      crank_tip: u64::from_le_bytes(*crank_tip),
+      min_client_version: min_client_version[0],
+      vesting_duration: u64::from_le_bytes(*vesting_duration),
+      total_vesting: u64::from_le_bytes(*total_vesting),
+      admin_action_count: u64::from_le_bytes(*admin_action_count),
+      precision_exp: precision_exp[0],
+      withdraw_only: match withdraw_only {
+        [0] => false,
+        [1] => true,
+        _ => return Err(ProgramError::InvalidAccountData),
+      },
+      last_admin_action: i64::from_le_bytes(*last_admin_action),
+      unseed_window_balance: u64::from_le_bytes(*unseed_window_balance),
+      pending_shares: u64::from_le_bytes(*pending_shares),
+      departed_shares: u64::from_le_bytes(*departed_shares),
     })
   }
   // Pack data from the data struct to [u8]
   fn pack_into_slice(&self, dst: &mut [u8]) {
     msg!("Write stake pool data");
-    let dst = array_mut_ref![dst, 0, 241];
+    let dst = array_mut_ref![dst, 0, 640];
     let (
       dst_owner,
       dst_state,
@@ -129,7 +322,42 @@ impl Pack for StakePool {
       dst_compensation,
       dst_mint_sen,
       dst_treasury_sen,
-    ) = mut_array_refs![dst, 32, 1, 8, 8, 32, 32, 32, 8, 8, 16, 32, 32];
+      dst_max_crank_amount,
+      dst_crank_interval,
+      dst_last_crank_timestamp,
+      dst_last_state_change,
+      dst_total_debt,
+      dst_min_reward_per_share,
+      dst_max_unseed_bps_per_day,
+      dst_unseed_window_start,
+      dst_unseeded_in_window,
+      dst_unseed_destination,
+      dst_min_harvest_interval,
+      dst_allow_pre_stake,
+      dst_version,
+      dst_acc_reward_per_share,
+      dst_last_update_delay,
+      dst_treasurer,
+      dst_cumulative_staked,
+      dst_cumulative_unstaked,
+      dst_activation_timestamp,
+      dst_max_accrual_periods,
+      dst_crank_tip,
+      dst_min_client_version,
+      dst_vesting_duration,
+      dst_total_vesting,
+      dst_admin_action_count,
+      dst_precision_exp,
+      dst_withdraw_only,
+      dst_last_admin_action,
+      dst_unseed_window_balance,
+      dst_pending_shares,
+      dst_departed_shares,
+      dst_reserved,
+    ) = mut_array_refs![
+      dst, 32, 1, 8, 8, 32, 32, 32, 8, 8, 16, 32, 32, 8, 8, 8, 8, 16, 16, 2, 8, 8, 32, 8, 1, 1, 16,
+      8, 32, 16, 16, 8, 8, 8, 1, 8, 8, 8, 1, 1, 8, 8, 8, 8, 104
+    ];
     let &StakePool {
       ref owner,
       state,
@@ -143,6 +371,37 @@ impl Pack for StakePool {
       compensation,
       ref mint_sen,
       ref treasury_sen,
+      max_crank_amount,
+      crank_interval,
+      last_crank_timestamp,
+      last_state_change,
+      total_debt,
+      min_reward_per_share,
+      max_unseed_bps_per_day,
+      unseed_window_start,
+      unseeded_in_window,
+      ref unseed_destination,
+      min_harvest_interval,
+      allow_pre_stake,
+      version,
+      acc_reward_per_share,
+      last_update_delay,
+      ref treasurer,
+      cumulative_staked,
+      cumulative_unstaked,
+      activation_timestamp,
+      max_accrual_periods,
+      crank_tip,
+      min_client_version,
+      vesting_duration,
+      total_vesting,
+      admin_action_count,
+      precision_exp,
+      withdraw_only,
+      last_admin_action,
+      unseed_window_balance,
+      pending_shares,
+      departed_shares,
     } = self;
     dst_owner.copy_from_slice(owner.as_ref());
     *dst_state = [state as u8];
@@ -156,5 +415,120 @@ impl Pack for StakePool {
     *dst_compensation = compensation.to_le_bytes();
     dst_mint_sen.copy_from_slice(mint_sen.as_ref());
     dst_treasury_sen.copy_from_slice(treasury_sen.as_ref());
+    *dst_max_crank_amount = max_crank_amount.to_le_bytes();
+    *dst_crank_interval = crank_interval.to_le_bytes();
+    *dst_last_crank_timestamp = last_crank_timestamp.to_le_bytes();
+    *dst_last_state_change = last_state_change.to_le_bytes();
+    *dst_total_debt = total_debt.to_le_bytes();
+    *dst_min_reward_per_share = min_reward_per_share.to_le_bytes();
+    *dst_max_unseed_bps_per_day = max_unseed_bps_per_day.to_le_bytes();
+    *dst_unseed_window_start = unseed_window_start.to_le_bytes();
+    *dst_unseeded_in_window = unseeded_in_window.to_le_bytes();
+    dst_unseed_destination.copy_from_slice(unseed_destination.as_ref());
+    *dst_min_harvest_interval = min_harvest_interval.to_le_bytes();
+    *dst_allow_pre_stake = [allow_pre_stake as u8];
+    *dst_version = [version];
+    *dst_acc_reward_per_share = acc_reward_per_share.to_le_bytes();
+    *dst_last_update_delay = last_update_delay.to_le_bytes();
+    dst_treasurer.copy_from_slice(treasurer.as_ref());
+    *dst_cumulative_staked = cumulative_staked.to_le_bytes();
+    *dst_cumulative_unstaked = cumulative_unstaked.to_le_bytes();
+    *dst_activation_timestamp = activation_timestamp.to_le_bytes();
+    *dst_max_accrual_periods = max_accrual_periods.to_le_bytes();
+    *dst_crank_tip = crank_tip.to_le_bytes();
+    *dst_min_client_version = [min_client_version];
+    *dst_vesting_duration = vesting_duration.to_le_bytes();
+    *dst_total_vesting = total_vesting.to_le_bytes();
+    *dst_admin_action_count = admin_action_count.to_le_bytes();
+    *dst_precision_exp = [precision_exp];
+    *dst_withdraw_only = [withdraw_only as u8];
+    *dst_last_admin_action = last_admin_action.to_le_bytes();
+    *dst_unseed_window_balance = unseed_window_balance.to_le_bytes();
+    *dst_pending_shares = pending_shares.to_le_bytes();
+    *dst_departed_shares = departed_shares.to_le_bytes();
+    *dst_reserved = [0; RESERVED_LEN];
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Named invariant and how to break it
+  type Violation = (&'static str, fn(&mut StakePool));
+
+  fn valid() -> StakePool {
+    StakePool {
+      state: StakePoolState::Initialized,
+      reward: 100,
+      period: 60,
+      total_shares: 1000,
+      mint_share: Pubkey::new_unique(),
+      version: ACCUMULATOR_VERSION,
+      acc_reward_per_share: 7,
+      ..StakePool::default()
+    }
+  }
+
+  #[test]
+  fn test_validate_accepts_a_consistent_pool() {
+    assert_eq!(valid().validate(), Ok(()));
+    let legacy = StakePool {
+      version: 0,
+      acc_reward_per_share: 0,
+      ..valid()
+    };
+    assert_eq!(legacy.validate(), Ok(()));
+  }
+
+  #[test]
+  fn test_validate_rejects_each_violated_invariant() {
+    let violations: Vec<Violation> = vec![
+      ("uninitialized", |data| {
+        data.state = StakePoolState::Uninitialized
+      }),
+      ("zero reward", |data| data.reward = 0),
+      ("zero period", |data| data.period = 0),
+      ("shares without a mint", |data| {
+        data.mint_share = Pubkey::default()
+      }),
+      ("unseed limit", |data| data.max_unseed_bps_per_day = 10001),
+      ("precision too low", |data| {
+        data.precision_exp = PRECISION_EXP - 1
+      }),
+      ("precision too high", |data| {
+        data.precision_exp = MAX_PRECISION_EXP + 1
+      }),
+      ("floor above the reward", |data| {
+        data.min_reward_per_share = 101 * PRECISION
+      }),
+      ("legacy accumulator", |data| data.version = 0),
+      ("legacy delay", |data| {
+        data.version = 0;
+        data.acc_reward_per_share = 0;
+        data.last_update_delay = 1;
+      }),
+    ];
+    for (invariant, violate) in violations {
+      let mut data = valid();
+      violate(&mut data);
+      assert_eq!(
+        data.validate(),
+        Err(AppError::InconsistentState),
+        "{}",
+        invariant
+      );
+    }
+  }
+
+  #[test]
+  fn test_unpack_of_an_undersized_buffer_is_a_clean_error() {
+    // Empty, of the first deployed layout and one byte short
+    for len in [0, 241, StakePool::LEN - 1].iter() {
+      assert_eq!(
+        StakePool::unpack_from_slice(&vec![0; *len]).unwrap_err(),
+        ProgramError::AccountDataTooSmall
+      );
+    }
   }
 }
@@ -0,0 +1,1286 @@
+use crate::context::{
+  account_specs, AccountSpec, ClaimVestedContext, HarvestContext, InitializeAccountsContext,
+  InitializeContributionContext, InitializeStakePoolContext, SeedContext, StakeContext,
+  StakePoolOwnerContext, UnseedContext, UnstakeContext, WithdrawSeedContext,
+};
+use crate::helper::pubutil::{associated_token_address, Boolean, NATIVE_MINT};
+use crate::instruction::AppInstruction;
+use crate::processor::Processor;
+use crate::schema::{
+  account::{Account, AccountState},
+  debt::Debt,
+  mint::Mint,
+  stake_pool::StakePool,
+};
+use solana_program::{
+  account_info::AccountInfo,
+  clock::Clock,
+  entrypoint::ProgramResult,
+  instruction::{AccountMeta, Instruction},
+  program_error::ProgramError,
+  program_option::COption,
+  program_pack::Pack,
+  program_stubs::{set_syscall_stubs, SyscallStubs},
+  pubkey::Pubkey,
+  rent::Rent,
+  system_instruction::SystemInstruction,
+  system_program,
+  sysvar::{self, Sysvar},
+};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Once;
+
+///
+/// Fake account
+/// Owns what an AccountInfo borrows, so handlers can run without a validator
+///
+#[derive(Clone, Debug, Default)]
+pub struct FakeAccount {
+  pub key: Pubkey,
+  pub owner: Pubkey,
+  pub lamports: u64,
+  pub data: Vec<u8>,
+  pub is_signer: bool,
+  pub is_writable: bool,
+}
+
+impl FakeAccount {
+  pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+    FakeAccount {
+      key,
+      owner,
+      lamports: 1,
+      data,
+      is_signer: false,
+      is_writable: true,
+    }
+  }
+
+  pub fn signer(mut self) -> Self {
+    self.is_signer = true;
+    self
+  }
+
+  pub fn readonly(mut self) -> Self {
+    self.is_writable = false;
+    self
+  }
+
+  pub fn info(&mut self) -> AccountInfo<'_> {
+    AccountInfo::new(
+      &self.key,
+      self.is_signer,
+      self.is_writable,
+      &mut self.lamports,
+      &mut self.data,
+      &self.owner,
+      false,
+      0,
+    )
+  }
+}
+
+fn packed<T: Pack>(value: T) -> Vec<u8> {
+  let mut data = vec![0; T::LEN];
+  T::pack(value, &mut data).unwrap();
+  data
+}
+
+pub fn fake_signer(key: Pubkey) -> FakeAccount {
+  FakeAccount::new(key, Pubkey::default(), vec![]).signer()
+}
+
+pub fn fake_stake_pool_account(key: Pubkey, program_id: Pubkey, data: StakePool) -> FakeAccount {
+  FakeAccount::new(key, program_id, packed(data))
+}
+
+pub fn fake_debt_account(key: Pubkey, program_id: Pubkey, data: Debt) -> FakeAccount {
+  FakeAccount::new(key, program_id, packed(data))
+}
+
+pub fn fake_token_account(
+  key: Pubkey,
+  splt_program_id: Pubkey,
+  mint: Pubkey,
+  owner: Pubkey,
+  amount: u64,
+) -> FakeAccount {
+  // Wrapped SOL holds its amount as lamports above the rent reserve
+  let reserve = Rent::default().minimum_balance(Account::LEN);
+  let is_native = if mint == NATIVE_MINT {
+    COption::Some(reserve)
+  } else {
+    COption::None
+  };
+  let data = Account {
+    mint,
+    owner,
+    amount,
+    state: AccountState::Initialized,
+    is_native,
+    ..Account::default()
+  };
+  let mut acc = FakeAccount::new(key, splt_program_id, packed(data));
+  if is_native.is_some() {
+    acc.lamports = reserve + amount;
+  }
+  acc
+}
+
+///
+/// Run a handler over the fake accounts in order
+///
+pub fn run_handler<F>(
+  handler: F,
+  program_id: &Pubkey,
+  accounts: &mut [FakeAccount],
+) -> ProgramResult
+where
+  F: for<'a, 'b, 'c> Fn(&'a Pubkey, &'b [AccountInfo<'c>]) -> ProgramResult,
+{
+  install_stubs();
+  PROGRAM_ID.with(|id| id.set(*program_id));
+  let infos: Vec<AccountInfo> = accounts.iter_mut().map(|acc| acc.info()).collect();
+  handler(program_id, &infos)
+}
+
+///
+/// Programs the stubs answer for
+///
+pub fn splt_program_id() -> Pubkey {
+  Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap()
+}
+
+pub fn splata_program_id() -> Pubkey {
+  Pubkey::from_str("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL").unwrap()
+}
+
+pub fn memo_program_id() -> Pubkey {
+  Pubkey::from_str("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").unwrap()
+}
+
+///
+/// Error of a CPI failed on purpose
+///
+pub const INJECTED_FAILURE: u32 = 0xdead;
+
+// The stubs are process wide, their state is per test thread
+thread_local! {
+  static CLOCK: Cell<i64> = const { Cell::new(0) };
+  static PROGRAM_ID: Cell<Pubkey> = const { Cell::new(Pubkey::new_from_array([0; 32])) };
+  static CPI_COUNT: Cell<usize> = const { Cell::new(0) };
+  static FAIL_CPI_AT: Cell<Option<usize>> = const { Cell::new(None) };
+  static ASSIGNED: RefCell<BTreeMap<Pubkey, Pubkey>> = const { RefCell::new(BTreeMap::new()) };
+  static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+static INSTALL: Once = Once::new();
+
+///
+/// Swap in the stubs, idempotent
+///
+pub fn install_stubs() {
+  INSTALL.call_once(|| {
+    set_syscall_stubs(Box::new(TestStubs {}));
+  });
+}
+
+///
+/// Mock clock
+///
+pub fn warp_to(unix_timestamp: i64) {
+  install_stubs();
+  CLOCK.with(|clock| clock.set(unix_timestamp));
+}
+
+pub fn warp(seconds: i64) {
+  warp_to(now() + seconds);
+}
+
+pub fn now() -> i64 {
+  CLOCK.with(|clock| clock.get())
+}
+
+///
+/// Fail the nth CPI from now on, counting from one, `None` disarms
+///
+pub fn fail_cpi_at(nth: Option<usize>) {
+  CPI_COUNT.with(|count| count.set(0));
+  FAIL_CPI_AT.with(|fail| fail.set(nth));
+}
+
+///
+/// CPIs issued since the last `fail_cpi_at`
+///
+pub fn cpi_count() -> usize {
+  CPI_COUNT.with(|count| count.get())
+}
+
+///
+/// Drain the logs of the current thread
+///
+pub fn take_logs() -> Vec<String> {
+  LOGS.with(|logs| logs.borrow_mut().split_off(0))
+}
+
+///
+/// Bytes of a hex string, for fixtures recorded from other programs
+///
+pub fn from_hex(hex: &str) -> Vec<u8> {
+  (0..hex.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+    .collect()
+}
+
+struct TestStubs {}
+
+impl SyscallStubs for TestStubs {
+  fn sol_log(&self, message: &str) {
+    LOGS.with(|logs| logs.borrow_mut().push(message.to_string()));
+  }
+
+  fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    let clock = Clock {
+      unix_timestamp: now(),
+      ..Clock::default()
+    };
+    unsafe { *(var_addr as *mut Clock) = clock };
+    0
+  }
+
+  fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    unsafe { *(var_addr as *mut Rent) = Rent::default() };
+    0
+  }
+
+  fn sol_invoke_signed(
+    &self,
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+  ) -> ProgramResult {
+    let count = CPI_COUNT.with(|count| {
+      count.set(count.get() + 1);
+      count.get()
+    });
+    if FAIL_CPI_AT.with(|fail| fail.get()) == Some(count) {
+      return Err(ProgramError::Custom(INJECTED_FAILURE));
+    }
+    let accounts = instruction
+      .accounts
+      .iter()
+      .map(|meta| {
+        account_infos
+          .iter()
+          .find(|info| *info.key == meta.pubkey)
+          .ok_or(ProgramError::NotEnoughAccountKeys)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    // Signed by the transaction or derived from the caller
+    let program_id = PROGRAM_ID.with(|program_id| program_id.get());
+    let is_authorized = |acc: &AccountInfo| {
+      acc.is_signer
+        || signers_seeds
+          .iter()
+          .any(|seeds| Pubkey::create_program_address(seeds, &program_id).ok() == Some(*acc.key))
+    };
+    if instruction.program_id == system_program::id() {
+      fake_system_program(&instruction.data, &accounts)
+    } else if instruction.program_id == splt_program_id() {
+      fake_splt_program(&instruction.data, &accounts, is_authorized)
+    } else if instruction.program_id == splata_program_id() {
+      fake_splata_program(&accounts)
+    } else if instruction.program_id == memo_program_id() {
+      let memo = String::from_utf8(instruction.data.clone())
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+      LOGS.with(|logs| logs.borrow_mut().push(format!("Memo: {}", memo)));
+      Ok(())
+    } else {
+      Err(ProgramError::IncorrectProgramId)
+    }
+  }
+}
+
+// Data cannot grow in place, a created account gets a fresh buffer
+fn alloc(acc: &AccountInfo, space: usize) -> ProgramResult {
+  if acc.data_len() == space {
+    return Ok(());
+  }
+  if acc.data_len() != 0 {
+    return Err(ProgramError::AccountAlreadyInitialized);
+  }
+  let data: &'static mut [u8] = Box::leak(vec![0; space].into_boxed_slice());
+  *acc.data.borrow_mut() = data;
+  Ok(())
+}
+
+// The rest of the instruction sees the new owner as on the runtime,
+// the bank also applies it to the account it keeps
+fn assign(acc: &AccountInfo, owner: Pubkey) {
+  // Every AccountInfo of the instruction points at the owner of the same fake account,
+  // written through as solana-program-test does
+  #[allow(unknown_lints, invalid_reference_casting)]
+  unsafe {
+    std::ptr::write_volatile(acc.owner as *const Pubkey as *mut Pubkey, owner)
+  };
+  ASSIGNED.with(|assigned| assigned.borrow_mut().insert(*acc.key, owner));
+}
+
+fn move_lamports(src: &AccountInfo, dst: &AccountInfo, lamports: u64) -> ProgramResult {
+  let left = src
+    .lamports()
+    .checked_sub(lamports)
+    .ok_or(ProgramError::InsufficientFunds)?;
+  **src.lamports.borrow_mut() = left;
+  **dst.lamports.borrow_mut() += lamports;
+  Ok(())
+}
+
+fn fake_system_program(data: &[u8], accounts: &[&AccountInfo]) -> ProgramResult {
+  let ix: SystemInstruction = bincode_system_instruction(data)?;
+  match ix {
+    SystemInstruction::Transfer { lamports } => {
+      if !accounts[0].is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+      }
+      move_lamports(accounts[0], accounts[1], lamports)
+    }
+    SystemInstruction::Allocate { space } => alloc(accounts[0], space as usize),
+    SystemInstruction::Assign { owner } => {
+      assign(accounts[0], owner);
+      Ok(())
+    }
+    _ => Err(ProgramError::InvalidInstructionData),
+  }
+}
+
+// The three system instructions the program issues, as bincode lays them out
+fn bincode_system_instruction(data: &[u8]) -> Result<SystemInstruction, ProgramError> {
+  let tag = data
+    .get(..4)
+    .map(|tag| u32::from_le_bytes([tag[0], tag[1], tag[2], tag[3]]))
+    .ok_or(ProgramError::InvalidInstructionData)?;
+  let u64_at = |offset: usize| {
+    data
+      .get(offset..offset + 8)
+      .map(|bytes| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        u64::from_le_bytes(buf)
+      })
+      .ok_or(ProgramError::InvalidInstructionData)
+  };
+  match tag {
+    1 => Ok(SystemInstruction::Assign {
+      owner: Pubkey::new(
+        data
+          .get(4..36)
+          .ok_or(ProgramError::InvalidInstructionData)?,
+      ),
+    }),
+    2 => Ok(SystemInstruction::Transfer {
+      lamports: u64_at(4)?,
+    }),
+    8 => Ok(SystemInstruction::Allocate { space: u64_at(4)? }),
+    _ => Err(ProgramError::InvalidInstructionData),
+  }
+}
+
+fn fake_splt_program<F>(data: &[u8], accounts: &[&AccountInfo], is_authorized: F) -> ProgramResult
+where
+  F: Fn(&AccountInfo) -> bool,
+{
+  let (&tag, rest) = data
+    .split_first()
+    .ok_or(ProgramError::InvalidInstructionData)?;
+  let amount = || {
+    rest
+      .get(..8)
+      .map(|bytes| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        u64::from_le_bytes(buf)
+      })
+      .ok_or(ProgramError::InvalidInstructionData)
+  };
+  let authorize = |acc: &AccountInfo, authority: &Pubkey| {
+    if acc.key != authority || !is_authorized(acc) {
+      return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+  };
+  match tag {
+    // InitializeMint
+    0 => {
+      let mut mint = Mint::unpack_unchecked(&accounts[0].data.borrow())?;
+      if mint.is_initialized {
+        return Err(ProgramError::AccountAlreadyInitialized);
+      }
+      mint.decimals = rest[0];
+      mint.mint_authority = COption::Some(Pubkey::new(&rest[1..33]));
+      mint.freeze_authority = match rest.get(33) {
+        Some(1) => COption::Some(Pubkey::new(&rest[34..66])),
+        _ => COption::None,
+      };
+      mint.is_initialized = true;
+      Mint::pack(mint, &mut accounts[0].data.borrow_mut())
+    }
+    // InitializeAccount
+    1 => init_token_account(accounts[0], accounts[1].key, accounts[2].key),
+    // Transfer
+    3 => {
+      let amount = amount()?;
+      let mut src = Account::unpack(&accounts[0].data.borrow())?;
+      let mut dst = Account::unpack(&accounts[1].data.borrow())?;
+      // A delegate spends from its allowance
+      match src.delegate {
+        COption::Some(delegate) if *accounts[2].key == delegate && delegate != src.owner => {
+          authorize(accounts[2], &delegate)?;
+          src.delegated_amount = src
+            .delegated_amount
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+          if src.delegated_amount == 0 {
+            src.delegate = COption::None;
+          }
+        }
+        _ => authorize(accounts[2], &src.owner)?,
+      }
+      if src.mint != dst.mint {
+        return Err(ProgramError::InvalidAccountData);
+      }
+      if src.is_frozen() || dst.is_frozen() {
+        return Err(ProgramError::InvalidAccountData);
+      }
+      src.amount = src
+        .amount
+        .checked_sub(amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+      if accounts[0].key == accounts[1].key {
+        return Ok(());
+      }
+      if src.is_native() {
+        move_lamports(accounts[0], accounts[1], amount)?;
+      }
+      dst.amount = dst
+        .amount
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+      Account::pack(src, &mut accounts[0].data.borrow_mut())?;
+      Account::pack(dst, &mut accounts[1].data.borrow_mut())
+    }
+    // SetAuthority
+    6 => {
+      let new_authority = match rest.get(1) {
+        Some(1) => COption::Some(Pubkey::new(&rest[2..34])),
+        _ => COption::None,
+      };
+      if accounts[0].data_len() == Mint::LEN {
+        let mut mint = Mint::unpack(&accounts[0].data.borrow())?;
+        match rest[0] {
+          0 => {
+            let authority = mint.mint_authority.ok_or(ProgramError::InvalidArgument)?;
+            authorize(accounts[1], &authority)?;
+            mint.mint_authority = new_authority;
+          }
+          1 => {
+            let authority = mint.freeze_authority.ok_or(ProgramError::InvalidArgument)?;
+            authorize(accounts[1], &authority)?;
+            mint.freeze_authority = new_authority;
+          }
+          _ => return Err(ProgramError::InvalidArgument),
+        }
+        Mint::pack(mint, &mut accounts[0].data.borrow_mut())
+      } else {
+        let mut account = Account::unpack(&accounts[0].data.borrow())?;
+        match rest[0] {
+          2 => {
+            authorize(accounts[1], &account.owner)?;
+            account.owner = new_authority.ok_or(ProgramError::InvalidArgument)?;
+          }
+          3 => {
+            let authority = account.close_authority.unwrap_or(account.owner);
+            authorize(accounts[1], &authority)?;
+            account.close_authority = new_authority;
+          }
+          _ => return Err(ProgramError::InvalidArgument),
+        }
+        Account::pack(account, &mut accounts[0].data.borrow_mut())
+      }
+    }
+    // MintTo
+    7 => {
+      let amount = amount()?;
+      let mut mint = Mint::unpack(&accounts[0].data.borrow())?;
+      let mut dst = Account::unpack(&accounts[1].data.borrow())?;
+      let authority = mint.mint_authority.ok_or(ProgramError::InvalidArgument)?;
+      authorize(accounts[2], &authority)?;
+      if dst.mint != *accounts[0].key {
+        return Err(ProgramError::InvalidAccountData);
+      }
+      mint.supply = mint
+        .supply
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+      dst.amount = dst
+        .amount
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+      Mint::pack(mint, &mut accounts[0].data.borrow_mut())?;
+      Account::pack(dst, &mut accounts[1].data.borrow_mut())
+    }
+    // Burn
+    8 => {
+      let amount = amount()?;
+      let mut src = Account::unpack(&accounts[0].data.borrow())?;
+      let mut mint = Mint::unpack(&accounts[1].data.borrow())?;
+      authorize(accounts[2], &src.owner)?;
+      if src.mint != *accounts[1].key {
+        return Err(ProgramError::InvalidAccountData);
+      }
+      src.amount = src
+        .amount
+        .checked_sub(amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+      mint.supply = mint
+        .supply
+        .checked_sub(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+      Account::pack(src, &mut accounts[0].data.borrow_mut())?;
+      Mint::pack(mint, &mut accounts[1].data.borrow_mut())
+    }
+    // CloseAccount
+    9 => {
+      let src = Account::unpack(&accounts[0].data.borrow())?;
+      let authority = src.close_authority.unwrap_or(src.owner);
+      authorize(accounts[2], &authority)?;
+      if !src.is_native() && src.amount != 0 {
+        return Err(ProgramError::InvalidAccountData);
+      }
+      let lamports = accounts[0].lamports();
+      move_lamports(accounts[0], accounts[1], lamports)?;
+      for byte in accounts[0].data.borrow_mut().iter_mut() {
+        *byte = 0;
+      }
+      Ok(())
+    }
+    _ => Err(ProgramError::InvalidInstructionData),
+  }
+}
+
+fn init_token_account(acc: &AccountInfo, mint: &Pubkey, owner: &Pubkey) -> ProgramResult {
+  let mut account = Account::unpack_unchecked(&acc.data.borrow())?;
+  if account.state != AccountState::Uninitialized {
+    return Err(ProgramError::AccountAlreadyInitialized);
+  }
+  account.mint = *mint;
+  account.owner = *owner;
+  account.state = AccountState::Initialized;
+  if *mint == NATIVE_MINT {
+    let reserve = Rent::default().minimum_balance(Account::LEN);
+    account.is_native = COption::Some(reserve);
+    account.amount = acc.lamports().saturating_sub(reserve);
+  }
+  Account::pack(account, &mut acc.data.borrow_mut())
+}
+
+fn fake_splata_program(accounts: &[&AccountInfo]) -> ProgramResult {
+  let (funding_acc, target_acc, owner, mint_acc) =
+    (accounts[0], accounts[1], accounts[2], accounts[3]);
+  let key = associated_token_address(
+    owner.key,
+    mint_acc.key,
+    &splt_program_id(),
+    &splata_program_id(),
+  );
+  if key != *target_acc.key {
+    return Err(ProgramError::InvalidSeeds);
+  }
+  if !funding_acc.is_signer {
+    return Err(ProgramError::MissingRequiredSignature);
+  }
+  let lamports = Rent::default()
+    .minimum_balance(Account::LEN)
+    .saturating_sub(target_acc.lamports());
+  move_lamports(funding_acc, target_acc, lamports)?;
+  alloc(target_acc, Account::LEN)?;
+  assign(target_acc, splt_program_id());
+  init_token_account(target_acc, mint_acc.key, owner.key)
+}
+
+pub fn fake_mint_account(key: Pubkey, authority: Pubkey, supply: u64, decimals: u8) -> FakeAccount {
+  let data = Mint {
+    mint_authority: COption::Some(authority),
+    supply,
+    decimals,
+    is_initialized: true,
+    freeze_authority: COption::None,
+  };
+  FakeAccount::new(key, splt_program_id(), packed(data))
+}
+
+pub fn fake_rent_account() -> FakeAccount {
+  let mut acc = FakeAccount::new(sysvar::rent::id(), sysvar::id(), vec![0; Rent::size_of()]);
+  Rent::default().to_account_info(&mut acc.info()).unwrap();
+  acc
+}
+
+pub fn fake_program_account(key: Pubkey) -> FakeAccount {
+  FakeAccount::new(key, Pubkey::default(), vec![]).readonly()
+}
+
+///
+/// Account metas of an instruction from the specs of its context
+///
+pub fn metas(specs: &[AccountSpec], keys: &[Pubkey]) -> Vec<AccountMeta> {
+  specs
+    .iter()
+    .zip(keys)
+    .map(|(spec, key)| AccountMeta {
+      pubkey: *key,
+      is_signer: spec.is_signer(),
+      is_writable: spec.is_writable(),
+    })
+    .collect()
+}
+
+///
+/// Fake ledger
+/// Runs instructions through the processor and keeps the accounts of successful ones,
+/// a failed instruction leaves every account untouched as a failed transaction would
+///
+#[derive(Clone, Debug, Default)]
+pub struct Bank {
+  pub accounts: BTreeMap<Pubkey, FakeAccount>,
+}
+
+impl Bank {
+  pub fn new() -> Self {
+    install_stubs();
+    let mut bank = Bank::default();
+    bank.insert(fake_rent_account());
+    for key in [
+      system_program::id(),
+      splt_program_id(),
+      splata_program_id(),
+      memo_program_id(),
+    ]
+    .iter()
+    {
+      bank.insert(fake_program_account(*key));
+    }
+    bank
+  }
+
+  pub fn insert(&mut self, acc: FakeAccount) {
+    self.accounts.insert(acc.key, acc);
+  }
+
+  pub fn data(&self, key: &Pubkey) -> &[u8] {
+    self
+      .accounts
+      .get(key)
+      .map(|acc| &acc.data[..])
+      .unwrap_or(&[])
+  }
+
+  pub fn unpack<T: Pack + solana_program::program_pack::IsInitialized>(&self, key: &Pubkey) -> T {
+    T::unpack(self.data(key)).unwrap()
+  }
+
+  pub fn token_amount(&self, key: &Pubkey) -> u64 {
+    self.unpack::<Account>(key).amount
+  }
+
+  pub fn lamports(&self, key: &Pubkey) -> u64 {
+    self.accounts.get(key).map(|acc| acc.lamports).unwrap_or(0)
+  }
+
+  pub fn process(&mut self, ix: &Instruction) -> ProgramResult {
+    install_stubs();
+    PROGRAM_ID.with(|program_id| program_id.set(ix.program_id));
+    ASSIGNED.with(|assigned| assigned.borrow_mut().clear());
+    // One copy per key, flagged as the strongest meta asks
+    let mut working: Vec<FakeAccount> = vec![];
+    for meta in &ix.accounts {
+      match working.iter_mut().find(|acc| acc.key == meta.pubkey) {
+        Some(acc) => {
+          acc.is_signer |= meta.is_signer;
+          acc.is_writable |= meta.is_writable;
+        }
+        None => {
+          let mut acc = self.accounts.get(&meta.pubkey).cloned().unwrap_or_else(|| {
+            let mut acc = FakeAccount::new(meta.pubkey, system_program::id(), vec![]);
+            acc.lamports = 0;
+            acc
+          });
+          acc.is_signer = meta.is_signer;
+          acc.is_writable = meta.is_writable;
+          working.push(acc);
+        }
+      }
+    }
+    let before = working.clone();
+    let unique: Vec<AccountInfo> = working.iter_mut().map(|acc| acc.info()).collect();
+    let infos: Vec<AccountInfo> = ix
+      .accounts
+      .iter()
+      .map(|meta| {
+        unique
+          .iter()
+          .find(|info| *info.key == meta.pubkey)
+          .unwrap()
+          .clone()
+      })
+      .collect();
+    let result = Processor::process(&ix.program_id, &infos, &ix.data);
+    let after: Vec<(u64, Vec<u8>)> = unique
+      .iter()
+      .map(|info| (info.lamports(), info.data.borrow().to_vec()))
+      .collect();
+    drop(infos);
+    drop(unique);
+    result?;
+    // The runtime rejects writes to accounts passed readonly
+    for (acc, (lamports, data)) in before.iter().zip(&after) {
+      if !acc.is_writable && (acc.lamports != *lamports || acc.data != *data) {
+        return Err(ProgramError::InvalidAccountData);
+      }
+    }
+    let assigned = ASSIGNED.with(|assigned| std::mem::take(&mut *assigned.borrow_mut()));
+    for (acc, (lamports, data)) in before.into_iter().zip(after) {
+      let owner = assigned.get(&acc.key).copied().unwrap_or(acc.owner);
+      self.insert(FakeAccount {
+        lamports,
+        data,
+        owner,
+        is_signer: false,
+        is_writable: true,
+        ..acc
+      });
+    }
+    Ok(())
+  }
+}
+
+///
+/// Wallet of a staker with its token, share, SEN and debt accounts
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Staker {
+  pub wallet: Pubkey,
+  pub token_acc: Pubkey,
+  pub share_acc: Pubkey,
+  pub sen_acc: Pubkey,
+  pub debt_acc: Pubkey,
+}
+
+///
+/// Seeder of a pool with its SEN account and contribution ledger
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Contributor {
+  pub wallet: Pubkey,
+  pub sen_acc: Pubkey,
+  pub contribution_acc: Pubkey,
+}
+
+///
+/// Stake pool created through InitializeStakePool on a fresh bank
+///
+pub struct PoolFixture {
+  pub bank: Bank,
+  pub program_id: Pubkey,
+  pub payer: Pubkey,
+  pub owner: Pubkey,
+  pub owner_sen_acc: Pubkey, // Funded to seed the pool
+  pub stake_pool: Pubkey,
+  pub treasurer: Pubkey,
+  pub mint_share: Pubkey,
+  pub mint_token: Pubkey,
+  pub mint_sen: Pubkey,
+  pub treasury_token: Pubkey,
+  pub treasury_sen: Pubkey,
+}
+
+///
+/// Clock of the fixtures at creation
+///
+pub const GENESIS: i64 = 1600000000;
+
+impl PoolFixture {
+  pub fn new(reward: u64, period: u64) -> Self {
+    let mut fixture = Self::uninitialized();
+    fixture.initialize(reward, period, &[]).unwrap();
+    fixture
+  }
+
+  ///
+  /// Accounts of a pool the client prepared but did not initialize yet
+  ///
+  pub fn uninitialized() -> Self {
+    Self::uninitialized_with_reward_mint(Pubkey::new_unique())
+  }
+
+  ///
+  /// Accounts of an uninitialized pool rewarding in the given mint, such as wrapped SOL
+  ///
+  pub fn uninitialized_with_reward_mint(mint_sen: Pubkey) -> Self {
+    warp_to(GENESIS);
+    let mut bank = Bank::new();
+    let program_id = Pubkey::new_unique();
+    // Half of the keys cannot derive a treasurer
+    let (stake_pool, treasurer) = loop {
+      let stake_pool = Pubkey::new_unique();
+      if let Ok(treasurer) = Pubkey::create_program_address(&[&stake_pool.to_bytes()], &program_id)
+      {
+        break (stake_pool, treasurer);
+      }
+    };
+    let payer = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mint_share = Pubkey::new_unique();
+    let mint_token = Pubkey::new_unique();
+    let mint_authority = Pubkey::new_unique();
+    let treasury_token = associated_token_address(
+      &treasurer,
+      &mint_token,
+      &splt_program_id(),
+      &splata_program_id(),
+    );
+    let treasury_sen = associated_token_address(
+      &treasurer,
+      &mint_sen,
+      &splt_program_id(),
+      &splata_program_id(),
+    );
+    let owner_sen_acc = Pubkey::new_unique();
+    let mut payer_acc = FakeAccount::new(payer, system_program::id(), vec![]);
+    payer_acc.lamports = 1_000_000_000_000;
+    bank.insert(payer_acc);
+    bank.insert(FakeAccount::new(owner, system_program::id(), vec![]));
+    // Pre-created by the client unless prepared otherwise
+    let mut stake_pool_acc = FakeAccount::new(stake_pool, program_id, vec![0; StakePool::LEN]);
+    stake_pool_acc.lamports = Rent::default().minimum_balance(StakePool::LEN);
+    bank.insert(stake_pool_acc);
+    bank.insert(FakeAccount::new(
+      mint_share,
+      splt_program_id(),
+      vec![0; Mint::LEN],
+    ));
+    bank.insert(fake_mint_account(mint_token, mint_authority, 0, 6));
+    bank.insert(fake_mint_account(mint_sen, mint_authority, u64::MAX / 2, 6));
+    bank.insert(fake_token_account(
+      owner_sen_acc,
+      splt_program_id(),
+      mint_sen,
+      owner,
+      u64::MAX / 2,
+    ));
+    PoolFixture {
+      bank,
+      program_id,
+      payer,
+      owner,
+      owner_sen_acc,
+      stake_pool,
+      treasurer,
+      mint_share,
+      mint_token,
+      mint_sen,
+      treasury_token,
+      treasury_sen,
+    }
+  }
+
+  ///
+  /// Initialize the pool, trailing keys such as the registry's are passed writable
+  ///
+  pub fn initialize(&mut self, reward: u64, period: u64, extra: &[Pubkey]) -> ProgramResult {
+    let proof = self.program_id.xor(&self.stake_pool.xor(&self.treasurer));
+    let ix = AppInstruction::InitializeStakePool {
+      reward,
+      period,
+      genesis_timestamp: 0,
+      allow_pre_stake: false,
+      pool_seed: None,
+      precision_exp: crate::PRECISION_EXP,
+    };
+    let mut metas = metas(
+      InitializeStakePoolContext::SPECS,
+      &[
+        self.payer,
+        self.owner,
+        self.stake_pool,
+        self.mint_share,
+        proof,
+        self.mint_token,
+        self.treasury_token,
+        self.mint_sen,
+        self.treasury_sen,
+        self.treasurer,
+        system_program::id(),
+        splt_program_id(),
+        sysvar::rent::id(),
+        splata_program_id(),
+      ],
+    );
+    metas[2].is_signer = true;
+    for key in extra {
+      metas.push(AccountMeta::new(*key, false));
+    }
+    self.send_metas(&ix, metas)
+  }
+
+  pub fn send_metas(&mut self, ix: &AppInstruction, metas: Vec<AccountMeta>) -> ProgramResult {
+    self.send_data(ix.pack(), metas)
+  }
+
+  pub fn send_data(&mut self, data: Vec<u8>, metas: Vec<AccountMeta>) -> ProgramResult {
+    let _ = take_logs();
+    self.bank.process(&Instruction {
+      program_id: self.program_id,
+      accounts: metas,
+      data,
+    })
+  }
+
+  ///
+  /// Send with the fixed accounts flagged as the context declares,
+  /// keys beyond the context are passed readonly
+  ///
+  pub fn send(&mut self, ix: &AppInstruction, keys: &[Pubkey]) -> ProgramResult {
+    let mut metas = metas(account_specs(ix), keys);
+    for key in keys.iter().skip(metas.len()) {
+      metas.push(AccountMeta::new_readonly(*key, false));
+    }
+    self.send_metas(ix, metas)
+  }
+
+  ///
+  /// Owner instruction that only touches the stake pool
+  ///
+  pub fn send_owner(&mut self, ix: &AppInstruction) -> ProgramResult {
+    let keys = [self.owner, self.stake_pool];
+    self.send_metas(ix, metas(StakePoolOwnerContext::SPECS, &keys))
+  }
+
+  pub fn stake_pool_data(&self) -> StakePool {
+    self.bank.unpack(&self.stake_pool)
+  }
+
+  pub fn debt_data(&self, staker: &Staker) -> Debt {
+    self.bank.unpack(&staker.debt_acc)
+  }
+
+  ///
+  /// Rewrite the stake pool in place, for states no instruction reaches directly
+  ///
+  pub fn update_stake_pool<F: FnOnce(&mut StakePool)>(&mut self, f: F) {
+    let mut data = self.stake_pool_data();
+    f(&mut data);
+    let acc = self.bank.accounts.get_mut(&self.stake_pool).unwrap();
+    StakePool::pack(data, &mut acc.data).unwrap();
+  }
+
+  pub fn update_debt<F: FnOnce(&mut Debt)>(&mut self, staker: &Staker, f: F) {
+    let mut data = self.debt_data(staker);
+    f(&mut data);
+    let acc = self.bank.accounts.get_mut(&staker.debt_acc).unwrap();
+    Debt::pack(data, &mut acc.data).unwrap();
+  }
+
+  pub fn update_mint<F: FnOnce(&mut Mint)>(&mut self, key: &Pubkey, f: F) {
+    let mut data: Mint = self.bank.unpack(key);
+    f(&mut data);
+    let acc = self.bank.accounts.get_mut(key).unwrap();
+    Mint::pack(data, &mut acc.data).unwrap();
+  }
+
+  ///
+  /// Token account of the given mint and owner out of thin air
+  ///
+  pub fn add_token_account(&mut self, mint: Pubkey, owner: Pubkey, amount: u64) -> Pubkey {
+    let key = Pubkey::new_unique();
+    self.bank.insert(fake_token_account(
+      key,
+      splt_program_id(),
+      mint,
+      owner,
+      amount,
+    ));
+    key
+  }
+
+  ///
+  /// Staker holding the given stake tokens, its accounts initialized through the program
+  ///
+  pub fn add_staker(&mut self, tokens: u64) -> Staker {
+    let staker = self.new_staker(tokens);
+    self.initialize_accounts(&staker).unwrap();
+    staker
+  }
+
+  ///
+  /// Wallet and token account of a staker whose accounts are not initialized yet
+  ///
+  pub fn new_staker(&mut self, tokens: u64) -> Staker {
+    let wallet = Pubkey::new_unique();
+    let mut wallet_acc = FakeAccount::new(wallet, system_program::id(), vec![]);
+    wallet_acc.lamports = 1_000_000_000;
+    self.bank.insert(wallet_acc);
+    let token_acc = self.add_token_account(self.mint_token, wallet, tokens);
+    let share_acc = associated_token_address(
+      &wallet,
+      &self.mint_share,
+      &splt_program_id(),
+      &splata_program_id(),
+    );
+    let sen_acc = associated_token_address(
+      &wallet,
+      &self.mint_sen,
+      &splt_program_id(),
+      &splata_program_id(),
+    );
+    let (debt_acc, _) = Pubkey::find_program_address(
+      &[
+        &wallet.to_bytes(),
+        &self.stake_pool.to_bytes(),
+        &self.program_id.to_bytes(),
+      ],
+      &self.program_id,
+    );
+    Staker {
+      wallet,
+      token_acc,
+      share_acc,
+      sen_acc,
+      debt_acc,
+    }
+  }
+
+  pub fn initialize_accounts(&mut self, staker: &Staker) -> ProgramResult {
+    let keys = [
+      self.payer,
+      staker.wallet,
+      self.stake_pool,
+      self.mint_share,
+      self.mint_sen,
+      staker.sen_acc,
+      staker.share_acc,
+      staker.debt_acc,
+      system_program::id(),
+      splt_program_id(),
+      sysvar::rent::id(),
+      splata_program_id(),
+    ];
+    let ix = AppInstruction::InitializeAccounts;
+    self.send_metas(&ix, metas(InitializeAccountsContext::SPECS, &keys))
+  }
+
+  pub fn stake_keys(&self, staker: &Staker) -> Vec<Pubkey> {
+    vec![
+      staker.wallet,
+      self.stake_pool,
+      self.mint_share,
+      staker.token_acc,
+      self.treasury_token,
+      staker.share_acc,
+      staker.debt_acc,
+      staker.sen_acc,
+      self.treasury_sen,
+      self.treasurer,
+      splt_program_id(),
+      self.mint_token,
+    ]
+  }
+
+  pub fn stake(&mut self, staker: &Staker, amount: u64) -> ProgramResult {
+    let ix = AppInstruction::Stake {
+      amount,
+      reference: None,
+      idempotency_key: None,
+    };
+    let keys = self.stake_keys(staker);
+    self.send_metas(&ix, metas(StakeContext::SPECS, &keys))
+  }
+
+  pub fn unstake_keys(&self, staker: &Staker) -> Vec<Pubkey> {
+    vec![
+      staker.wallet,
+      self.stake_pool,
+      self.mint_share,
+      staker.token_acc,
+      self.treasury_token,
+      staker.share_acc,
+      staker.debt_acc,
+      staker.sen_acc,
+      self.treasury_sen,
+      self.treasurer,
+      splt_program_id(),
+    ]
+  }
+
+  pub fn unstake(&mut self, staker: &Staker, amount: u64) -> ProgramResult {
+    let ix = AppInstruction::Unstake {
+      amount,
+      reference: None,
+      idempotency_key: None,
+    };
+    let keys = self.unstake_keys(staker);
+    self.send_metas(&ix, metas(UnstakeContext::SPECS, &keys))
+  }
+
+  pub fn harvest_keys(&self, staker: &Staker) -> Vec<Pubkey> {
+    vec![
+      staker.wallet,
+      self.stake_pool,
+      self.mint_share,
+      staker.share_acc,
+      staker.debt_acc,
+      staker.sen_acc,
+      self.treasury_sen,
+      self.treasurer,
+      splt_program_id(),
+    ]
+  }
+
+  ///
+  /// Harvest signed by the staker to its own SEN account
+  ///
+  pub fn harvest(&mut self, staker: &Staker) -> ProgramResult {
+    let ix = AppInstruction::Harvest {
+      memo: false,
+      idempotency_key: None,
+    };
+    let keys = self.harvest_keys(staker);
+    let mut metas = metas(HarvestContext::SPECS, &keys);
+    metas[0].is_signer = true;
+    self.send_metas(&ix, metas)
+  }
+
+  ///
+  /// Yield the staker would harvest now, as logged by GetClaimable
+  ///
+  pub fn claimable(&mut self, staker: &Staker) -> u64 {
+    let keys = [self.stake_pool, staker.share_acc, staker.debt_acc];
+    self.send(&AppInstruction::GetClaimable, &keys).unwrap();
+    let log = take_logs()
+      .into_iter()
+      .find(|log| log.starts_with("CLAIMABLE:"))
+      .unwrap();
+    log["CLAIMABLE:".len()..].parse().unwrap()
+  }
+
+  pub fn claim_vested(&mut self, staker: &Staker) -> ProgramResult {
+    let keys = [
+      staker.wallet,
+      self.stake_pool,
+      staker.debt_acc,
+      staker.sen_acc,
+      self.treasury_sen,
+      self.treasurer,
+      splt_program_id(),
+    ];
+    let ix = AppInstruction::ClaimVested;
+    self.send_metas(&ix, metas(ClaimVestedContext::SPECS, &keys))
+  }
+
+  ///
+  /// Seed from the owner's SEN account
+  ///
+  pub fn seed(&mut self, amount: u64) -> ProgramResult {
+    let keys = [
+      self.owner,
+      self.stake_pool,
+      self.owner_sen_acc,
+      self.treasury_sen,
+      splt_program_id(),
+    ];
+    let ix = AppInstruction::Seed { amount };
+    self.send_metas(&ix, metas(SeedContext::SPECS, &keys))
+  }
+
+  pub fn unseed(&mut self, amount: u64, dst_sen_acc: Pubkey) -> ProgramResult {
+    let keys = [
+      self.owner,
+      self.stake_pool,
+      dst_sen_acc,
+      self.treasury_sen,
+      self.treasurer,
+      splt_program_id(),
+    ];
+    let ix = AppInstruction::Unseed { amount };
+    self.send_metas(&ix, metas(UnseedContext::SPECS, &keys))
+  }
+
+  ///
+  /// Contributor that seeded the given amount through its ledger
+  ///
+  pub fn add_contributor(&mut self, amount: u64) -> Contributor {
+    let wallet = Pubkey::new_unique();
+    self
+      .bank
+      .insert(FakeAccount::new(wallet, system_program::id(), vec![]));
+    let sen_acc = self.add_token_account(self.mint_sen, wallet, amount);
+    let (contribution_acc, _) = Pubkey::find_program_address(
+      &[
+        &wallet.to_bytes(),
+        &self.stake_pool.to_bytes(),
+        b"contribution",
+      ],
+      &self.program_id,
+    );
+    let keys = [
+      self.payer,
+      wallet,
+      self.stake_pool,
+      contribution_acc,
+      system_program::id(),
+      sysvar::rent::id(),
+    ];
+    let ix = AppInstruction::InitializeContribution;
+    self
+      .send_metas(&ix, metas(InitializeContributionContext::SPECS, &keys))
+      .unwrap();
+    let keys = [
+      wallet,
+      self.stake_pool,
+      sen_acc,
+      self.treasury_sen,
+      splt_program_id(),
+    ];
+    let mut metas = metas(SeedContext::SPECS, &keys);
+    metas.push(AccountMeta::new(contribution_acc, false));
+    self
+      .send_metas(&AppInstruction::Seed { amount }, metas)
+      .unwrap();
+    Contributor {
+      wallet,
+      sen_acc,
+      contribution_acc,
+    }
+  }
+
+  pub fn withdraw_seed(
+    &mut self,
+    contributor: &Contributor,
+    amount: u64,
+    dst_sen_acc: Pubkey,
+  ) -> ProgramResult {
+    let keys = [
+      contributor.wallet,
+      self.stake_pool,
+      contributor.contribution_acc,
+      dst_sen_acc,
+      self.treasury_sen,
+      self.treasurer,
+      splt_program_id(),
+    ];
+    let ix = AppInstruction::WithdrawSeed { amount };
+    self.send_metas(&ix, metas(WithdrawSeedContext::SPECS, &keys))
+  }
+}
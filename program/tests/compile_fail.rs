@@ -0,0 +1,9 @@
+//!
+//! Mistakes the type system must reject, checked with trybuild
+//!
+
+#[test]
+fn ui() {
+  let cases = trybuild::TestCases::new();
+  cases.compile_fail("tests/ui/*.rs");
+}
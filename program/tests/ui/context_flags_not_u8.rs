@@ -0,0 +1,7 @@
+use main::context;
+
+context!(TransferContext {
+  owner: "signer",
+});
+
+fn main() {}
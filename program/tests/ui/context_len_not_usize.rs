@@ -0,0 +1,8 @@
+use main::context;
+use main::context::PROGRAM;
+
+context!(TransferContext {
+  stake_pool_acc: PROGRAM => -1i64,
+});
+
+fn main() {}
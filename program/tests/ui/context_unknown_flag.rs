@@ -0,0 +1,9 @@
+use main::context;
+use main::context::SIGNER;
+
+context!(TransferContext {
+  owner: SIGNER,
+  stake_pool_acc: PROGAM,
+});
+
+fn main() {}
@@ -0,0 +1,8 @@
+use main::context::StakePoolOwnerContext;
+
+fn owner_of<'a, 'b>(context: StakePoolOwnerContext<'a, 'b>) {
+  let StakePoolOwnerContext { owner, treasurer, .. } = context;
+  let _ = (owner, treasurer);
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use main::helper::units::{ScaledDebt, TokenAmount};
+
+fn main() {
+  let debt = ScaledDebt(5);
+  let _: TokenAmount = debt;
+}
@@ -0,0 +1,5 @@
+use main::helper::units::{Shares, TokenAmount};
+
+fn main() {
+  let _ = Shares(1000).checked_add(TokenAmount(1000));
+}
@@ -0,0 +1,7 @@
+use main::helper::pattern::Pattern;
+use main::helper::units::{ScaledAmount, TokenAmount};
+
+fn main() {
+  let amount = TokenAmount(1000);
+  let _ = Pattern::accrue(amount, ScaledAmount(1), 1);
+}